@@ -0,0 +1,367 @@
+//! Debug Adapter Protocol server running one script, see
+//! <https://microsoft.github.io/debug-adapter-protocol/specification>
+
+use std::cell::RefCell;
+use std::io::{self, BufRead, Write};
+use std::rc::Rc;
+use std::{fs, mem};
+
+use fox_core::engine::debug::{
+    Breakpoint, Command, Debugger, Frontend, LineIndex, Pause, PauseReason,
+};
+use fox_core::engine::error::Error as RuntimeError;
+use fox_core::engine::interrupt::InterruptHandle;
+use serde_json::{json, Value as Json};
+
+use crate::{message, Error, Fox};
+
+const THREAD_ID: i64 = 1;
+const STACK_REFERENCE: i64 = 1;
+const LOCALS_REFERENCE: i64 = 2;
+
+/// Serve a client until it disconnects. Requests are read from `input` and responses and
+/// events written to `output`, usually stdin and stdout.
+pub fn serve(input: impl BufRead + 'static, output: impl Write + 'static) -> Result<(), Error> {
+    let transport = Rc::new(RefCell::new(Transport {
+        input: Box::new(input),
+        output: Box::new(output),
+        seq: 0,
+        disconnected: false,
+    }));
+
+    let Some(launch) = configure(&transport)? else {
+        return Ok(());
+    };
+    let source = match fs::read_to_string(&launch.program) {
+        Ok(source) => source,
+        Err(e) => {
+            let mut t = transport.borrow_mut();
+            t.event(
+                "output",
+                json!({ "category": "stderr", "output": format!("{e}\n") }),
+            )?;
+            t.event("terminated", json!({}))?;
+            return drain(&mut t);
+        }
+    };
+
+    let mut fox = Fox::new();
+    let frontend = Client {
+        transport: transport.clone(),
+        path: launch.program.clone(),
+        lines: LineIndex::new(&source),
+        interrupt: fox.interrupt_handle(),
+        entry: launch.stop_on_entry,
+    };
+    let mut debugger = Debugger::new(&source, frontend);
+    for line in launch.lines {
+        debugger = debugger.break_at(Breakpoint::Line(line));
+    }
+    if !launch.stop_on_entry {
+        debugger = debugger.run_to_breakpoint();
+    }
+
+    fox.set_output(OutputEvents(transport.clone(), "stdout"));
+    fox.set_error_output(OutputEvents(transport.clone(), "stderr"));
+    fox.set_input(io::empty());
+    fox.set_trace(debugger.into_hook());
+
+    let result = fox.load_str(&source);
+    let mut t = transport.borrow_mut();
+    let exit_code = match result {
+        Ok(()) | Err(Error::Runtime(RuntimeError::Interrupted)) => 0,
+        Err(e) => {
+            t.event(
+                "output",
+                json!({ "category": "stderr", "output": format!("{e}\n") }),
+            )?;
+            1
+        }
+    };
+    t.event("exited", json!({ "exitCode": exit_code }))?;
+    t.event("terminated", json!({}))?;
+    drain(&mut t)
+}
+
+struct Launch {
+    program: String,
+    stop_on_entry: bool,
+    lines: Vec<usize>,
+}
+
+/// Handle requests until `configurationDone`, `None` if the client left before
+fn configure(transport: &Rc<RefCell<Transport>>) -> Result<Option<Launch>, Error> {
+    let mut t = transport.borrow_mut();
+    let mut launch = Launch {
+        program: String::new(),
+        stop_on_entry: false,
+        lines: vec![],
+    };
+
+    while let Some(request) = t.read()? {
+        let args = &request["arguments"];
+        match request["command"].as_str().unwrap_or_default() {
+            "initialize" => {
+                t.respond(
+                    &request,
+                    json!({ "supportsConfigurationDoneRequest": true }),
+                )?;
+                t.event("initialized", json!({}))?;
+            }
+            "launch" => {
+                launch.program = args["program"].as_str().unwrap_or_default().to_string();
+                launch.stop_on_entry = args["stopOnEntry"].as_bool().unwrap_or(false);
+                t.respond(&request, json!({}))?;
+            }
+            "setBreakpoints" => {
+                launch.lines = breakpoint_lines(args);
+                t.respond(&request, verified(&launch.lines))?;
+            }
+            "threads" => t.respond(&request, threads())?,
+            "configurationDone" => {
+                t.respond(&request, json!({}))?;
+                return Ok(Some(launch));
+            }
+            "disconnect" => {
+                t.respond(&request, json!({}))?;
+                return Ok(None);
+            }
+            _ => t.respond(&request, json!({}))?,
+        }
+    }
+    Ok(None)
+}
+
+/// Answer the requests left once the script ended, until the client disconnects
+fn drain(t: &mut Transport) -> Result<(), Error> {
+    if t.disconnected {
+        return Ok(());
+    }
+    while let Some(request) = t.read()? {
+        t.respond(&request, json!({}))?;
+        if request["command"] == "disconnect" {
+            break;
+        }
+    }
+    Ok(())
+}
+
+fn breakpoint_lines(args: &Json) -> Vec<usize> {
+    args["breakpoints"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|b| b["line"].as_u64())
+        .map(|line| line as usize)
+        .collect()
+}
+
+fn verified(lines: &[usize]) -> Json {
+    let breakpoints: Vec<Json> = lines
+        .iter()
+        .map(|line| json!({ "verified": true, "line": line }))
+        .collect();
+    json!({ "breakpoints": breakpoints })
+}
+
+fn threads() -> Json {
+    json!({ "threads": [{ "id": THREAD_ID, "name": "main" }] })
+}
+
+/// Connection to the client
+struct Transport {
+    input: Box<dyn BufRead>,
+    output: Box<dyn Write>,
+    seq: i64,
+    disconnected: bool,
+}
+
+impl Transport {
+    fn read(&mut self) -> io::Result<Option<Json>> {
+        message::read(&mut self.input)
+    }
+
+    fn send(&mut self, mut message: Json) -> io::Result<()> {
+        self.seq += 1;
+        message["seq"] = json!(self.seq);
+        message::write(&mut self.output, &message)
+    }
+
+    fn respond(&mut self, request: &Json, body: Json) -> io::Result<()> {
+        self.send(json!({
+            "type": "response",
+            "request_seq": request["seq"],
+            "command": request["command"],
+            "success": true,
+            "body": body,
+        }))
+    }
+
+    fn event(&mut self, event: &str, body: Json) -> io::Result<()> {
+        self.send(json!({ "type": "event", "event": event, "body": body }))
+    }
+}
+
+/// Script output, sent to the client as `output` events
+struct OutputEvents(Rc<RefCell<Transport>>, &'static str);
+
+impl Write for OutputEvents {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let output = String::from_utf8_lossy(buf);
+        let body = json!({ "category": self.1, "output": output });
+        self.0.borrow_mut().event("output", body)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Answers the client while the script is paused
+struct Client {
+    transport: Rc<RefCell<Transport>>,
+    path: String,
+    lines: LineIndex,
+    interrupt: InterruptHandle,
+    /// Whether the first pause is the one requested by `stopOnEntry`
+    entry: bool,
+}
+
+impl Client {
+    /// Innermost first, each frame with the line it is at
+    fn stack_frames(&self, pause: &Pause) -> Json {
+        let calls = pause.trace.calls;
+        let mut position = pause.trace.position.clone();
+        let mut frames = vec![];
+        for depth in (0..=calls.len()).rev() {
+            let name = match depth {
+                0 => "main",
+                _ => &calls[depth - 1].name,
+            };
+            let (line, column) = position
+                .as_ref()
+                .map_or((0, 0), |span| self.lines.line_col(span.start));
+            frames.push(json!({
+                "id": calls.len() - depth,
+                "name": name,
+                "line": line,
+                "column": column,
+                "source": { "path": self.path },
+            }));
+            if depth > 0 {
+                position = calls[depth - 1].call_site.clone();
+            }
+        }
+        json!({ "stackFrames": frames, "totalFrames": frames.len() })
+    }
+
+    fn variables(&self, pause: &Pause, reference: i64) -> Json {
+        let variables: Vec<Json> = match reference {
+            STACK_REFERENCE => pause
+                .trace
+                .stack
+                .values()
+                .iter()
+                .rev()
+                .enumerate()
+                .map(|(i, v)| variable(&i.to_string(), v.data_ref().repr()))
+                .collect(),
+            LOCALS_REFERENCE => pause
+                .trace
+                .locals
+                .entries()
+                .into_iter()
+                .map(|(name, v)| variable(name, v.data_ref().repr()))
+                .collect(),
+            _ => vec![],
+        };
+        json!({ "variables": variables })
+    }
+}
+
+fn variable(name: &str, value: String) -> Json {
+    json!({ "name": name, "value": value, "variablesReference": 0 })
+}
+
+impl Frontend for Client {
+    fn paused(&mut self, pause: &Pause, breakpoints: &mut Vec<Breakpoint>) -> Command {
+        let reason = match &pause.reason {
+            PauseReason::Step if mem::take(&mut self.entry) => "entry",
+            PauseReason::Step => "step",
+            PauseReason::Breakpoint(_) => "breakpoint",
+        };
+
+        let transport = self.transport.clone();
+        let mut t = transport.borrow_mut();
+        let stopped = json!({ "reason": reason, "threadId": THREAD_ID });
+        if t.event("stopped", stopped).is_err() {
+            self.interrupt.interrupt();
+            return Command::Continue;
+        }
+
+        loop {
+            let request = match t.read() {
+                Ok(Some(request)) => request,
+                // The client is gone, stop the script
+                _ => {
+                    self.interrupt.interrupt();
+                    return Command::Continue;
+                }
+            };
+
+            let args = &request["arguments"];
+            let (body, command) = match request["command"].as_str().unwrap_or_default() {
+                "threads" => (threads(), None),
+                "stackTrace" => (self.stack_frames(pause), None),
+                "scopes" => {
+                    let mut scopes = vec![json!({
+                        "name": "Stack",
+                        "variablesReference": STACK_REFERENCE,
+                        "expensive": false,
+                    })];
+                    // Locals are only known for the innermost frame
+                    if args["frameId"].as_i64() == Some(0) {
+                        scopes.push(json!({
+                            "name": "Locals",
+                            "variablesReference": LOCALS_REFERENCE,
+                            "expensive": false,
+                        }));
+                    }
+                    (json!({ "scopes": scopes }), None)
+                }
+                "variables" => {
+                    let reference = args["variablesReference"].as_i64().unwrap_or_default();
+                    (self.variables(pause, reference), None)
+                }
+                "setBreakpoints" => {
+                    let lines = breakpoint_lines(args);
+                    breakpoints.retain(|b| !matches!(b, Breakpoint::Line(_)));
+                    breakpoints.extend(lines.iter().copied().map(Breakpoint::Line));
+                    (verified(&lines), None)
+                }
+                "continue" => (
+                    json!({ "allThreadsContinued": true }),
+                    Some(Command::Continue),
+                ),
+                "next" => (json!({}), Some(Command::StepOver)),
+                "stepIn" => (json!({}), Some(Command::StepIn)),
+                "stepOut" => (json!({}), Some(Command::StepOut)),
+                "disconnect" | "terminate" => {
+                    t.disconnected = true;
+                    self.interrupt.interrupt();
+                    (json!({}), Some(Command::Continue))
+                }
+                _ => (json!({}), None),
+            };
+
+            if t.respond(&request, body).is_err() {
+                self.interrupt.interrupt();
+                return Command::Continue;
+            }
+            if let Some(command) = command {
+                return command;
+            }
+        }
+    }
+}
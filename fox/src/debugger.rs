@@ -0,0 +1,120 @@
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Write};
+
+use fox_core::engine::debug::{Breakpoint, Command, Frontend, Pause, PauseReason};
+
+const HELP: &str = "\
+s, step       step into the next expression
+n, next       step over words
+o, out        run until the current word returns
+c, continue   run until a breakpoint
+b <word|line> add a breakpoint
+d <word|line> remove a breakpoint
+breaks        list breakpoints
+p, stack      print the stack
+l, locals     print the locals
+w, where      print the current line
+q, quit       stop the script";
+
+#[cfg(unix)]
+const TERMINAL: &str = "/dev/tty";
+#[cfg(windows)]
+const TERMINAL: &str = "CONIN$";
+
+/// Debugger frontend reading commands from the terminal, leaving stdin to the script
+pub struct Console {
+    lines: Vec<String>,
+    input: Box<dyn BufRead>,
+}
+
+impl Console {
+    /// Fails without a terminal, such as when run by another program
+    pub fn new(source: &str) -> io::Result<Self> {
+        let terminal = File::open(TERMINAL).map_err(|e| {
+            io::Error::new(
+                e.kind(),
+                format!("the debugger reads its commands from a terminal: {e}"),
+            )
+        })?;
+        Ok(Console {
+            lines: source.lines().map(str::to_string).collect(),
+            input: Box::new(BufReader::new(terminal)),
+        })
+    }
+
+    fn print_where(&self, pause: &Pause) {
+        match pause.line {
+            Some(line) => {
+                let text = self.lines.get(line - 1).map_or("", |l| l.trim());
+                println!("{line:>4} | {text}");
+            }
+            None => println!("   ? |"),
+        }
+    }
+}
+
+/// A number is a line, anything else a word
+pub fn parse_breakpoint(s: &str) -> Breakpoint {
+    match s.parse() {
+        Ok(line) => Breakpoint::Line(line),
+        Err(_) => Breakpoint::Word(s.to_string()),
+    }
+}
+
+impl Frontend for Console {
+    fn paused(&mut self, pause: &Pause, breakpoints: &mut Vec<Breakpoint>) -> Command {
+        if let PauseReason::Breakpoint(b) = &pause.reason {
+            println!("breakpoint {b:?}");
+        }
+        self.print_where(pause);
+        println!(
+            "  > {}  {}",
+            pause.trace.expr.repr(),
+            pause.trace.stack.repr()
+        );
+
+        loop {
+            print!("(fox) ");
+            let _ = io::stdout().flush();
+
+            let mut line = String::new();
+            // Without input left, let the script finish
+            if self.input.read_line(&mut line).unwrap_or(0) == 0 {
+                breakpoints.clear();
+                return Command::Continue;
+            }
+
+            let mut words = line.split_whitespace();
+            match (words.next(), words.next()) {
+                (Some("s" | "step"), _) => return Command::StepIn,
+                (Some("n" | "next"), _) => return Command::StepOver,
+                (Some("o" | "out"), _) => return Command::StepOut,
+                (Some("c" | "continue"), _) => return Command::Continue,
+                (Some("b"), Some(b)) => breakpoints.push(parse_breakpoint(b)),
+                (Some("d"), Some(b)) => {
+                    let b = parse_breakpoint(b);
+                    breakpoints.retain(|other| *other != b);
+                }
+                (Some("breaks"), _) => {
+                    for b in breakpoints.iter() {
+                        println!("{b:?}");
+                    }
+                }
+                (Some("p" | "stack"), _) => {
+                    for (i, v) in pause.trace.stack.values().iter().rev().enumerate() {
+                        println!("{i:>4}: {}", v.data_ref().repr());
+                    }
+                }
+                (Some("l" | "locals"), _) => {
+                    for (name, v) in pause.trace.locals.entries() {
+                        println!("{name} = {}", v.data_ref().repr());
+                    }
+                }
+                (Some("w" | "where"), _) => self.print_where(pause),
+                (Some("q" | "quit"), _) => return Command::Quit,
+                (None, _) => {}
+                _ => println!("{HELP}"),
+            }
+        }
+    }
+}
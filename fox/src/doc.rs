@@ -0,0 +1,247 @@
+//! Reference pages of `.fox` files, listing their words and types with the `##` comments
+//! written above them
+
+use fox_core::engine::lint::Natives;
+use fox_core::engine::typing::{Signatures, StackEffect};
+use fox_core::parsing::parser::{Ast, Definition, TypeDefinition};
+
+/// Definitions of a file, named after it
+pub struct Module {
+    pub name: String,
+    pub types: Vec<TypeDoc>,
+    pub words: Vec<Word>,
+}
+
+pub struct TypeDoc {
+    /// Source of the definition, such as `type Option o = | None | Some o`
+    pub definition: String,
+    pub name: String,
+    pub doc: Option<String>,
+}
+
+pub struct Word {
+    pub name: String,
+    /// Declared effect, or the one inferred from the body
+    pub effect: Option<StackEffect>,
+    pub doc: Option<String>,
+}
+
+/// A fenced code block of a doc comment
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Example {
+    pub code: String,
+    /// Line of the doc comment holding the first line of code, starting at 0
+    pub line: usize,
+}
+
+pub fn module(name: &str, ast: &Ast, natives: &Natives) -> Module {
+    let mut signatures = Signatures::new(ast, |name| natives.get(name).cloned().flatten());
+    let mut module = Module {
+        name: name.to_string(),
+        types: vec![],
+        words: vec![],
+    };
+
+    for def in &ast.defs {
+        match def {
+            Definition::Function(f) => module.words.push(Word {
+                name: f.name.clone(),
+                effect: signatures.get(&f.name),
+                doc: f.doc.clone(),
+            }),
+            Definition::Type(t) => module.types.push(TypeDoc {
+                definition: type_definition(t),
+                name: t.name.clone(),
+                doc: t.doc.clone(),
+            }),
+        }
+    }
+    module
+}
+
+/// The fenced code blocks of a doc comment
+pub fn examples(doc: &str) -> Vec<Example> {
+    let mut examples = vec![];
+    let mut current: Option<Example> = None;
+    for (i, line) in doc.lines().enumerate() {
+        match current.take() {
+            None if line.trim_start().starts_with("```") => {
+                current = Some(Example {
+                    code: String::new(),
+                    line: i + 1,
+                })
+            }
+            None => {}
+            Some(example) if line.trim_start().starts_with("```") => examples.push(example),
+            Some(mut example) => {
+                example.code.push_str(line);
+                example.code.push('\n');
+                current = Some(example);
+            }
+        }
+    }
+    examples
+}
+
+pub fn markdown(module: &Module) -> String {
+    let mut out = format!("# {}\n", module.name);
+
+    if !module.types.is_empty() {
+        out.push_str("\n## Types\n");
+    }
+    for t in &module.types {
+        out.push_str(&format!(
+            "\n### {}\n\n```fox\n{}\n```\n",
+            t.name, t.definition
+        ));
+        if let Some(doc) = &t.doc {
+            out.push_str(&format!("\n{doc}\n"));
+        }
+    }
+
+    if !module.words.is_empty() {
+        out.push_str("\n## Words\n");
+    }
+    for word in &module.words {
+        let signature = match &word.effect {
+            Some(effect) => format!("{} {effect}", word.name),
+            None => word.name.clone(),
+        };
+        out.push_str(&format!(
+            "\n### {}\n\n```fox\n{signature}\n```\n",
+            word.name
+        ));
+        if let Some(doc) = &word.doc {
+            out.push_str(&format!("\n{doc}\n"));
+        }
+    }
+    out
+}
+
+/// A standalone page, rendered from the Markdown one
+pub fn html(module: &Module) -> String {
+    page(&module.name, &render(&markdown(module)))
+}
+
+/// Links to the pages of some modules, `extension` being the one of the pages
+pub fn index(modules: &[Module], extension: &str) -> String {
+    let links = modules
+        .iter()
+        .map(|m| (m.name.as_str(), format!("{}.{extension}", m.name)));
+    match extension {
+        "html" => {
+            let items: String = links
+                .map(|(name, file)| format!("<li><a href=\"{file}\">{}</a></li>\n", escape(name)))
+                .collect();
+            page(
+                "Modules",
+                &format!("<h1>Modules</h1>\n<ul>\n{items}</ul>\n"),
+            )
+        }
+        _ => {
+            let items: String = links
+                .map(|(name, file)| format!("- [{name}]({file})\n"))
+                .collect();
+            format!("# Modules\n\n{items}")
+        }
+    }
+}
+
+fn type_definition(t: &TypeDefinition) -> String {
+    let mut out = format!("type {}", t.name);
+    for var in &t.vars {
+        out.push_str(&format!(" {var}"));
+    }
+    out.push_str(" =");
+    for v in &t.variants {
+        let record = v.items.iter().any(|(name, _)| !name.starts_with('_'));
+        let items: Vec<String> = v
+            .items
+            .iter()
+            .map(|(name, item)| match record {
+                true => format!("{name}: {item}"),
+                false => item.clone(),
+            })
+            .collect();
+        out.push_str(&match (record, items.is_empty()) {
+            (true, _) => format!("\n    | {} {{ {} }}", v.name, items.join(", ")),
+            (false, true) => format!("\n    | {}", v.name),
+            (false, false) => format!("\n    | {} {}", v.name, items.join(" ")),
+        });
+    }
+    out
+}
+
+fn page(title: &str, body: &str) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n\
+         </head>\n<body>\n{body}</body>\n</html>\n",
+        escape(title)
+    )
+}
+
+/// HTML of the Markdown written by `markdown`: headings, fenced code and paragraphs
+/// with inline code
+fn render(markdown: &str) -> String {
+    let mut out = String::new();
+    let mut paragraph: Vec<&str> = vec![];
+    let mut code: Option<String> = None;
+
+    let flush = |paragraph: &mut Vec<&str>, out: &mut String| {
+        if !paragraph.is_empty() {
+            out.push_str(&format!("<p>{}</p>\n", inline(&paragraph.join("\n"))));
+            paragraph.clear();
+        }
+    };
+
+    for line in markdown.lines() {
+        if let Some(block) = &mut code {
+            if line.starts_with("```") {
+                out.push_str(&format!("<pre><code>{}</code></pre>\n", escape(block)));
+                code = None;
+            } else {
+                block.push_str(line);
+                block.push('\n');
+            }
+            continue;
+        }
+
+        let heading = line.chars().take_while(|c| *c == '#').count();
+        if line.starts_with("```") {
+            flush(&mut paragraph, &mut out);
+            code = Some(String::new());
+        } else if heading > 0 && line[heading..].starts_with(' ') {
+            flush(&mut paragraph, &mut out);
+            let text = line[heading..].trim();
+            out.push_str(&format!(
+                "<h{heading} id=\"{}\">{}</h{heading}>\n",
+                escape(text),
+                escape(text)
+            ));
+        } else if line.trim().is_empty() {
+            flush(&mut paragraph, &mut out);
+        } else {
+            paragraph.push(line);
+        }
+    }
+    flush(&mut paragraph, &mut out);
+    out
+}
+
+/// Escaped text, with `code` spans
+fn inline(text: &str) -> String {
+    text.split('`')
+        .enumerate()
+        .map(|(i, part)| match i % 2 {
+            1 => format!("<code>{}</code>", escape(part)),
+            _ => escape(part),
+        })
+        .collect()
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,19 @@
+use fox_core::engine::doctest::Failure;
+use fox_core::parsing::ParseError;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("Parse error: {}", .0.iter().map(ToString::to_string).collect::<Vec<_>>().join(", "))]
+    Parse(Vec<ParseError>),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error(transparent)]
+    Runtime(#[from] fox_core::engine::error::Error),
+    /// A doc comment example failing, or leaving other values than the ones written in it
+    #[error("{}", .0.message)]
+    Example(Failure),
+    #[cfg(feature = "serde")]
+    #[error(transparent)]
+    Serde(#[from] fox_core::engine::serialize::Error),
+}
@@ -1,30 +1,297 @@
+use std::io::{BufRead, Write};
+use std::{fs, path::Path};
+
+pub use fox_core::engine::{
+    adt::FoxType,
+    context::CallContext,
+    convert::{FromFox, IntoArgs, IntoReturn, ToFox},
+    native::IntoNative,
+    typing::{StackEffect, Typed},
+    value::{NativeObject, Value},
+};
+use fox_core::{
+    engine::{
+        builder::EngineBuilder,
+        compile::{compile, lower},
+        debug::{LineIndex, Trace},
+        doctest,
+        engine::Engine,
+        expr::Expr,
+        function::Function,
+        interrupt::InterruptHandle,
+        stack::Stack,
+    },
+    parsing::{
+        self,
+        lexer::Span,
+        parser::{Ast, Definition},
+    },
+    stdlib,
+};
+pub use fox_macro::{fox_fn, FoxType, NativeObject};
+
+// Code generated by `fox_macro` reaches `fox_core` through `::fox::__core`, in this crate too
+#[doc(hidden)]
+pub use fox_core as __core;
+extern crate self as fox;
+
+pub use crate::error::Error;
+
+pub mod dap;
+pub mod doc;
+mod error;
+pub mod lsp;
+mod message;
+
+/// A `test "name" { ... }` block of a source, or a code block of a `##` comment
+pub struct Test {
+    pub name: String,
+    /// Span of the name, or start of the first line of code
+    pub span: Span,
+    body: Body,
+}
+
+enum Body {
+    Block(Vec<Expr>),
+    Example(String),
+}
+
+/// A Fox interpreter ready to be embedded
+pub struct Fox {
+    engine: Engine,
+}
+
+impl Fox {
+    /// Interpreter with the pure core and console access
+    pub fn new() -> Self {
+        Fox::from_builder(Engine::builder().console())
+    }
+
+    /// Interpreter with the capabilities granted by `builder`
+    pub fn from_builder(builder: EngineBuilder) -> Self {
+        Fox {
+            engine: builder.build(),
+        }
+    }
+
+    /// Load the definitions of a source file and run its top level code
+    pub fn load_str(&mut self, source: &str) -> Result<(), Error> {
+        let ast = parsing::parse(source).map_err(Error::Parse)?;
+        let (functions, body) = compile(&ast);
+        stdlib::register(&mut self.engine.definitions, functions);
+        self.engine.eval(&body)?;
+        Ok(())
+    }
+
+    pub fn load_file(&mut self, path: impl AsRef<Path>) -> Result<(), Error> {
+        let source = fs::read_to_string(path)?;
+        self.load_str(&source)
+    }
+
+    /// Load the definitions of a source file without running its top level code,
+    /// returning its `test` blocks followed by the examples of its doc comments
+    pub fn load_tests(&mut self, source: &str) -> Result<Vec<Test>, Error> {
+        let ast = parsing::parse(source).map_err(Error::Parse)?;
+        let (functions, _) = compile(&ast);
+        stdlib::register(&mut self.engine.definitions, functions);
+
+        let mut tests: Vec<Test> = ast
+            .tests
+            .iter()
+            .map(|test| Test {
+                name: test.name.clone(),
+                span: test.span.clone(),
+                body: Body::Block(lower(&test.body)),
+            })
+            .collect();
+        tests.extend(examples(source, &ast));
+        Ok(tests)
+    }
+
+    /// Run a test, leaving the stack as it was
+    ///
+    /// Examples run on an empty stack, their definitions are dropped afterwards.
+    pub fn run_test(&mut self, test: &Test) -> Result<(), Error> {
+        match &test.body {
+            Body::Block(body) => {
+                let depth = self.engine.stack.len();
+                let result = self.engine.eval(body);
+                self.engine.stack.truncate(depth);
+                Ok(result?)
+            }
+            Body::Example(code) => {
+                let definitions = self.engine.definitions.clone();
+                let stack = std::mem::replace(&mut self.engine.stack, Stack::new());
+                let result = doctest::run(&mut self.engine, code);
+                self.engine.definitions = definitions;
+                self.engine.stack = stack;
+                result.map_err(Error::Example)
+            }
+        }
+    }
+
+    /// Source position of the last word run, such as the one raising an error
+    pub fn position(&self) -> Option<Span> {
+        self.engine.position()
+    }
+
+    /// Call a word and convert its topmost result
+    pub fn call<R: FromFox>(&mut self, name: &str, args: impl IntoArgs) -> Result<R, Error> {
+        let depth = self.engine.stack.len();
+        args.push_args(&mut self.engine.stack);
+
+        let result = self
+            .engine
+            .eval(&[Expr::symbol(name)])
+            .and_then(|_| self.engine.stack.pop())
+            .and_then(R::from_fox);
+
+        self.engine.stack.truncate(depth);
+        Ok(result?)
+    }
+
+    /// Call a word for its side effects, ignoring anything it leaves on the stack
+    pub fn run(&mut self, name: &str, args: impl IntoArgs) -> Result<(), Error> {
+        let depth = self.engine.stack.len();
+        args.push_args(&mut self.engine.stack);
+
+        let result = self.engine.eval(&[Expr::symbol(name)]);
+
+        self.engine.stack.truncate(depth);
+        Ok(result?)
+    }
+
+    /// Expose a Rust function or closure as a word
+    pub fn register_fn<Args>(&mut self, name: &str, f: impl IntoNative<Args>) {
+        self.engine
+            .definitions
+            .set(name.to_string(), f.into_native(name));
+    }
+
+    /// Define a word generated by `#[fox_fn]`, such as `my_fn::word()`
+    pub fn register_word(&mut self, word: Function) {
+        self.engine.definitions.set(word.name().to_string(), word);
+    }
+
+    /// Store host data reachable by natives through `CallContext::user_data`
+    pub fn set_user_data<T: 'static>(&mut self, data: T) {
+        self.engine.set_user_data(data);
+    }
+
+    pub fn user_data<T: 'static>(&self) -> Option<&T> {
+        self.engine.user_data()
+    }
+
+    /// Handle stopping the running script, from another thread or a trace hook
+    pub fn interrupt_handle(&self) -> InterruptHandle {
+        self.engine.interrupt_handle()
+    }
+
+    /// Capture what scripts print instead of writing to stdout
+    pub fn set_output(&mut self, output: impl Write + 'static) {
+        self.engine.set_output(output);
+    }
+
+    pub fn set_error_output(&mut self, output: impl Write + 'static) {
+        self.engine.set_error_output(output);
+    }
+
+    /// Feed the lines read by `prompt` and `read-line`
+    pub fn set_input(&mut self, input: impl BufRead + 'static) {
+        self.engine.set_input(input);
+    }
+
+    /// Observe every expression run, e.g. to log or debug a script
+    pub fn set_trace(&mut self, hook: impl FnMut(&Trace) + 'static) {
+        self.engine.set_trace(hook);
+    }
+
+    /// Make a native type known to scripts, defining the `Type.is?` predicate
+    pub fn register_type<T: NativeObject>(&mut self) {
+        let full_name = std::any::type_name::<T>();
+        let name = full_name.rsplit("::").next().unwrap_or(full_name);
+        let word = format!("{name}.is?");
+
+        let f = Function::native(word.clone(), |ctx| {
+            let stack = ctx.stack();
+            let v = stack.pop()?;
+            stack.push_bool(v.as_native_typed::<T>().is_ok());
+            Ok(())
+        });
+        self.engine.definitions.set(word, f);
+    }
+
+    /// Make a type deriving `FoxType` known to scripts, along with its constructors
+    pub fn register_fox_type<T: FoxType>(&mut self) {
+        self.register_type::<T>();
+        stdlib::register(&mut self.engine.definitions, T::constructors());
+    }
+}
+
+/// Convert a serializable Rust value, such as a struct, to a Fox value
+#[cfg(feature = "serde")]
+pub fn to_value<T: serde::Serialize>(value: &T) -> Result<Value, Error> {
+    Ok(fox_core::engine::serialize::to_value(value)?)
+}
+
+/// Convert a Fox value back to a deserializable Rust value
+#[cfg(feature = "serde")]
+pub fn from_value<T: serde::de::DeserializeOwned>(value: Value) -> Result<T, Error> {
+    Ok(fox_core::engine::serialize::from_value(value)?)
+}
+
+impl Default for Fox {
+    fn default() -> Self {
+        Fox::new()
+    }
+}
+
+/// The code blocks of the doc comments, named after their definition
+fn examples(source: &str, ast: &Ast) -> Vec<Test> {
+    let lines = LineIndex::new(source);
+    let mut tests = vec![];
+    for def in &ast.defs {
+        let (name, span, doc) = match def {
+            Definition::Function(f) => (&f.name, &f.span, &f.doc),
+            Definition::Type(t) => (&t.name, &t.span, &t.doc),
+        };
+        let Some(doc) = doc else {
+            continue;
+        };
+
+        let first = lines.line(span.start) - doc.split('\n').count();
+        for (i, example) in doc::examples(doc).into_iter().enumerate() {
+            let start = lines.start(first + example.line);
+            tests.push(Test {
+                name: format!("{name} (example {})", i + 1),
+                span: start..start,
+                body: Body::Example(example.code),
+            });
+        }
+    }
+    tests
+}
+
 #[cfg(test)]
 mod tests {
+    use crate::fox_fn;
+    use fox_core::engine::context::CallContext;
     use fox_core::engine::engine::Engine;
-    use fox_core::engine::env::Env;
     use fox_core::engine::error::Error;
     use fox_core::engine::expr::Expr;
     use fox_core::engine::function::Function;
-    use fox_core::engine::stack::Stack;
-    use fox_core::engine::value::Value;
-    use fox_core::stdlib::list::List;
+    use fox_core::engine::value::List;
 
     #[test]
     fn tests() {
-        fn list_new(
-            defs: &Env<Function>,
-            locals: &Env<Value>,
-            stack: &mut Stack,
-        ) -> Result<(), Error> {
+        fn list_new(ctx: &mut CallContext) -> Result<(), Error> {
+            let stack = ctx.stack();
             let obj = List(vec![]);
             stack.push_native(obj);
             Ok(())
         }
-        fn list_push(
-            defs: &Env<Function>,
-            locals: &Env<Value>,
-            stack: &mut Stack,
-        ) -> Result<(), Error> {
+        fn list_push(ctx: &mut CallContext) -> Result<(), Error> {
+            let stack = ctx.stack();
             println!("{:?}", stack);
             let v = stack.pop()?;
             println!("{:?}", stack);
@@ -54,4 +321,244 @@ mod tests {
 
         println!("Stack: {:?}", engine.stack)
     }
+
+    #[fox_fn]
+    fn add(a: i64, b: i64) -> i64 {
+        a + b
+    }
+
+    #[fox_fn(name = "checked-div")]
+    fn div(a: f64, b: f64) -> Result<f64, Error> {
+        if b == 0.0 {
+            Err(Error::StackError("Division by zero".to_string()))
+        } else {
+            Ok(a / b)
+        }
+    }
+
+    #[test]
+    fn test_fox_fn() {
+        use crate::Fox;
+
+        assert_eq!(add::NAME, "add");
+        assert_eq!(add::signature().to_string(), "(Int Int -> Int)");
+        assert_eq!(div::signature().to_string(), "(Float Float -> Float)");
+
+        let mut fox = Fox::new();
+        fox.register_word(add::word());
+        fox.register_word(div::word());
+
+        assert_eq!(fox.call::<i64>("add", (1, 2)).unwrap(), 3);
+        assert_eq!(fox.call::<f64>("checked-div", (1.0, 4.0)).unwrap(), 0.25);
+        assert!(fox.call::<f64>("checked-div", (1.0, 0.0)).is_err());
+    }
+
+    #[derive(Debug, Clone, PartialEq, crate::FoxType)]
+    struct Point {
+        x: f64,
+        y: f64,
+    }
+
+    #[derive(Debug, Clone, PartialEq, crate::FoxType)]
+    enum Shape {
+        Circle(Point, f64),
+        Empty,
+    }
+
+    #[derive(Debug, Clone, PartialEq, crate::NativeObject)]
+    struct Handle(i64);
+
+    #[test]
+    fn test_derive() {
+        use crate::Fox;
+
+        let mut fox = Fox::new();
+        fox.register_fox_type::<Point>();
+        fox.register_fox_type::<Shape>();
+        fox.register_type::<Handle>();
+        fox.load_str(
+            "def area = match | Circle { -> r, c r r * 3 * } | Empty { 0 }\n\
+             def norm2 = -> p p .x p .x * p .y p .y * +",
+        )
+        .unwrap();
+
+        let p = fox.call::<Point>("Point", (3.0, 4.0)).unwrap();
+        assert_eq!((p.x, p.y), (3.0, 4.0));
+        assert_eq!(fox.call::<f64>("norm2", (p.clone(),)).unwrap(), 25.0);
+        assert_eq!(
+            fox.call::<f64>("area", (Shape::Circle(p, 2.0),)).unwrap(),
+            12.0
+        );
+        assert_eq!(fox.call::<f64>("area", (Shape::Empty,)).unwrap(), 0.0);
+        assert!(fox.call::<bool>("Handle.is?", (Handle(1),)).unwrap());
+        assert_eq!(fox.call::<Handle>("dup", (Handle(7),)).unwrap().0, 7);
+
+        let circle = Shape::Circle(Point { x: 1.0, y: 2.0 }, 3.0);
+        assert!(fox
+            .call::<bool>("==", (circle.clone(), circle.clone()))
+            .unwrap());
+        assert!(!fox.call::<bool>("==", (circle, Shape::Empty)).unwrap());
+        assert!(!fox.call::<bool>("==", (Handle(1), Shape::Empty)).unwrap());
+        fox.load_str("1 2 Point 1 2 Point assertEq").unwrap();
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde() {
+        use std::collections::HashMap;
+
+        #[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+        struct Config {
+            name: String,
+            retries: i64,
+            ratio: f64,
+            tags: Vec<String>,
+            limits: HashMap<String, i64>,
+            parent: Option<String>,
+        }
+
+        let config = Config {
+            name: "fox".to_string(),
+            retries: 3,
+            ratio: 0.5,
+            tags: vec!["a".to_string(), "b".to_string()],
+            limits: HashMap::from([("depth".to_string(), 10)]),
+            parent: None,
+        };
+
+        let mut fox = crate::Fox::new();
+        let value = crate::to_value(&config).unwrap();
+        let value: crate::Value = fox.call("dup", (value,)).unwrap();
+        assert_eq!(crate::from_value::<Config>(value).unwrap(), config);
+
+        let json = serde_json::to_string(&crate::ToFox::to_fox((1, 'c', Some(2)))).unwrap();
+        assert_eq!(json, r#"[1,"c",2]"#);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_round_trip() {
+        use crate::{from_value, to_value, FromFox};
+        use fox_core::engine::adt::Variant;
+
+        #[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+        enum Shape {
+            Empty,
+            Circle(f64),
+            Rect(f64, f64),
+            Named { name: String, sides: Option<u8> },
+        }
+
+        let value = to_value(&String::new()).unwrap();
+        assert_eq!(from_value::<String>(value).unwrap(), "");
+
+        let value = to_value(&Some(1)).unwrap();
+        assert_eq!(Option::<i64>::from_fox(value.clone()).unwrap(), Some(1));
+        assert_eq!(from_value::<Option<i64>>(value).unwrap(), Some(1));
+        let value = to_value(&None::<i64>).unwrap();
+        assert_eq!(from_value::<Option<i64>>(value).unwrap(), None);
+        let value = to_value(&Some(None::<i64>)).unwrap();
+        assert_eq!(
+            from_value::<Option<Option<i64>>>(value).unwrap(),
+            Some(None)
+        );
+
+        let shapes = vec![
+            Shape::Empty,
+            Shape::Circle(1.5),
+            Shape::Rect(2.0, 3.0),
+            Shape::Named {
+                name: "square".to_string(),
+                sides: Some(4),
+            },
+        ];
+        for shape in shapes {
+            let value = to_value(&shape).unwrap();
+            let variant = value.as_native_typed::<Variant>().unwrap();
+            assert_eq!(variant.type_name, "Shape");
+            assert_eq!(from_value::<Shape>(value).unwrap(), shape);
+        }
+    }
+
+    #[test]
+    fn test_embedding() {
+        use crate::Fox;
+
+        let mut fox = Fox::new();
+        fox.register_fn("add", |a: f64, b: f64| a + b);
+        fox.register_type::<List>();
+        fox.load_str(
+            "def square = dup *\n\
+             def sum-of-squares = square swap square add\n\
+             \n\
+             3 square drop",
+        )
+        .unwrap();
+
+        assert_eq!(fox.call::<f64>("square", (4.0,)).unwrap(), 16.0);
+        assert_eq!(fox.call::<i64>("sum-of-squares", (3, 4)).unwrap(), 25);
+        assert!(fox.call::<bool>("List.is?", ("abc",)).unwrap());
+        assert!(!fox.call::<bool>("List.is?", (1.0,)).unwrap());
+        assert!(fox.call::<f64>("missing", ()).is_err());
+    }
+
+    #[test]
+    fn test_run_tests() {
+        use crate::Fox;
+
+        let mut fox = Fox::new();
+        let tests = fox
+            .load_tests(
+                "def double = 2 *\n\
+                 \"never run\" panic\n\
+                 test \"double\" { 2 double 4 assertEq 1 2 assertNe }\n\
+                 test \"errors\" { \"odd\" Err assertErr { \"boom\" panic } assertErr }\n\
+                 test \"lists\" {\n    [1, 2, 3] [1, 2, 4] assertEq\n}",
+            )
+            .unwrap();
+        let names: Vec<&str> = tests.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(names, ["double", "errors", "lists"]);
+
+        assert!(fox.run_test(&tests[0]).is_ok());
+        assert!(fox.run_test(&tests[1]).is_ok());
+        let error = fox.run_test(&tests[2]).unwrap_err().to_string();
+        assert_eq!(
+            error,
+            "Assertion failed: values differ\n   found: [1, 2, 3]\nexpected: [1, 2, 4]\n                 ^"
+        );
+        assert_eq!(fox.position(), Some(190..198));
+    }
+
+    #[test]
+    fn test_run_examples() {
+        use crate::{Error, Fox};
+
+        let mut fox = Fox::new();
+        let tests = fox
+            .load_tests(
+                "## ```\n\
+                 ## 2 double\n\
+                 ## # 4\n\
+                 ## ```\n\
+                 ## Or\n\
+                 ## ```\n\
+                 ## 1 double\n\
+                 ## # 3\n\
+                 ## ```\n\
+                 def double = 2 *",
+            )
+            .unwrap();
+        let names: Vec<&str> = tests.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(names, ["double (example 1)", "double (example 2)"]);
+        assert_eq!(tests[1].span, 46..46);
+
+        assert!(fox.run_test(&tests[0]).is_ok());
+        match fox.run_test(&tests[1]) {
+            Err(Error::Example(failure)) => {
+                assert_eq!(failure.line, 1);
+                assert_eq!(failure.message, "expected 3, found 2");
+            }
+            _ => panic!("the example should fail"),
+        }
+    }
 }
@@ -0,0 +1,573 @@
+//! Language Server Protocol server for `.fox` files, see
+//! <https://microsoft.github.io/language-server-protocol/specification>
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::io::{BufRead, Write};
+use std::path::Path;
+
+use fox_core::engine::compile::qualified;
+use fox_core::engine::engine::Engine;
+use fox_core::engine::lint::{self, Natives};
+use fox_core::engine::typing::{Signatures, StackEffect};
+use fox_core::parsing::lexer::{self, Literal, Span, Spanned, Token};
+use fox_core::parsing::parser::{Ast, Definition};
+use fox_core::parsing::{self, ParseError};
+use serde_json::{json, Value as Json};
+
+use crate::{message, Error};
+
+/// Semantic token types, indexed by the encoded tokens
+const TOKEN_TYPES: [&str; 7] = [
+    "keyword",
+    "function",
+    "type",
+    "namespace",
+    "number",
+    "string",
+    "operator",
+];
+
+const METHOD_NOT_FOUND: i64 = -32601;
+
+// Completion item and symbol kinds
+const FUNCTION: i64 = 3;
+const MODULE: i64 = 9;
+const SYMBOL_ENUM: i64 = 10;
+const SYMBOL_FUNCTION: i64 = 12;
+const SYMBOL_ENUM_MEMBER: i64 = 22;
+
+/// Serve a client until it exits. Requests are read from `input` and responses and
+/// notifications written to `output`, usually stdin and stdout.
+pub fn serve(mut input: impl BufRead, output: impl Write) -> Result<(), Error> {
+    let mut server = Server {
+        output,
+        documents: HashMap::new(),
+        natives: lint::natives(&Engine::builder().build().definitions),
+    };
+
+    while let Some(request) = message::read(&mut input)? {
+        let method = request["method"].as_str().unwrap_or_default();
+        if method == "exit" {
+            break;
+        }
+
+        let result = server.handle(method, &request["params"])?;
+        // Notifications have no id and get no response
+        if request["id"].is_null() {
+            continue;
+        }
+        let response = match result {
+            Some(result) => json!({ "jsonrpc": "2.0", "id": request["id"], "result": result }),
+            None => {
+                let message = format!("unknown method {method}");
+                let error = json!({ "code": METHOD_NOT_FOUND, "message": message });
+                json!({ "jsonrpc": "2.0", "id": request["id"], "error": error })
+            }
+        };
+        message::write(&mut server.output, &response)?;
+    }
+    Ok(())
+}
+
+struct Server<W> {
+    output: W,
+    documents: HashMap<String, Document>,
+    /// Words of the standard library with their effect, if known
+    natives: Natives,
+}
+
+impl<W: Write> Server<W> {
+    /// Result of a request, `None` for unknown methods
+    fn handle(&mut self, method: &str, params: &Json) -> Result<Option<Json>, Error> {
+        let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+        let result = match method {
+            "initialize" => json!({
+                "capabilities": {
+                    "textDocumentSync": 1,
+                    "hoverProvider": true,
+                    "definitionProvider": true,
+                    "completionProvider": { "triggerCharacters": ["."] },
+                    "documentSymbolProvider": true,
+                    "semanticTokensProvider": {
+                        "legend": { "tokenTypes": TOKEN_TYPES, "tokenModifiers": [] },
+                        "full": true,
+                    },
+                },
+                "serverInfo": { "name": "fox" },
+            }),
+            "shutdown" => Json::Null,
+            "textDocument/didOpen" => {
+                let text = params["textDocument"]["text"].as_str().unwrap_or_default();
+                self.open(uri, text)?;
+                Json::Null
+            }
+            "textDocument/didChange" => {
+                // Full sync, the last change has the whole text
+                let changes = params["contentChanges"].as_array();
+                if let Some(text) = changes.and_then(|c| c.last()?["text"].as_str()) {
+                    self.open(uri, text)?;
+                }
+                Json::Null
+            }
+            "textDocument/didClose" => {
+                self.documents.remove(uri);
+                self.publish(uri, vec![])?;
+                Json::Null
+            }
+            "textDocument/hover" => self.hover(uri, &params["position"]),
+            "textDocument/definition" => self.definition(uri, &params["position"]),
+            "textDocument/completion" => self.completion(uri, &params["position"]),
+            "textDocument/documentSymbol" => self.symbols(uri),
+            "textDocument/semanticTokens/full" => self.semantic_tokens(uri),
+            _ if method.starts_with("$/") || method == "initialized" => Json::Null,
+            _ => return Ok(None),
+        };
+        Ok(Some(result))
+    }
+
+    fn open(&mut self, uri: &str, text: &str) -> Result<(), Error> {
+        let document = Document::new(text);
+        let diagnostics = self.diagnostics(&document);
+        self.documents.insert(uri.to_string(), document);
+        self.publish(uri, diagnostics)
+    }
+
+    fn publish(&mut self, uri: &str, diagnostics: Vec<Json>) -> Result<(), Error> {
+        let notification = json!({
+            "jsonrpc": "2.0",
+            "method": "textDocument/publishDiagnostics",
+            "params": { "uri": uri, "diagnostics": diagnostics },
+        });
+        Ok(message::write(&mut self.output, &notification)?)
+    }
+
+    /// Parse errors and lints
+    fn diagnostics(&self, document: &Document) -> Vec<Json> {
+        let diagnostic = |span: &Span, severity: i64, message: String| {
+            json!({
+                "range": document.lines.range(span),
+                "severity": severity,
+                "source": "fox",
+                "message": message,
+            })
+        };
+
+        let mut diagnostics: Vec<Json> = document
+            .errors
+            .iter()
+            .map(|e| diagnostic(&e.span, 1, e.message.clone()))
+            .collect();
+
+        for lint in lint::lint(&document.source, &document.ast, &self.natives) {
+            let mut warning = diagnostic(&lint.span, 2, lint.message);
+            warning["code"] = json!(lint.rule.name());
+            diagnostics.push(warning);
+        }
+
+        diagnostics
+    }
+
+    fn signatures<'a>(&'a self, ast: &'a Ast) -> Signatures<'a> {
+        Signatures::new(ast, |name| self.natives.get(name).cloned().flatten())
+    }
+
+    /// Effect of a word as seen from a document
+    fn effect(&self, document: &Document, name: &str) -> Option<StackEffect> {
+        self.signatures(&document.ast).get(name)
+    }
+
+    fn hover(&self, uri: &str, position: &Json) -> Json {
+        let Some(document) = self.documents.get(uri) else {
+            return Json::Null;
+        };
+        let Some((name, span)) = document.name_at(document.lines.offset(position)) else {
+            return Json::Null;
+        };
+        let Some(effect) = self.effect(document, &name) else {
+            return Json::Null;
+        };
+
+        let mut value = format!("```fox\n{name} {effect}\n```");
+        let doc = document.ast.defs.iter().find_map(|def| match def {
+            Definition::Function(f) if f.name == name => f.doc.as_ref(),
+            _ => None,
+        });
+        if let Some(doc) = doc {
+            value = format!("{value}\n\n{doc}");
+        }
+
+        json!({
+            "contents": { "kind": "markdown", "value": value },
+            "range": document.lines.range(&span),
+        })
+    }
+
+    /// Definitions are looked up in the document, then in the other open documents and
+    /// `.fox` files of its directory
+    fn definition(&self, uri: &str, position: &Json) -> Json {
+        let Some(document) = self.documents.get(uri) else {
+            return Json::Null;
+        };
+        let Some((name, _)) = document.name_at(document.lines.offset(position)) else {
+            return Json::Null;
+        };
+
+        let location = |uri: &str, document: &Document| {
+            let span = document.definition(&name)?;
+            Some(json!({ "uri": uri, "range": document.lines.range(&span) }))
+        };
+
+        let open = std::iter::once((uri, document)).chain(
+            self.documents
+                .iter()
+                .filter(|(other, _)| *other != uri)
+                .map(|(uri, document)| (uri.as_str(), document)),
+        );
+        for (uri, document) in open {
+            if let Some(location) = location(uri, document) {
+                return location;
+            }
+        }
+
+        let Some(dir) = uri
+            .strip_prefix("file://")
+            .map(Path::new)
+            .and_then(Path::parent)
+        else {
+            return Json::Null;
+        };
+        let mut files: Vec<_> = fs::read_dir(dir)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "fox"))
+            .collect();
+        files.sort();
+        for path in files {
+            let uri = format!("file://{}", path.display());
+            if self.documents.contains_key(&uri) {
+                continue;
+            }
+            let Ok(text) = fs::read_to_string(&path) else {
+                continue;
+            };
+            if let Some(location) = location(&uri, &Document::new(&text)) {
+                return location;
+            }
+        }
+        Json::Null
+    }
+
+    /// Words and modules, or the members of a module after `Module.`
+    fn completion(&self, uri: &str, position: &Json) -> Json {
+        let Some(document) = self.documents.get(uri) else {
+            return Json::Null;
+        };
+        let offset = document.lines.offset(position);
+        let before: String = document.lines.chars[..offset]
+            .iter()
+            .rev()
+            .take_while(|c| c.is_alphanumeric() || "._><=-+?!*/%|~".contains(**c))
+            .collect();
+        let prefix: String = before.chars().rev().collect();
+
+        let (path, partial) = match prefix.rsplit_once('.') {
+            Some((path, partial)) => {
+                let modules: Vec<String> = path.split('.').map(str::to_string).collect();
+                (qualified(&modules, ""), partial)
+            }
+            None => (String::new(), prefix.as_str()),
+        };
+
+        let mut words: Vec<(String, Option<StackEffect>)> = self
+            .natives
+            .iter()
+            .map(|(name, effect)| (name.clone(), effect.clone()))
+            .collect();
+        let mut signatures = self.signatures(&document.ast);
+        for name in defined_names(&document.ast) {
+            let effect = signatures.get(&name);
+            words.push((name, effect));
+        }
+
+        let mut items = BTreeMap::new();
+        if path.is_empty() {
+            items.insert("Core".to_string(), (MODULE, None));
+        }
+        for (name, effect) in words {
+            let Some(rest) = name.strip_prefix(&path) else {
+                continue;
+            };
+            let item = match rest.split_once('.') {
+                Some((module, _)) => (module.to_string(), (MODULE, None)),
+                None => (rest.to_string(), (FUNCTION, effect)),
+            };
+            items.entry(item.0).or_insert(item.1);
+        }
+
+        let items: Vec<Json> = items
+            .into_iter()
+            .filter(|(label, _)| label.starts_with(partial))
+            .map(|(label, (kind, effect))| {
+                let detail = effect.map(|e| e.to_string());
+                json!({ "label": label, "kind": kind, "detail": detail })
+            })
+            .collect();
+        json!(items)
+    }
+
+    fn symbols(&self, uri: &str) -> Json {
+        let Some(document) = self.documents.get(uri) else {
+            return Json::Null;
+        };
+        let ast = &document.ast;
+        let mut signatures = self.signatures(ast);
+        let symbol = |name: &str, detail: String, kind: i64, span: &Span| {
+            let range = document.lines.range(span);
+            json!({
+                "name": name,
+                "detail": detail,
+                "kind": kind,
+                "range": range,
+                "selectionRange": range,
+            })
+        };
+
+        let symbols: Vec<Json> = ast
+            .defs
+            .iter()
+            .map(|def| match def {
+                Definition::Function(f) => {
+                    let effect = signatures.get(&f.name);
+                    let detail = effect.map(|e| e.to_string()).unwrap_or_default();
+                    symbol(&f.name, detail, SYMBOL_FUNCTION, &f.span)
+                }
+                Definition::Type(t) => {
+                    let mut symbol = symbol(&t.name, t.vars.join(" "), SYMBOL_ENUM, &t.span);
+                    let variants: Vec<Json> = t
+                        .variants
+                        .iter()
+                        .map(|v| {
+                            let effect = signatures.get(&v.name);
+                            let detail = effect.map(|e| e.to_string()).unwrap_or_default();
+                            json!({
+                                "name": v.name,
+                                "detail": detail,
+                                "kind": SYMBOL_ENUM_MEMBER,
+                                "range": document.lines.range(&v.span),
+                                "selectionRange": document.lines.range(&v.span),
+                            })
+                        })
+                        .collect();
+                    symbol["children"] = json!(variants);
+                    symbol
+                }
+            })
+            .collect();
+        json!(symbols)
+    }
+
+    /// Tokens relative to the previous one, five numbers each
+    fn semantic_tokens(&self, uri: &str) -> Json {
+        let Some(document) = self.documents.get(uri) else {
+            return Json::Null;
+        };
+
+        let mut data = vec![];
+        let (mut last_line, mut last_start) = (0, 0);
+        for (i, (token, span)) in document.tokens.iter().enumerate() {
+            let next = document.tokens.get(i + 1);
+            let Some(kind) = token_type(token, span, next) else {
+                continue;
+            };
+            let (line, start) = document.lines.position(span.start);
+            let (end_line, end) = document.lines.position(span.end);
+            // Tokens can't span several lines
+            if line != end_line {
+                continue;
+            }
+
+            let delta_start = if line == last_line {
+                start - last_start
+            } else {
+                start
+            };
+            data.extend([line - last_line, delta_start, end - start, kind, 0]);
+            (last_line, last_start) = (line, start);
+        }
+        json!({ "data": data })
+    }
+}
+
+fn token_type(token: &Token, span: &Span, next: Option<&Spanned<Token>>) -> Option<usize> {
+    let name = match token {
+        Token::Def
+        | Token::Type
+        | Token::Test
+        | Token::Then
+        | Token::Else
+        | Token::Match
+        | Token::Try
+        | Token::Catch => "keyword",
+        Token::LowerName(_) => "function",
+        Token::UpperName(_) => match next {
+            Some((Token::Dot, dot)) if dot.start == span.end => "namespace",
+            _ => "type",
+        },
+        Token::Literal(Literal::Int(_) | Literal::Float(_)) => "number",
+        Token::Literal(Literal::Char(_) | Literal::String(_)) => "string",
+        Token::Arrow | Token::Eq | Token::Pipe | Token::Lambda | Token::Colon => "operator",
+        _ => return None,
+    };
+    TOKEN_TYPES.iter().position(|t| *t == name)
+}
+
+/// Words defined by a file, including type constructors
+fn defined_names(ast: &Ast) -> Vec<String> {
+    ast.defs
+        .iter()
+        .flat_map(|def| match def {
+            Definition::Function(f) => vec![f.name.clone()],
+            Definition::Type(t) => t.variants.iter().map(|v| v.name.clone()).collect(),
+        })
+        .collect()
+}
+
+struct Document {
+    source: String,
+    lines: Lines,
+    tokens: Vec<Spanned<Token>>,
+    /// What could be parsed, even with errors
+    ast: Ast,
+    errors: Vec<ParseError>,
+}
+
+impl Document {
+    fn new(text: &str) -> Self {
+        let (ast, errors) = parsing::parse_partial(text);
+        Document {
+            source: text.to_string(),
+            lines: Lines::new(text),
+            tokens: lexer::lex_partial(text).0,
+            ast,
+            errors,
+        }
+    }
+
+    /// Full name of the term under an offset, such as `List.push`, with its span
+    fn name_at(&self, offset: usize) -> Option<(String, Span)> {
+        let tokens = &self.tokens;
+        let i = tokens
+            .iter()
+            .position(|(_, span)| span.start <= offset && offset <= span.end)?;
+        let name = |i: usize| match tokens.get(i) {
+            Some((Token::LowerName(n) | Token::UpperName(n), _)) => Some(n.clone()),
+            _ => None,
+        };
+        name(i)?;
+
+        // A path is made of names joined by dots, without spaces
+        let joined = |a: usize, b: usize| {
+            matches!(tokens.get(b), Some((Token::Dot, _)))
+                && tokens[a].1.end == tokens[b].1.start
+                && tokens
+                    .get(b + 1)
+                    .is_some_and(|t| t.1.start == tokens[b].1.end)
+        };
+        let mut start = i;
+        while start >= 2
+            && matches!(tokens[start - 2].0, Token::UpperName(_))
+            && joined(start - 2, start - 1)
+        {
+            start -= 2;
+        }
+        let mut end = i;
+        while matches!(tokens[end].0, Token::UpperName(_)) && joined(end, end + 1) {
+            if name(end + 2).is_none() {
+                break;
+            }
+            end += 2;
+        }
+
+        let mut path: Vec<String> = (start..=end).step_by(2).filter_map(name).collect();
+        let last = path.pop()?;
+        Some((
+            qualified(&path, &last),
+            tokens[start].1.start..tokens[end].1.end,
+        ))
+    }
+
+    /// Span of the name of a word or type defined by the document
+    fn definition(&self, name: &str) -> Option<Span> {
+        self.ast.defs.iter().find_map(|def| match def {
+            Definition::Function(f) if f.name == name => Some(f.span.clone()),
+            Definition::Function(_) => None,
+            Definition::Type(t) if t.name == name => Some(t.span.clone()),
+            Definition::Type(t) => t
+                .variants
+                .iter()
+                .find(|v| v.name == name)
+                .map(|v| v.span.clone()),
+        })
+    }
+}
+
+/// Converts the char offsets of spans to positions counting UTF-16 units, the default
+/// encoding of the protocol
+struct Lines {
+    chars: Vec<char>,
+    starts: Vec<usize>,
+}
+
+impl Lines {
+    fn new(text: &str) -> Self {
+        let chars: Vec<char> = text.chars().collect();
+        let newlines = chars
+            .iter()
+            .enumerate()
+            .filter(|(_, c)| **c == '\n')
+            .map(|(i, _)| i + 1);
+        Lines {
+            starts: std::iter::once(0).chain(newlines).collect(),
+            chars,
+        }
+    }
+
+    /// Line and character, both starting at 0
+    fn position(&self, offset: usize) -> (usize, usize) {
+        let offset = offset.min(self.chars.len());
+        let line = self.starts.partition_point(|start| *start <= offset) - 1;
+        let character = self.chars[self.starts[line]..offset]
+            .iter()
+            .map(|c| c.len_utf16())
+            .sum();
+        (line, character)
+    }
+
+    fn range(&self, span: &Span) -> Json {
+        let (start_line, start) = self.position(span.start);
+        let (end_line, end) = self.position(span.end);
+        json!({
+            "start": { "line": start_line, "character": start },
+            "end": { "line": end_line, "character": end },
+        })
+    }
+
+    /// Char offset of a position, clamped to its line
+    fn offset(&self, position: &Json) -> usize {
+        let line = position["line"].as_u64().unwrap_or_default() as usize;
+        let character = position["character"].as_u64().unwrap_or_default() as usize;
+        let Some(mut offset) = self.starts.get(line).copied() else {
+            return self.chars.len();
+        };
+
+        let mut units = 0;
+        while offset < self.chars.len() && self.chars[offset] != '\n' && units < character {
+            units += self.chars[offset].len_utf16();
+            offset += 1;
+        }
+        offset
+    }
+}
@@ -0,0 +1,477 @@
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::{fs, io};
+
+use clap::{Parser, Subcommand};
+use fox::{doc, Fox};
+use fox_core::engine::builder::EngineBuilder;
+use fox_core::engine::debug::{self, Debugger, LineIndex};
+use fox_core::engine::error::Error as RuntimeError;
+use fox_core::engine::lint::{self, Rule};
+use fox_core::parsing::{self, format, ParseError};
+
+mod debugger;
+
+#[derive(Parser)]
+#[command(name = "fox", about = "The Fox language")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Run a script
+    Run {
+        file: PathBuf,
+        /// Print every expression with the stack before and after it
+        #[arg(long)]
+        trace: bool,
+    },
+    /// Run a script in the step debugger, reading commands from the terminal
+    Debug {
+        file: PathBuf,
+        /// Stop at a word, or at a line when given a number
+        #[arg(short, long = "break", value_name = "WORD|LINE")]
+        breakpoints: Vec<String>,
+        /// Run up to the first breakpoint instead of stopping before the first expression
+        #[arg(long)]
+        no_stop: bool,
+    },
+    /// Serve the Debug Adapter Protocol over stdin and stdout
+    Dap,
+    /// Serve the Language Server Protocol over stdin and stdout
+    Lsp,
+    /// Format `.fox` files, searching directories recursively
+    Fmt {
+        #[arg(default_value = ".")]
+        paths: Vec<PathBuf>,
+        /// List the files that are not formatted instead of writing them, failing if any
+        #[arg(long)]
+        check: bool,
+    },
+    /// Check `.fox` files for suspicious code, searching directories recursively
+    Lint {
+        #[arg(default_value = ".")]
+        paths: Vec<PathBuf>,
+        /// Print the lints as a JSON array
+        #[arg(long)]
+        json: bool,
+        /// Rewrite the files with the fixes of the lints having one
+        #[arg(long)]
+        fix: bool,
+        /// Don't report a lint, such as `unused-local`
+        #[arg(short = 'A', long, value_name = "RULE")]
+        allow: Vec<Rule>,
+    },
+    /// Run the `test` blocks and doc comment examples of `.fox` files, searching directories
+    /// recursively
+    Test {
+        #[arg(default_value = ".")]
+        paths: Vec<PathBuf>,
+        /// Only run the tests whose name contains this text
+        #[arg(short, long)]
+        filter: Option<String>,
+    },
+    /// Write a reference page for each `.fox` file, searching directories recursively
+    Doc {
+        #[arg(default_value = ".")]
+        paths: Vec<PathBuf>,
+        /// Directory of the pages
+        #[arg(short, long, default_value = "doc")]
+        out: PathBuf,
+        /// Write HTML pages instead of Markdown
+        #[arg(long)]
+        html: bool,
+    },
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let result = match cli.command {
+        Command::Run { file, trace } => run(file, trace),
+        Command::Debug {
+            file,
+            breakpoints,
+            no_stop,
+        } => debug(file, breakpoints, no_stop),
+        Command::Dap => fox::dap::serve(io::stdin().lock(), io::stdout()),
+        Command::Lsp => fox::lsp::serve(io::stdin().lock(), io::stdout()),
+        Command::Fmt { paths, check } => return fmt(paths, check),
+        Command::Lint {
+            paths,
+            json,
+            fix,
+            allow,
+        } => return lint(paths, json, fix, allow),
+        Command::Test { paths, filter } => return test(paths, filter),
+        Command::Doc { paths, out, html } => return doc(paths, out, html),
+    };
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        // Already reported with their position
+        Err(fox::Error::Parse(_)) => ExitCode::FAILURE,
+        Err(e) => {
+            eprintln!("{e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Scripts run from the command line can use the console, files of the current directory,
+/// environment variables, the clock and random numbers
+fn builder() -> EngineBuilder {
+    EngineBuilder::new()
+        .console()
+        .filesystem(".")
+        .env_vars()
+        .clock()
+        .random()
+}
+
+fn interpreter() -> Fox {
+    Fox::from_builder(builder())
+}
+
+/// Run a script, printing every parse error
+fn load(fox: &mut Fox, file: &Path, source: &str) -> Result<(), fox::Error> {
+    let result = fox.load_str(source);
+    if let Err(fox::Error::Parse(errors)) = &result {
+        report(file, source, errors);
+    }
+    result
+}
+
+/// Print parse errors as `file:line:column: message`
+fn report(file: &Path, source: &str, errors: &[ParseError]) {
+    let lines = LineIndex::new(source);
+    for e in errors {
+        let (line, column) = lines.line_col(e.span.start);
+        eprintln!("{}:{line}:{column}: {}", file.display(), e.message);
+    }
+}
+
+fn run(file: PathBuf, trace: bool) -> Result<(), fox::Error> {
+    let source = fs::read_to_string(&file)?;
+    let mut fox = interpreter();
+    if trace {
+        fox.set_trace(debug::tracer(io::stderr()));
+    }
+    load(&mut fox, &file, &source)
+}
+
+fn debug(file: PathBuf, breakpoints: Vec<String>, no_stop: bool) -> Result<(), fox::Error> {
+    let source = fs::read_to_string(&file)?;
+    let mut fox = interpreter();
+    let console = debugger::Console::new(&source)?;
+    let mut debugger = Debugger::new(&source, console).interrupt(fox.interrupt_handle());
+    for b in breakpoints {
+        debugger = debugger.break_at(debugger::parse_breakpoint(&b));
+    }
+    if no_stop {
+        debugger = debugger.run_to_breakpoint();
+    }
+
+    fox.set_trace(debugger.into_hook());
+    match load(&mut fox, &file, &source) {
+        // Stopped with `q`
+        Err(fox::Error::Runtime(RuntimeError::Interrupted)) => Ok(()),
+        result => result,
+    }
+}
+
+fn fmt(paths: Vec<PathBuf>, check: bool) -> ExitCode {
+    let files = match fox_files(paths) {
+        Ok(files) => files,
+        Err(e) => {
+            eprintln!("{e}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let mut failed = false;
+    for file in files {
+        let source = match fs::read_to_string(&file) {
+            Ok(source) => source,
+            Err(e) => {
+                eprintln!("{}: {e}", file.display());
+                failed = true;
+                continue;
+            }
+        };
+        match format::format(&source) {
+            Ok(formatted) if formatted == source => {}
+            Ok(_) if check => {
+                println!("{}", file.display());
+                failed = true;
+            }
+            Ok(formatted) => {
+                if let Err(e) = fs::write(&file, formatted) {
+                    eprintln!("{}: {e}", file.display());
+                    failed = true;
+                }
+            }
+            Err(errors) => {
+                report(&file, &source, &errors);
+                failed = true;
+            }
+        }
+    }
+
+    match failed {
+        true => ExitCode::FAILURE,
+        false => ExitCode::SUCCESS,
+    }
+}
+
+fn lint(paths: Vec<PathBuf>, json: bool, fix: bool, allow: Vec<Rule>) -> ExitCode {
+    let files = match fox_files(paths) {
+        Ok(files) => files,
+        Err(e) => {
+            eprintln!("{e}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let natives = lint::natives(&builder().build().definitions);
+    let mut failed = false;
+    let mut reported = vec![];
+    for file in files {
+        let mut source = match fs::read_to_string(&file) {
+            Ok(source) => source,
+            Err(e) => {
+                eprintln!("{}: {e}", file.display());
+                failed = true;
+                continue;
+            }
+        };
+        let mut ast = match parsing::parse(&source) {
+            Ok(ast) => ast,
+            Err(errors) => {
+                report(&file, &source, &errors);
+                failed = true;
+                continue;
+            }
+        };
+
+        let mut lints = lint::lint(&source, &ast, &natives);
+        // A fix can reveal another lint, such as `dup swap swap drop`
+        while fix && lints.iter().any(|lint| lint.fix.is_some()) {
+            let fixed = lint::fix(&source, &lints);
+            let Ok(fixed_ast) = parsing::parse(&fixed) else {
+                break;
+            };
+            (source, ast) = (fixed, fixed_ast);
+            lints = lint::lint(&source, &ast, &natives);
+            if let Err(e) = fs::write(&file, &source) {
+                eprintln!("{}: {e}", file.display());
+                failed = true;
+                break;
+            }
+        }
+
+        let lines = LineIndex::new(&source);
+        for lint in lints.into_iter().filter(|lint| !allow.contains(&lint.rule)) {
+            let (line, column) = lines.line_col(lint.span.start);
+            if json {
+                let (end_line, end_column) = lines.line_col(lint.span.end);
+                let fix = lint.fix.map(|fix| fix.replacement);
+                reported.push(serde_json::json!({
+                    "file": file,
+                    "rule": lint.rule.name(),
+                    "message": lint.message,
+                    "line": line,
+                    "column": column,
+                    "end_line": end_line,
+                    "end_column": end_column,
+                    "fix": fix,
+                }));
+            } else {
+                let file = file.display();
+                println!("{file}:{line}:{column}: {}: {}", lint.rule, lint.message);
+            }
+            failed = true;
+        }
+    }
+
+    if json {
+        println!("{}", serde_json::Value::Array(reported));
+    }
+    match failed {
+        true => ExitCode::FAILURE,
+        false => ExitCode::SUCCESS,
+    }
+}
+
+/// Run the tests of every file in a new interpreter, printing the failures once all ran
+fn test(paths: Vec<PathBuf>, filter: Option<String>) -> ExitCode {
+    let files = match fox_files(paths) {
+        Ok(files) => files,
+        Err(e) => {
+            eprintln!("{e}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let (mut passed, mut failures, mut broken) = (0, vec![], false);
+    for file in files {
+        let source = match fs::read_to_string(&file) {
+            Ok(source) => source,
+            Err(e) => {
+                eprintln!("{}: {e}", file.display());
+                broken = true;
+                continue;
+            }
+        };
+        let mut fox = interpreter();
+        let tests = match fox.load_tests(&source) {
+            Ok(tests) => tests,
+            Err(fox::Error::Parse(errors)) => {
+                report(&file, &source, &errors);
+                broken = true;
+                continue;
+            }
+            Err(e) => {
+                eprintln!("{}: {e}", file.display());
+                broken = true;
+                continue;
+            }
+        };
+
+        let lines = LineIndex::new(&source);
+        let selected = tests.iter().filter(|test| {
+            filter
+                .as_ref()
+                .is_none_or(|f| test.name.contains(f.as_str()))
+        });
+        for test in selected {
+            match fox.run_test(test) {
+                Ok(()) => {
+                    println!("test {}: {} ... ok", file.display(), test.name);
+                    passed += 1;
+                }
+                Err(e) => {
+                    println!("test {}: {} ... FAILED", file.display(), test.name);
+                    let span = match &e {
+                        fox::Error::Runtime(RuntimeError::User(_, Some(span))) => {
+                            Some(span.clone())
+                        }
+                        fox::Error::Example(_) => None,
+                        _ => fox.position(),
+                    };
+                    let (mut line, column) =
+                        lines.line_col(span.unwrap_or(test.span.clone()).start);
+                    if let fox::Error::Example(failure) = &e {
+                        line += failure.line;
+                    }
+                    let location = format!("{}:{line}:{column}", file.display());
+                    failures.push((location, test.name.clone(), e));
+                }
+            }
+        }
+    }
+
+    for (location, name, e) in &failures {
+        println!("\n{location}: `{name}` failed\n{e}");
+    }
+    let status = if failures.is_empty() { "ok" } else { "FAILED" };
+    println!(
+        "\ntest result: {status}. {passed} passed; {} failed",
+        failures.len()
+    );
+
+    match broken || !failures.is_empty() {
+        true => ExitCode::FAILURE,
+        false => ExitCode::SUCCESS,
+    }
+}
+
+fn doc(paths: Vec<PathBuf>, out: PathBuf, html: bool) -> ExitCode {
+    let files = match fox_files(paths) {
+        Ok(files) => files,
+        Err(e) => {
+            eprintln!("{e}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let natives = lint::natives(&builder().build().definitions);
+    let mut modules = vec![];
+    let mut failed = false;
+    for file in files {
+        let source = match fs::read_to_string(&file) {
+            Ok(source) => source,
+            Err(e) => {
+                eprintln!("{}: {e}", file.display());
+                failed = true;
+                continue;
+            }
+        };
+        match parsing::parse(&source) {
+            Ok(ast) => {
+                let name = file.file_stem().unwrap_or_default().to_string_lossy();
+                modules.push(doc::module(&name, &ast, &natives));
+            }
+            Err(errors) => {
+                report(&file, &source, &errors);
+                failed = true;
+            }
+        }
+    }
+
+    let extension = if html { "html" } else { "md" };
+    let mut pages: Vec<(String, String)> = modules
+        .iter()
+        .map(|module| {
+            let page = if html {
+                doc::html(module)
+            } else {
+                doc::markdown(module)
+            };
+            (module.name.clone(), page)
+        })
+        .collect();
+    pages.push(("index".to_string(), doc::index(&modules, extension)));
+
+    let written = fs::create_dir_all(&out).and_then(|()| {
+        pages
+            .iter()
+            .try_for_each(|(name, page)| fs::write(out.join(format!("{name}.{extension}")), page))
+    });
+    if let Err(e) = written {
+        eprintln!("{}: {e}", out.display());
+        failed = true;
+    }
+
+    match failed {
+        true => ExitCode::FAILURE,
+        false => ExitCode::SUCCESS,
+    }
+}
+
+/// The files given, and the `.fox` files found under the directories given, in order
+fn fox_files(paths: Vec<PathBuf>) -> io::Result<Vec<PathBuf>> {
+    fn walk(path: PathBuf, files: &mut Vec<PathBuf>) -> io::Result<()> {
+        if !path.is_dir() {
+            files.push(path);
+            return Ok(());
+        }
+        let mut entries = fs::read_dir(&path)?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<io::Result<Vec<_>>>()?;
+        entries.sort();
+        for entry in entries {
+            if entry.is_dir() || entry.extension().is_some_and(|ext| ext == "fox") {
+                walk(entry, files)?;
+            }
+        }
+        Ok(())
+    }
+
+    let mut files = vec![];
+    for path in paths {
+        walk(path, &mut files)?;
+    }
+    Ok(files)
+}
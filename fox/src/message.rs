@@ -0,0 +1,41 @@
+//! JSON messages framed by a `Content-Length` header, as used by the debug adapter and
+//! language server protocols
+
+use std::io::{self, BufRead, Write};
+
+use serde_json::Value as Json;
+
+/// Read the next message, `None` once the input is closed
+pub(crate) fn read(input: &mut dyn BufRead) -> io::Result<Option<Json>> {
+    let mut length = None;
+    loop {
+        let mut header = String::new();
+        if input.read_line(&mut header)? == 0 {
+            return Ok(None);
+        }
+        let header = header.trim();
+        if header.is_empty() {
+            break;
+        }
+        if let Some(value) = header.strip_prefix("Content-Length:") {
+            length = value.trim().parse().ok();
+        }
+    }
+
+    let length = length.ok_or_else(|| invalid("missing Content-Length"))?;
+    let mut body = vec![0; length];
+    input.read_exact(&mut body)?;
+    serde_json::from_slice(&body)
+        .map(Some)
+        .map_err(|e| invalid(&e.to_string()))
+}
+
+pub(crate) fn write(output: &mut dyn Write, message: &Json) -> io::Result<()> {
+    let body = message.to_string();
+    write!(output, "Content-Length: {}\r\n\r\n{body}", body.len())?;
+    output.flush()
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
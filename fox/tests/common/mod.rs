@@ -0,0 +1,40 @@
+//! Helpers shared by the integration tests, each of them using only some
+#![allow(dead_code)]
+
+use std::cell::RefCell;
+use std::io::{Cursor, Write};
+use std::rc::Rc;
+
+use serde_json::Value as Json;
+
+/// Output shared with the code under test, to read what it wrote
+#[derive(Clone, Default)]
+pub struct Output(pub Rc<RefCell<Vec<u8>>>);
+
+impl Write for Output {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.borrow_mut().write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Input of a debug adapter or language server receiving `messages`
+pub fn framed(messages: impl IntoIterator<Item = Json>) -> Cursor<Vec<u8>> {
+    let mut input = vec![];
+    for message in messages {
+        let body = message.to_string();
+        input.extend(format!("Content-Length: {}\r\n\r\n{body}", body.len()).bytes());
+    }
+    Cursor::new(input)
+}
+
+/// Messages written by a debug adapter or language server
+pub fn unframed(output: &[u8]) -> Vec<Json> {
+    String::from_utf8_lossy(output)
+        .split("Content-Length: ")
+        .filter_map(|m| Some(serde_json::from_str(m.split_once("\r\n\r\n")?.1).unwrap()))
+        .collect()
+}
@@ -0,0 +1,54 @@
+mod common;
+
+use common::{framed, unframed, Output};
+
+#[test]
+fn test_dap() {
+    let program = std::env::temp_dir().join("fox_dap_test.fox");
+    std::fs::write(&program, "def square = -> n n n *\n3 square\nprintln").unwrap();
+
+    let requests = [
+        serde_json::json!({ "command": "initialize", "arguments": {} }),
+        serde_json::json!({ "command": "launch", "arguments": { "program": program } }),
+        serde_json::json!({
+            "command": "setBreakpoints",
+            "arguments": { "source": { "path": program }, "breakpoints": [{ "line": 1 }] },
+        }),
+        serde_json::json!({ "command": "configurationDone" }),
+        serde_json::json!({ "command": "stackTrace", "arguments": { "threadId": 1 } }),
+        serde_json::json!({ "command": "variables", "arguments": { "variablesReference": 2 } }),
+        serde_json::json!({ "command": "continue", "arguments": { "threadId": 1 } }),
+        serde_json::json!({ "command": "disconnect" }),
+    ];
+    let requests = requests.into_iter().enumerate().map(|(seq, mut request)| {
+        request["seq"] = serde_json::json!(seq + 1);
+        request["type"] = serde_json::json!("request");
+        request
+    });
+
+    let output = Output::default();
+    fox::dap::serve(framed(requests), output.clone()).unwrap();
+
+    let messages = unframed(&output.0.take());
+    let find = |key: &str, name: &str| {
+        messages
+            .iter()
+            .find(|m| m[key] == name)
+            .unwrap_or_else(|| panic!("no {name} in {messages:?}"))
+    };
+
+    assert_eq!(find("event", "stopped")["body"]["reason"], "breakpoint");
+    let frames = &find("command", "stackTrace")["body"]["stackFrames"];
+    assert_eq!(frames[0]["name"], "square");
+    assert_eq!(frames[1]["line"], 2);
+    let locals = &find("command", "variables")["body"]["variables"];
+    assert_eq!(locals[0]["name"], "n");
+    assert_eq!(locals[0]["value"], "3");
+    let printed: String = messages
+        .iter()
+        .filter(|m| m["event"] == "output")
+        .filter_map(|m| m["body"]["output"].as_str())
+        .collect();
+    assert_eq!(printed, "9\n");
+    assert_eq!(find("event", "exited")["body"]["exitCode"], 0);
+}
@@ -0,0 +1,34 @@
+use fox::doc::{self, Example};
+use fox_core::engine::engine::Engine;
+use fox_core::engine::lint;
+
+#[test]
+fn test_doc() {
+    let source = "## A pair of values\n\
+                  type Pair a = Pair a a\n\
+                  ## Twice a number\n\
+                  ##\n\
+                  ## ```\n\
+                  ## 2 double\n\
+                  ## # 4\n\
+                  ## ```\n\
+                  def double = 2 *";
+    let ast = fox_core::parsing::parse(source).unwrap();
+    let natives = lint::natives(&Engine::builder().build().definitions);
+    let module = doc::module("numbers", &ast, &natives);
+
+    assert_eq!(
+        doc::markdown(&module),
+        "# numbers\n\n## Types\n\n### Pair\n\n```fox\ntype Pair a =\n    | Pair a a\n```\n\n\
+         A pair of values\n\n## Words\n\n### double\n\n```fox\ndouble (Int -> Int)\n```\n\n\
+         Twice a number\n\n```\n2 double\n# 4\n```\n"
+    );
+    assert!(doc::html(&module).contains("<h3 id=\"double\">double</h3>"));
+    assert_eq!(
+        doc::examples(module.words[0].doc.as_ref().unwrap()),
+        vec![Example {
+            code: "2 double\n# 4\n".to_string(),
+            line: 3,
+        }]
+    );
+}
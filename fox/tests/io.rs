@@ -0,0 +1,24 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+mod common;
+
+use common::Output;
+use fox::Fox;
+
+#[test]
+fn test_io_handles() {
+    let output = Output::default();
+    let traced = Rc::new(RefCell::new(0));
+    let mut fox = Fox::new();
+    fox.set_output(output.clone());
+    fox.set_input("Ada\n".as_bytes());
+    let counter = traced.clone();
+    fox.set_trace(move |_| *counter.borrow_mut() += 1);
+
+    fox.load_str("\"Name? \" prompt match | Ok { println } | Err { drop }")
+        .unwrap();
+
+    assert_eq!(String::from_utf8(output.0.take()).unwrap(), "Name? Ada\n");
+    assert!(*traced.borrow() > 0);
+}
@@ -0,0 +1,98 @@
+use serde_json::json;
+
+mod common;
+
+use common::{framed, unframed};
+
+#[test]
+fn test_lsp() {
+    let source = [
+        "type Shape = Circle Float",
+        "",
+        "def square = dup *",
+        "def area (Shape -> Float) = drop",
+        "",
+        "2 square List.",
+    ]
+    .join("\n");
+    let uri = "file:///tmp/fox_lsp_test.fox";
+    let document = json!({ "uri": uri });
+    let at = |line: usize, character: usize| {
+        let position = json!({ "line": line, "character": character });
+        json!({ "textDocument": document, "position": position })
+    };
+    let requests = [
+        ("initialize", json!({})),
+        (
+            "textDocument/didOpen",
+            json!({ "textDocument": { "uri": uri, "text": source } }),
+        ),
+        ("textDocument/hover", at(5, 3)),
+        ("textDocument/definition", at(5, 3)),
+        ("textDocument/completion", at(5, 14)),
+        (
+            "textDocument/documentSymbol",
+            json!({ "textDocument": document }),
+        ),
+        (
+            "textDocument/semanticTokens/full",
+            json!({ "textDocument": document }),
+        ),
+        ("shutdown", json!(null)),
+    ];
+    let requests = requests
+        .into_iter()
+        .enumerate()
+        .map(|(id, (method, params))| {
+            let mut request = json!({ "jsonrpc": "2.0", "method": method, "params": params });
+            // didOpen is a notification
+            if id != 1 {
+                request["id"] = json!(id);
+            }
+            request
+        });
+
+    let mut output = vec![];
+    fox::lsp::serve(
+        framed(requests.chain([json!({ "method": "exit" })])),
+        &mut output,
+    )
+    .unwrap();
+
+    let messages = unframed(&output);
+    let result = |id: usize| &messages.iter().find(|m| m["id"] == id).unwrap()["result"];
+
+    let diagnostics = &messages[1]["params"]["diagnostics"];
+    // The incomplete path, and `area` not returning anything
+    assert_eq!(diagnostics.as_array().unwrap().len(), 2);
+    assert_eq!(diagnostics[0]["severity"], 1);
+    assert_eq!(
+        diagnostics[1]["range"]["start"],
+        json!({ "line": 3, "character": 4 })
+    );
+
+    assert_eq!(
+        result(2)["contents"]["value"],
+        "```fox\nsquare (a -> a)\n```"
+    );
+    assert_eq!(
+        result(3)["range"]["start"],
+        json!({ "line": 2, "character": 4 })
+    );
+
+    let completions = result(4).as_array().unwrap();
+    assert!(completions.iter().any(|c| c["label"] == "push"));
+    assert!(completions.iter().all(|c| c["label"] != "square"));
+
+    let symbols = result(5).as_array().unwrap();
+    assert_eq!(symbols[0]["name"], "Shape");
+    assert_eq!(symbols[0]["children"][0]["detail"], "(Float -> Shape)");
+    assert_eq!(symbols[1]["detail"], "(a -> a)");
+
+    // `type` keyword then `Shape` type
+    let tokens = result(6)["data"].as_array().unwrap();
+    assert_eq!(
+        tokens[..10],
+        json!([0, 0, 4, 0, 0, 0, 5, 5, 2, 0]).as_array().unwrap()[..]
+    );
+}
@@ -0,0 +1,108 @@
+use crate::engine::function::Function;
+use crate::engine::value::{NativeObject, Value, ValueData};
+
+/// Value of a type defined in Fox with `type`
+#[derive(Debug, Clone)]
+pub struct Variant {
+    pub type_name: String,
+    pub name: String,
+    pub fields: Vec<(String, Value)>,
+}
+
+impl Variant {
+    /// Variant with positional fields, named `_0`, `_1`, ...
+    pub fn tuple(type_name: &str, name: &str, values: Vec<Value>) -> Self {
+        Variant {
+            type_name: type_name.to_string(),
+            name: name.to_string(),
+            fields: values
+                .into_iter()
+                .enumerate()
+                .map(|(n, v)| (format!("_{n}"), v))
+                .collect(),
+        }
+    }
+
+    /// Value of the first field
+    pub fn first(&self) -> Option<&Value> {
+        self.fields.first().map(|(_, v)| v)
+    }
+}
+
+impl NativeObject for Variant {
+    fn repr(&self) -> String {
+        if self.fields.is_empty() {
+            return self.name.clone();
+        }
+
+        let tuple = self.fields.iter().all(|(name, _)| name.starts_with('_'));
+        let fields: Vec<String> = self
+            .fields
+            .iter()
+            .map(|(name, v)| match tuple {
+                true => v.data_ref().repr(),
+                false => format!("{name}: {}", v.data_ref().repr()),
+            })
+            .collect();
+
+        match tuple {
+            true => format!("{}({})", self.name, fields.join(", ")),
+            false => format!("{} {{{}}}", self.name, fields.join(", ")),
+        }
+    }
+
+    fn cloned(&self) -> Box<dyn NativeObject> {
+        Box::new(self.clone())
+    }
+
+    fn size(&self) -> usize {
+        1 + self.fields.iter().map(|(_, v)| v.size()).sum::<usize>()
+    }
+
+    fn eq(&self, other: &dyn NativeObject) -> bool {
+        other
+            .as_any()
+            .downcast_ref::<Variant>()
+            .is_some_and(|other| {
+                self.type_name == other.type_name
+                    && self.name == other.name
+                    && self.fields == other.fields
+            })
+    }
+
+    fn variant(&self) -> Option<(&str, Vec<Value>)> {
+        let fields = self.fields.iter().map(|(_, v)| v.clone()).collect();
+        Some((&self.name, fields))
+    }
+
+    fn field(&self, name: &str) -> Option<Value> {
+        self.fields
+            .iter()
+            .find(|(field, _)| field == name)
+            .map(|(_, v)| v.clone())
+    }
+}
+
+/// Word building a variant, its fields are popped in declaration order
+pub fn constructor(type_name: &str, name: &str, field_names: Vec<String>) -> Function {
+    let type_name = type_name.to_string();
+    let variant = name.to_string();
+
+    Function::native(name, move |ctx| {
+        let stack = ctx.stack();
+        let values = stack.pop_n(field_names.len())?;
+        let fields = field_names.iter().cloned().zip(values).collect();
+        stack.push(ValueData::Native(Box::new(Variant {
+            type_name: type_name.clone(),
+            name: variant.clone(),
+            fields,
+        })));
+        Ok(())
+    })
+}
+
+/// A Rust type usable as a Fox ADT, implemented by `#[derive(FoxType)]`
+pub trait FoxType: NativeObject + Sized {
+    /// One word per variant, or a single one named after a struct
+    fn constructors() -> Vec<Function>;
+}
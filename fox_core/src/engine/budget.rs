@@ -0,0 +1,66 @@
+use std::fmt::{Display, Formatter};
+
+/// Nesting of calls allowed when a budget sets none, so that deep recursion fails with an
+/// error instead of overflowing the 8 MB native stack of a main thread
+pub const DEFAULT_CALL_DEPTH: usize = 1000;
+
+/// Execution limits of an [`Engine`](crate::engine::engine::Engine).
+///
+/// Every limit is optional, `Budget::default()` only limits the nesting of calls to
+/// [`DEFAULT_CALL_DEPTH`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Budget {
+    pub max_instructions: Option<u64>,
+    pub max_stack_depth: Option<usize>,
+    /// Values on the stack, the ones held by lists and other values included. The limit is
+    /// approximate: they are counted every few hundred instructions, so a single word
+    /// building a large value, such as `collect` on a long `Seq`, can go past it.
+    pub max_values: Option<usize>,
+    /// Nesting of calls, [`DEFAULT_CALL_DEPTH`] if not set
+    pub max_call_depth: Option<usize>,
+}
+
+impl Budget {
+    pub fn unlimited() -> Self {
+        Budget::default()
+    }
+
+    pub fn instructions(mut self, n: u64) -> Self {
+        self.max_instructions = Some(n);
+        self
+    }
+
+    pub fn stack_depth(mut self, n: usize) -> Self {
+        self.max_stack_depth = Some(n);
+        self
+    }
+
+    pub fn values(mut self, n: usize) -> Self {
+        self.max_values = Some(n);
+        self
+    }
+
+    pub fn call_depth(mut self, n: usize) -> Self {
+        self.max_call_depth = Some(n);
+        self
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Limit {
+    Instructions(u64),
+    StackDepth(usize),
+    Values(usize),
+    CallDepth(usize),
+}
+
+impl Display for Limit {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Limit::Instructions(n) => write!(f, "more than {n} instructions"),
+            Limit::StackDepth(n) => write!(f, "stack deeper than {n} values"),
+            Limit::Values(n) => write!(f, "more than {n} live values"),
+            Limit::CallDepth(n) => write!(f, "calls nested deeper than {n}"),
+        }
+    }
+}
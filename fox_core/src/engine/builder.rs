@@ -0,0 +1,92 @@
+use std::collections::HashSet;
+use std::path::PathBuf;
+
+use crate::engine::budget::Budget;
+use crate::engine::capability::Capability;
+use crate::engine::engine::Engine;
+use crate::engine::function::Function;
+use crate::stdlib;
+
+/// Builds an [`Engine`] with the standard library, only granting the requested capabilities.
+///
+/// The pure core is always registered. Words of modules that were not granted are still
+/// defined, but fail with `Error::PermissionDenied` when called.
+#[derive(Debug, Clone, Default)]
+pub struct EngineBuilder {
+    budget: Budget,
+    capabilities: HashSet<Capability>,
+    fs_root: Option<PathBuf>,
+}
+
+impl EngineBuilder {
+    pub fn new() -> Self {
+        EngineBuilder::default()
+    }
+
+    pub fn budget(mut self, budget: Budget) -> Self {
+        self.budget = budget;
+        self
+    }
+
+    pub fn console(self) -> Self {
+        self.grant(Capability::Console)
+    }
+
+    /// Filesystem access, restricted to files under `root`
+    pub fn filesystem(mut self, root: impl Into<PathBuf>) -> Self {
+        self.fs_root = Some(root.into());
+        self.grant(Capability::Filesystem)
+    }
+
+    pub fn env_vars(self) -> Self {
+        self.grant(Capability::EnvVars)
+    }
+
+    pub fn clock(self) -> Self {
+        self.grant(Capability::Clock)
+    }
+
+    pub fn random(self) -> Self {
+        self.grant(Capability::Random)
+    }
+
+    fn grant(mut self, capability: Capability) -> Self {
+        self.capabilities.insert(capability);
+        self
+    }
+
+    pub fn build(self) -> Engine {
+        let mut engine = Engine::with_budget(self.budget);
+        let defs = &mut engine.definitions;
+
+        stdlib::register(defs, stdlib::assert::natives());
+        stdlib::register(defs, stdlib::base::natives());
+        stdlib::register(defs, stdlib::io::natives());
+        stdlib::register(defs, stdlib::list::natives());
+        stdlib::register(defs, stdlib::option::natives());
+        stdlib::register(defs, stdlib::result::natives());
+        stdlib::register(defs, stdlib::seq::natives());
+        stdlib::register(defs, stdlib::string::natives());
+        stdlib::register(defs, stdlib::table::natives());
+        stdlib::register(defs, stdlib::tuple::natives());
+
+        let fs_root = self.fs_root.unwrap_or_default();
+        let modules: [(Capability, Vec<Function>); 5] = [
+            (Capability::Console, stdlib::io::console_natives()),
+            (Capability::Filesystem, stdlib::io::fs_natives(fs_root)),
+            (Capability::EnvVars, stdlib::env::natives()),
+            (Capability::Clock, stdlib::time::natives()),
+            (Capability::Random, stdlib::random::natives()),
+        ];
+
+        for (capability, natives) in modules {
+            if self.capabilities.contains(&capability) {
+                stdlib::register(defs, natives);
+            } else {
+                stdlib::deny(defs, natives, capability);
+            }
+        }
+
+        engine
+    }
+}
@@ -0,0 +1,24 @@
+use std::fmt::{Display, Formatter};
+
+/// Side effects a script may be granted access to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Capability {
+    Console,
+    Filesystem,
+    EnvVars,
+    Clock,
+    Random,
+}
+
+impl Display for Capability {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Capability::Console => "console",
+            Capability::Filesystem => "filesystem",
+            Capability::EnvVars => "env-vars",
+            Capability::Clock => "clock",
+            Capability::Random => "random",
+        };
+        f.write_str(name)
+    }
+}
@@ -0,0 +1,98 @@
+use crate::engine::adt;
+use crate::engine::expr::{Expr, Pattern};
+use crate::engine::function::Function;
+use crate::engine::typing::constructor_effect;
+use crate::engine::value::{List, Value, ValueData};
+use crate::parsing::{
+    lexer::Literal,
+    parser::{self, Ast, Definition},
+};
+
+/// Lower a parsed file to its functions and its top level code
+///
+/// Type definitions produce one constructor word per variant.
+pub fn compile(ast: &Ast) -> (Vec<Function>, Vec<Expr>) {
+    let functions = ast
+        .defs
+        .iter()
+        .flat_map(|def| match def {
+            Definition::Function(f) => {
+                let function = Function::fox(f.name.clone(), lower(&f.body));
+                vec![match &f.ftype {
+                    Some(ftype) => function.with_signature(ftype.into()),
+                    None => function,
+                }]
+            }
+            Definition::Type(t) => t
+                .variants
+                .iter()
+                .map(|v| {
+                    let fields = v.items.iter().map(|(name, _)| name.clone()).collect();
+                    adt::constructor(&t.name, &v.name, fields)
+                        .with_signature(constructor_effect(t, v))
+                })
+                .collect(),
+        })
+        .collect();
+
+    (functions, lower(&ast.body))
+}
+
+pub fn lower(exprs: &[parser::Expr]) -> Vec<Expr> {
+    exprs
+        .iter()
+        .flat_map(|e| match e {
+            parser::Expr::Term { span, .. } if !span.is_empty() => {
+                vec![Expr::Position(span.clone()), lower_one(e)]
+            }
+            _ => vec![lower_one(e)],
+        })
+        .collect()
+}
+
+fn lower_one(e: &parser::Expr) -> Expr {
+    match e {
+        parser::Expr::Literal(Literal::Int(i)) => Expr::Num(*i as f64),
+        parser::Expr::Literal(Literal::Float(f)) => Expr::Num(*f),
+        parser::Expr::Literal(Literal::Char(c)) => Expr::Char(*c),
+        parser::Expr::Literal(Literal::String(s)) => Expr::String(s.clone()),
+        parser::Expr::Term { name, module, .. } => Expr::Symbol(qualified(module, name)),
+        parser::Expr::Local { names, .. } => Expr::Local(names.clone()),
+        parser::Expr::Quote(items) => Expr::Quote(lower(items)),
+        parser::Expr::Field(name) => Expr::Field(name.clone()),
+        parser::Expr::Match(arms) => Expr::Match(
+            arms.iter()
+                .map(|arm| (pattern(&arm.pattern), lower(&arm.body)))
+                .collect(),
+        ),
+    }
+}
+
+fn pattern(pattern: &parser::Pattern) -> Pattern {
+    match pattern {
+        parser::Pattern::Variant(name) => Pattern::Variant(name.clone()),
+        parser::Pattern::Bind(name) => Pattern::Bind(name.clone()),
+        parser::Pattern::Wildcard => Pattern::Wildcard,
+        parser::Pattern::Literal(literal) => Pattern::Value(match literal {
+            Literal::Int(i) => ValueData::Num(*i as f64).into(),
+            Literal::Float(f) => ValueData::Num(*f).into(),
+            Literal::Char(c) => ValueData::Char(*c).into(),
+            Literal::String(s) => Value::from(ValueData::Native(Box::new(List::from_string(s)))),
+        }),
+    }
+}
+
+/// Full name of a term, words of the `Core` module are always in scope
+pub fn qualified(module: &[String], name: &str) -> String {
+    let module = match module {
+        [core, rest @ ..] if core == "Core" => rest,
+        _ => module,
+    };
+
+    module
+        .iter()
+        .map(String::as_str)
+        .chain([name])
+        .collect::<Vec<_>>()
+        .join(".")
+}
@@ -0,0 +1,118 @@
+use std::io::{BufRead, Write};
+
+use crate::engine::engine::Engine;
+use crate::engine::env::Env;
+use crate::engine::error::Error;
+use crate::engine::function::Function;
+use crate::engine::generator::Generator;
+use crate::engine::stack::Stack;
+use crate::engine::value::{List, Value};
+use crate::parsing::lexer::Span;
+use crate::stdlib::seq::Seq;
+
+/// What a native word can reach while it runs
+pub struct CallContext<'a> {
+    engine: &'a mut Engine,
+    locals: &'a mut Env<Value>,
+}
+
+impl<'a> CallContext<'a> {
+    pub(crate) fn new(engine: &'a mut Engine, locals: &'a mut Env<Value>) -> Self {
+        CallContext { engine, locals }
+    }
+
+    pub fn stack(&mut self) -> &mut Stack {
+        &mut self.engine.stack
+    }
+
+    pub fn definitions(&self) -> &Env<Function> {
+        &self.engine.definitions
+    }
+
+    /// Locals of the caller
+    pub fn locals(&self) -> &Env<Value> {
+        self.locals
+    }
+
+    pub fn locals_mut(&mut self) -> &mut Env<Value> {
+        self.locals
+    }
+
+    /// Source position of the running word, when known
+    pub fn position(&self) -> Option<Span> {
+        self.engine.position()
+    }
+
+    /// Pop a list, collecting the `Seq` left by a lazy word such as `map`
+    pub fn pop_list(&mut self) -> Result<List, Error> {
+        let value = self.stack().pop()?;
+        if let Ok(list) = value.as_native_typed::<List>() {
+            return Ok(list.clone());
+        }
+        let seq = value
+            .as_native_typed::<Seq>()
+            .map_err(|_| Error::CastError("List".to_string()))?;
+        Ok(List(seq.clone().collect(self)?))
+    }
+
+    /// Pop a string, which may be a `Seq` of chars
+    pub fn pop_string(&mut self) -> Result<String, Error> {
+        self.pop_list()?.to_string()
+    }
+
+    /// Run a quotation, or the word named by a symbol, on the current stack.
+    ///
+    /// The callee shares the budget of the running evaluation. A quotation sees the locals
+    /// where it was written, a word named by a symbol the caller's.
+    pub fn call(&mut self, callable: &Value) -> Result<(), Error> {
+        self.engine.apply(callable, self.locals)
+    }
+
+    /// Run the word `name` on the current stack
+    pub fn call_word(&mut self, name: &str) -> Result<(), Error> {
+        self.engine.apply_word(name, self.locals)
+    }
+
+    /// Count one instruction against the budget, failing if the evaluation was interrupted
+    pub fn tick(&mut self) -> Result<(), Error> {
+        self.engine.step()
+    }
+
+    /// Make the enclosing word return `result`, an `Err`
+    pub(crate) fn propagate(&mut self, result: Value) -> Error {
+        self.engine.propagate(result)
+    }
+
+    /// The `Err` carried by an `Error::Propagated` that was caught
+    pub(crate) fn take_propagated(&mut self) -> Option<Value> {
+        self.engine.take_propagated()
+    }
+
+    /// Run a generator until its next value
+    pub(crate) fn resume(&mut self, generator: &mut Generator) -> Result<Option<Value>, Error> {
+        self.engine.resume(generator)
+    }
+
+    /// Writer set with `Engine::set_output`
+    pub fn output(&mut self) -> &mut dyn Write {
+        self.engine.output()
+    }
+
+    pub fn error_output(&mut self) -> &mut dyn Write {
+        self.engine.error_output()
+    }
+
+    /// Reader set with `Engine::set_input`
+    pub fn input(&mut self) -> &mut dyn BufRead {
+        self.engine.input()
+    }
+
+    /// Host data stored with `Engine::set_user_data`
+    pub fn user_data<T: 'static>(&self) -> Option<&T> {
+        self.engine.user_data()
+    }
+
+    pub fn user_data_mut<T: 'static>(&mut self) -> Option<&mut T> {
+        self.engine.user_data_mut()
+    }
+}
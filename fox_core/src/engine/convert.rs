@@ -0,0 +1,308 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use crate::engine::adt::Variant;
+use crate::engine::error::Error;
+use crate::engine::stack::Stack;
+use crate::engine::value::{List, Value, ValueData};
+use crate::stdlib::seq::Seq;
+use crate::stdlib::table::Table;
+use crate::stdlib::tuple::Tuple;
+
+/// Conversion of a Rust value into a Fox value
+pub trait ToFox {
+    fn to_fox(self) -> Value;
+}
+
+/// Conversion of a Fox value into a Rust value
+pub trait FromFox: Sized {
+    fn from_fox(value: Value) -> Result<Self, Error>;
+}
+
+impl ToFox for Value {
+    fn to_fox(self) -> Value {
+        self
+    }
+}
+
+impl FromFox for Value {
+    fn from_fox(value: Value) -> Result<Self, Error> {
+        Ok(value)
+    }
+}
+
+impl ToFox for f64 {
+    fn to_fox(self) -> Value {
+        ValueData::Num(self).into()
+    }
+}
+
+impl FromFox for f64 {
+    fn from_fox(value: Value) -> Result<Self, Error> {
+        value.as_num()
+    }
+}
+
+impl ToFox for f32 {
+    fn to_fox(self) -> Value {
+        ValueData::Num(self as f64).into()
+    }
+}
+
+impl FromFox for f32 {
+    fn from_fox(value: Value) -> Result<Self, Error> {
+        Ok(value.as_num()? as f32)
+    }
+}
+
+macro_rules! impl_int {
+    ($($t:ty),*) => {
+        $(impl ToFox for $t {
+            fn to_fox(self) -> Value {
+                ValueData::Num(self as f64).into()
+            }
+        }
+
+        /// Fails on fractional or out of range numbers
+        impl FromFox for $t {
+            fn from_fox(value: Value) -> Result<Self, Error> {
+                let n = value.as_num()?;
+                // `MAX + 1` is a power of two, so unlike `MAX` it is exact as a float
+                let end = <$t>::MAX as f64 + 1.0;
+                if n.fract() == 0.0 && n >= <$t>::MIN as f64 && n < end {
+                    Ok(n as $t)
+                } else {
+                    Err(Error::CastError(stringify!($t).to_string()))
+                }
+            }
+        })*
+    };
+}
+
+impl_int!(i8, i16, i32, i64, isize, u8, u16, u32, u64, usize);
+
+impl ToFox for bool {
+    fn to_fox(self) -> Value {
+        ValueData::Bool(self).into()
+    }
+}
+
+impl FromFox for bool {
+    fn from_fox(value: Value) -> Result<Self, Error> {
+        value.as_bool()
+    }
+}
+
+impl ToFox for char {
+    fn to_fox(self) -> Value {
+        ValueData::Char(self).into()
+    }
+}
+
+impl FromFox for char {
+    fn from_fox(value: Value) -> Result<Self, Error> {
+        value.as_char()
+    }
+}
+
+impl ToFox for String {
+    fn to_fox(self) -> Value {
+        ValueData::Native(Box::new(List::from_string(&self))).into()
+    }
+}
+
+impl ToFox for &str {
+    fn to_fox(self) -> Value {
+        ValueData::Native(Box::new(List::from_string(self))).into()
+    }
+}
+
+impl FromFox for String {
+    fn from_fox(value: Value) -> Result<Self, Error> {
+        value.as_native_typed::<List>()?.to_string()
+    }
+}
+
+impl<T: ToFox> ToFox for Vec<T> {
+    fn to_fox(self) -> Value {
+        List(self.into_iter().map(ToFox::to_fox).collect()).to_fox()
+    }
+}
+
+impl<T: FromFox> FromFox for Vec<T> {
+    fn from_fox(value: Value) -> Result<Self, Error> {
+        value
+            .as_native_typed::<List>()?
+            .0
+            .iter()
+            .cloned()
+            .map(T::from_fox)
+            .collect()
+    }
+}
+
+impl<K: ToFox, V: ToFox> ToFox for HashMap<K, V> {
+    fn to_fox(self) -> Value {
+        let pairs = self.into_iter().map(|(k, v)| (k.to_fox(), v.to_fox()));
+        Table(pairs.collect()).to_fox()
+    }
+}
+
+impl<K: FromFox + Eq + Hash, V: FromFox> FromFox for HashMap<K, V> {
+    fn from_fox(value: Value) -> Result<Self, Error> {
+        value
+            .as_native_typed::<Table>()?
+            .0
+            .iter()
+            .map(|(k, v)| Ok((K::from_fox(k.clone())?, V::from_fox(v.clone())?)))
+            .collect()
+    }
+}
+
+/// `Some value` or `None`
+impl<T: ToFox> ToFox for Option<T> {
+    fn to_fox(self) -> Value {
+        match self {
+            Some(v) => Variant::tuple("Option", "Some", vec![v.to_fox()]).to_fox(),
+            None => Variant::tuple("Option", "None", vec![]).to_fox(),
+        }
+    }
+}
+
+impl<T: FromFox> FromFox for Option<T> {
+    fn from_fox(value: Value) -> Result<Self, Error> {
+        let variant = value.as_native_typed::<Variant>()?;
+        match (variant.type_name.as_str(), variant.name.as_str()) {
+            ("Option", "None") => Ok(None),
+            ("Option", "Some") => variant.first().cloned().map(T::from_fox).transpose(),
+            _ => Err(Error::CastError("Option".to_string())),
+        }
+    }
+}
+
+/// `Ok value` or `Err error`
+impl<T: ToFox, E: ToFox> ToFox for Result<T, E> {
+    fn to_fox(self) -> Value {
+        match self {
+            Ok(v) => Variant::tuple("Result", "Ok", vec![v.to_fox()]).to_fox(),
+            Err(e) => Variant::tuple("Result", "Err", vec![e.to_fox()]).to_fox(),
+        }
+    }
+}
+
+impl<T: FromFox, E: FromFox> FromFox for Result<T, E> {
+    fn from_fox(value: Value) -> Result<Self, Error> {
+        let variant = value.as_native_typed::<Variant>()?;
+        let first = || {
+            variant
+                .first()
+                .cloned()
+                .ok_or(Error::CastError("Result".to_string()))
+        };
+        match (variant.type_name.as_str(), variant.name.as_str()) {
+            ("Result", "Ok") => Ok(Ok(T::from_fox(first()?)?)),
+            ("Result", "Err") => Ok(Err(E::from_fox(first()?)?)),
+            _ => Err(Error::CastError("Result".to_string())),
+        }
+    }
+}
+
+macro_rules! impl_native {
+    ($($t:ty),*) => {
+        $(impl ToFox for $t {
+            fn to_fox(self) -> Value {
+                ValueData::Native(Box::new(self)).into()
+            }
+        }
+
+        impl FromFox for $t {
+            fn from_fox(value: Value) -> Result<Self, Error> {
+                value.as_native_typed::<Self>().cloned()
+            }
+        })*
+    };
+}
+
+impl_native!(List, Seq, Table, Tuple, Variant);
+
+macro_rules! impl_tuple {
+    ($n:literal: $($arg:ident),*) => {
+        impl<$($arg: ToFox),*> ToFox for ($($arg,)*) {
+            #[allow(non_snake_case)]
+            fn to_fox(self) -> Value {
+                let ($($arg,)*) = self;
+                Tuple(vec![$($arg.to_fox()),*]).to_fox()
+            }
+        }
+
+        impl<$($arg: FromFox),*> FromFox for ($($arg,)*) {
+            fn from_fox(value: Value) -> Result<Self, Error> {
+                let tuple = value.as_native_typed::<Tuple>()?;
+                if tuple.0.len() != $n {
+                    return Err(Error::CastError(format!("Tuple{}", $n)));
+                }
+                let mut items = tuple.0.iter().cloned();
+                Ok(($($arg::from_fox(items.next().unwrap())?,)*))
+            }
+        }
+    };
+}
+
+impl_tuple!(1: A);
+impl_tuple!(2: A, B);
+impl_tuple!(3: A, B, C);
+impl_tuple!(4: A, B, C, D);
+impl_tuple!(5: A, B, C, D, E);
+impl_tuple!(6: A, B, C, D, E, F);
+impl_tuple!(7: A, B, C, D, E, F, G);
+impl_tuple!(8: A, B, C, D, E, F, G, H);
+impl_tuple!(9: A, B, C, D, E, F, G, H, I);
+
+/// Values left on the stack by a native function once it returns
+pub trait IntoReturn {
+    fn push_to(self, stack: &mut Stack) -> Result<(), Error>;
+}
+
+impl IntoReturn for () {
+    fn push_to(self, _: &mut Stack) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+impl<T: ToFox> IntoReturn for T {
+    fn push_to(self, stack: &mut Stack) -> Result<(), Error> {
+        stack.push(self.to_fox());
+        Ok(())
+    }
+}
+
+impl<T: IntoReturn> IntoReturn for Result<T, Error> {
+    fn push_to(self, stack: &mut Stack) -> Result<(), Error> {
+        self?.push_to(stack)
+    }
+}
+
+/// Arguments pushed on the stack before calling a word, the last one ends up on top
+pub trait IntoArgs {
+    fn push_args(self, stack: &mut Stack);
+}
+
+macro_rules! impl_into_args {
+    ($($arg:ident),*) => {
+        impl<$($arg: ToFox),*> IntoArgs for ($($arg,)*) {
+            #[allow(non_snake_case, unused_variables)]
+            fn push_args(self, stack: &mut Stack) {
+                let ($($arg,)*) = self;
+                $(stack.push($arg.to_fox());)*
+            }
+        }
+    };
+}
+
+impl_into_args!();
+impl_into_args!(A);
+impl_into_args!(A, B);
+impl_into_args!(A, B, C);
+impl_into_args!(A, B, C, D);
+impl_into_args!(A, B, C, D, E);
+impl_into_args!(A, B, C, D, E, F);
@@ -0,0 +1,239 @@
+use std::io::Write;
+
+use crate::engine::env::Env;
+use crate::engine::expr::Expr;
+use crate::engine::interrupt::InterruptHandle;
+use crate::engine::stack::Stack;
+use crate::engine::value::Value;
+use crate::parsing::lexer::Span;
+
+/// Whether a trace event comes before or after its expression ran
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    Before,
+    After,
+}
+
+/// State of the engine around one expression, given to the trace hook
+pub struct Trace<'a> {
+    pub expr: &'a Expr,
+    pub phase: Phase,
+    pub stack: &'a Stack,
+    pub locals: &'a Env<Value>,
+    /// Number of words and quotations being run
+    pub depth: usize,
+    /// Position of the last word with a known position
+    pub position: Option<Span>,
+    /// Fox words being run, the innermost last
+    pub calls: &'a [CallFrame],
+}
+
+/// A Fox word being run, with the position it was called from
+#[derive(Debug, Clone)]
+pub struct CallFrame {
+    pub name: String,
+    pub call_site: Option<Span>,
+}
+
+/// Trace hook printing every expression with the stack before and after it ran
+pub fn tracer(mut output: impl Write) -> impl FnMut(&Trace) {
+    move |trace| {
+        let indent = "  ".repeat(trace.depth);
+        let _ = match trace.phase {
+            Phase::Before => writeln!(
+                output,
+                "{indent}{}  {}",
+                trace.expr.repr(),
+                trace.stack.repr()
+            ),
+            Phase::After => writeln!(output, "{indent}  -> {}", trace.stack.repr()),
+        };
+    }
+}
+
+/// Line numbers of char offsets in a source
+#[derive(Debug, Clone)]
+pub struct LineIndex {
+    starts: Vec<usize>,
+}
+
+impl LineIndex {
+    pub fn new(source: &str) -> Self {
+        let newlines = source
+            .chars()
+            .enumerate()
+            .filter(|(_, c)| *c == '\n')
+            .map(|(i, _)| i + 1);
+        LineIndex {
+            starts: std::iter::once(0).chain(newlines).collect(),
+        }
+    }
+
+    /// Line of a char offset, starting at 1
+    pub fn line(&self, offset: usize) -> usize {
+        self.starts.partition_point(|start| *start <= offset)
+    }
+
+    /// Line and column of a char offset, both starting at 1
+    pub fn line_col(&self, offset: usize) -> (usize, usize) {
+        let line = self.line(offset);
+        (line, offset - self.starts[line - 1] + 1)
+    }
+
+    /// Char offset where a line starts, starting at 1
+    pub fn start(&self, line: usize) -> usize {
+        self.starts[line - 1]
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Breakpoint {
+    /// Stops before the word is called
+    Word(String),
+    /// Stops when execution reaches the line, and each time a loop on it starts over.
+    /// Lines start at 1.
+    Line(usize),
+}
+
+/// What to do once paused
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+    Continue,
+    /// Stop at the next expression, entering called words
+    StepIn,
+    /// Stop at the next expression of the current word
+    StepOver,
+    /// Stop once the current word returns
+    StepOut,
+    /// Stop the script, its evaluation failing with `Error::Interrupted`
+    Quit,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PauseReason {
+    Step,
+    Breakpoint(Breakpoint),
+}
+
+/// Why and where the engine stopped
+pub struct Pause<'a, 'b> {
+    pub trace: &'a Trace<'b>,
+    pub reason: PauseReason,
+    pub line: Option<usize>,
+}
+
+/// Interactive side of a [`Debugger`], asked what to do whenever the engine stops
+pub trait Frontend {
+    /// Breakpoints can be changed before resuming
+    fn paused(&mut self, pause: &Pause, breakpoints: &mut Vec<Breakpoint>) -> Command;
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Mode {
+    Running,
+    StepIn,
+    StepOver(usize),
+    StepOut(usize),
+}
+
+/// Step debugger driven by the trace hook of an engine.
+///
+/// It starts paused before the first expression, install it with
+/// `engine.set_trace(debugger.into_hook())`.
+pub struct Debugger<F: Frontend> {
+    frontend: F,
+    breakpoints: Vec<Breakpoint>,
+    lines: LineIndex,
+    mode: Mode,
+    line: Option<usize>,
+    /// Char offset of the last known position
+    offset: Option<usize>,
+    interrupt: Option<InterruptHandle>,
+}
+
+impl<F: Frontend> Debugger<F> {
+    /// Debugger for code compiled from `source`
+    pub fn new(source: &str, frontend: F) -> Self {
+        Debugger {
+            frontend,
+            breakpoints: vec![],
+            lines: LineIndex::new(source),
+            mode: Mode::StepIn,
+            line: None,
+            offset: None,
+            interrupt: None,
+        }
+    }
+
+    /// Handle of the debugged engine, raised by [`Command::Quit`]
+    pub fn interrupt(mut self, handle: InterruptHandle) -> Self {
+        self.interrupt = Some(handle);
+        self
+    }
+
+    pub fn break_at(mut self, breakpoint: Breakpoint) -> Self {
+        self.breakpoints.push(breakpoint);
+        self
+    }
+
+    /// Only stop at breakpoints, instead of before the first expression
+    pub fn run_to_breakpoint(mut self) -> Self {
+        self.mode = Mode::Running;
+        self
+    }
+
+    pub fn into_hook(mut self) -> impl FnMut(&Trace) {
+        move |trace| self.on_trace(trace)
+    }
+
+    fn on_trace(&mut self, trace: &Trace) {
+        if trace.phase == Phase::After {
+            return;
+        }
+
+        let offset = trace.position.as_ref().map(|span| span.start);
+        let line = offset.map(|offset| self.lines.line(offset));
+        // A line is hit when entered, and again when a loop jumps back in it
+        let hit_line = line.is_some() && (line != self.line || offset < self.offset);
+        if line.is_some() {
+            self.line = line;
+            self.offset = offset;
+        }
+
+        let reason = match self.mode {
+            Mode::StepIn => Some(PauseReason::Step),
+            Mode::StepOver(depth) if trace.depth <= depth => Some(PauseReason::Step),
+            Mode::StepOut(depth) if trace.depth < depth => Some(PauseReason::Step),
+            _ => self
+                .breakpoints
+                .iter()
+                .find(|b| match b {
+                    Breakpoint::Word(name) => matches!(trace.expr, Expr::Symbol(s) if s == name),
+                    Breakpoint::Line(l) => hit_line && line == Some(*l),
+                })
+                .cloned()
+                .map(PauseReason::Breakpoint),
+        };
+
+        if let Some(reason) = reason {
+            let pause = Pause {
+                trace,
+                reason,
+                line: self.line,
+            };
+            self.mode = match self.frontend.paused(&pause, &mut self.breakpoints) {
+                Command::Continue => Mode::Running,
+                Command::StepIn => Mode::StepIn,
+                Command::StepOver => Mode::StepOver(trace.depth),
+                Command::StepOut => Mode::StepOut(trace.depth),
+                Command::Quit => {
+                    self.breakpoints.clear();
+                    if let Some(handle) = &self.interrupt {
+                        handle.interrupt();
+                    }
+                    Mode::Running
+                }
+            };
+        }
+    }
+}
@@ -0,0 +1,132 @@
+//! Snippets of code checked against the values written in their comments
+//!
+//! A comment line right below a line of code holds the values expected at that point,
+//! written as Fox literals: `# stack: [1 2]` for the whole stack, or `# [4, 5]` for the
+//! values on its top. Other comments are left alone. A `Seq` is checked as the list of
+//! its values.
+//!
+//! The code between two checks is run on its own, so its locals are gone after a check.
+
+use crate::engine::compile::compile;
+use crate::engine::context::CallContext;
+use crate::engine::convert::ToFox;
+use crate::engine::engine::Engine;
+use crate::engine::env::Env;
+use crate::engine::error::Error;
+use crate::engine::value::{List, Value};
+use crate::stdlib;
+use crate::stdlib::seq::Seq;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Failure {
+    /// Line of the snippet, starting at 0
+    pub line: usize,
+    pub message: String,
+}
+
+/// Run a snippet, checking the stack at each comment holding expected values
+pub fn run(engine: &mut Engine, code: &str) -> Result<(), Failure> {
+    let lines: Vec<&str> = code.lines().collect();
+    let mut start = 0;
+    for i in 0..lines.len() {
+        let Some(expected) = expectation(&lines, i) else {
+            continue;
+        };
+        eval(engine, &lines[start..i]).map_err(|message| Failure {
+            line: i - 1,
+            message,
+        })?;
+        start = i + 1;
+
+        let (whole, expected) = match expected.strip_prefix("stack:") {
+            Some(stack) => (true, stack.trim()),
+            None => (false, expected),
+        };
+        check(engine, whole, expected).map_err(|message| Failure { line: i, message })?;
+    }
+
+    eval(engine, &lines[start..]).map_err(|message| Failure {
+        line: lines.len().saturating_sub(1),
+        message,
+    })
+}
+
+/// Text of the comment on a line, if it holds values and follows a line of code
+fn expectation<'a>(lines: &[&'a str], i: usize) -> Option<&'a str> {
+    let text = lines[i].trim_start().strip_prefix('#')?.trim();
+    let previous = lines[..i].last()?.trim();
+    if previous.is_empty() || previous.starts_with('#') {
+        return None;
+    }
+
+    let literal = text.starts_with(|c: char| c.is_ascii_digit() || "-\"'[(".contains(c));
+    match text {
+        "true" | "false" => Some(text),
+        _ if literal || text.starts_with("stack:") => Some(text),
+        _ => None,
+    }
+}
+
+fn eval(engine: &mut Engine, lines: &[&str]) -> Result<(), String> {
+    let ast = crate::parsing::parse(&lines.join("\n")).map_err(|errors| {
+        let errors: Vec<String> = errors.iter().map(ToString::to_string).collect();
+        errors.join(", ")
+    })?;
+    let (functions, body) = compile(&ast);
+    stdlib::register(&mut engine.definitions, functions);
+    engine.eval(&body).map_err(|e: Error| e.to_string())
+}
+
+/// Compare the stack, or its top, to the values of some code
+fn check(engine: &mut Engine, whole: bool, expected: &str) -> Result<(), String> {
+    let values = match whole {
+        true => expected
+            .strip_prefix('[')
+            .and_then(|s| s.strip_suffix(']'))
+            .ok_or_else(|| format!("`{expected}` is not a stack such as `[1 2]`"))?,
+        false => expected,
+    };
+    let mut reference = Engine::builder().build();
+    eval(&mut reference, &[values])?;
+    let expected = reference.stack.values();
+
+    let found = engine.stack.values();
+    let found = match whole {
+        true => found,
+        false => &found[found.len().saturating_sub(expected.len())..],
+    };
+    let found = collect_seqs(engine, found.to_vec()).map_err(|e| e.to_string())?;
+    if same(&found, expected) {
+        return Ok(());
+    }
+    Err(match whole {
+        true => format!(
+            "expected the stack [{}], found [{}]",
+            repr(expected),
+            repr(&found)
+        ),
+        false => format!("expected {}, found {}", repr(expected), repr(&found)),
+    })
+}
+
+/// The values, with the `Seq`s left by lazy words turned into lists
+fn collect_seqs(engine: &mut Engine, values: Vec<Value>) -> Result<Vec<Value>, Error> {
+    let mut locals = Env::new(None);
+    let mut ctx = CallContext::new(engine, &mut locals);
+    values
+        .into_iter()
+        .map(|v| match v.as_native_typed::<Seq>() {
+            Ok(seq) => Ok(List(seq.clone().collect(&mut ctx)?).to_fox()),
+            Err(_) => Ok(v),
+        })
+        .collect()
+}
+
+fn same(a: &[Value], b: &[Value]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).all(|(a, b)| a.data_ref() == b.data_ref())
+}
+
+fn repr(values: &[Value]) -> String {
+    let values: Vec<String> = values.iter().map(|v| v.data_ref().repr()).collect();
+    values.join(" ")
+}
@@ -1,13 +1,53 @@
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::io::{self, BufRead, BufReader, Write};
+
+use crate::engine::budget::{Budget, Limit, DEFAULT_CALL_DEPTH};
+use crate::engine::builder::EngineBuilder;
+use crate::engine::context::CallContext;
+use crate::engine::debug::{CallFrame, Phase, Trace};
 use crate::engine::env::Env;
 use crate::engine::error::Error;
-use crate::engine::expr::Expr;
-use crate::engine::function::Function;
+use crate::engine::expr::{Expr, Pattern};
+use crate::engine::function::{Function, Intrinsic};
+use crate::engine::generator::{Action, Frame, Generator};
+use crate::engine::interrupt::InterruptHandle;
 use crate::engine::stack::Stack;
 use crate::engine::value::{Value, ValueData};
+use crate::parsing::lexer::Span;
+use crate::stdlib::result::error_value;
+use crate::stdlib::seq::to_seq;
 
 pub struct Engine {
     pub definitions: Env<Function>,
     pub stack: Stack,
+    pub budget: Budget,
+    interrupt: InterruptHandle,
+    instructions: u64,
+    call_depth: usize,
+    user_data: HashMap<TypeId, Box<dyn Any>>,
+    memory_checks: u64,
+    position: Option<Span>,
+    output: Box<dyn Write>,
+    error_output: Box<dyn Write>,
+    input: Box<dyn BufRead>,
+    trace: Option<TraceHook>,
+    /// Fox words being run, only tracked while tracing
+    calls: Vec<CallFrame>,
+    /// The `Err` of the `Error::Propagated` being raised
+    propagated: Option<Value>,
+}
+
+/// Number of instructions between two counts of the values on the stack
+const VALUES_CHECK_INTERVAL: u64 = 256;
+
+/// Called before and after each expression is run
+pub type TraceHook = Box<dyn FnMut(&Trace)>;
+
+impl Default for Engine {
+    fn default() -> Self {
+        Engine::new()
+    }
 }
 
 impl Engine {
@@ -15,53 +55,560 @@ impl Engine {
         Engine {
             definitions: Env::new(None),
             stack: Stack::new(),
+            budget: Budget::unlimited(),
+            interrupt: InterruptHandle::new(),
+            instructions: 0,
+            call_depth: 0,
+            user_data: HashMap::new(),
+            memory_checks: 0,
+            position: None,
+            output: Box::new(io::stdout()),
+            error_output: Box::new(io::stderr()),
+            input: Box::new(BufReader::new(io::stdin())),
+            trace: None,
+            calls: vec![],
+            propagated: None,
+        }
+    }
+
+    pub fn with_budget(budget: Budget) -> Self {
+        Engine {
+            budget,
+            ..Engine::new()
         }
     }
 
+    pub fn builder() -> EngineBuilder {
+        EngineBuilder::new()
+    }
+
+    pub fn interrupt_handle(&self) -> InterruptHandle {
+        self.interrupt.clone()
+    }
+
+    /// Store host data reachable by natives through their `CallContext`, one value per type
+    pub fn set_user_data<T: 'static>(&mut self, data: T) {
+        self.user_data.insert(TypeId::of::<T>(), Box::new(data));
+    }
+
+    pub fn user_data<T: 'static>(&self) -> Option<&T> {
+        self.user_data.get(&TypeId::of::<T>())?.downcast_ref()
+    }
+
+    pub fn user_data_mut<T: 'static>(&mut self) -> Option<&mut T> {
+        self.user_data.get_mut(&TypeId::of::<T>())?.downcast_mut()
+    }
+
+    /// Where `print` and `println` write, the process stdout by default
+    pub fn set_output(&mut self, output: impl Write + 'static) {
+        self.output = Box::new(output);
+    }
+
+    /// Where `eprint` writes, the process stderr by default
+    pub fn set_error_output(&mut self, output: impl Write + 'static) {
+        self.error_output = Box::new(output);
+    }
+
+    /// Where `prompt` and `read-line` read, the process stdin by default
+    pub fn set_input(&mut self, input: impl BufRead + 'static) {
+        self.input = Box::new(input);
+    }
+
+    /// Observe every expression run, replacing the previous hook.
+    ///
+    /// Expressions run by a generator are only seen before they run.
+    pub fn set_trace(&mut self, hook: impl FnMut(&Trace) + 'static) {
+        self.trace = Some(Box::new(hook));
+    }
+
+    pub fn clear_trace(&mut self) {
+        self.trace = None;
+    }
+
+    pub(crate) fn output(&mut self) -> &mut dyn Write {
+        &mut self.output
+    }
+
+    pub(crate) fn error_output(&mut self) -> &mut dyn Write {
+        &mut self.error_output
+    }
+
+    pub(crate) fn input(&mut self) -> &mut dyn BufRead {
+        &mut self.input
+    }
+
+    /// Source position of the word being run, when known
+    pub fn position(&self) -> Option<Span> {
+        self.position.clone()
+    }
+
     pub fn eval(&mut self, exprs: &[Expr]) -> Result<(), Error> {
         let mut locals: Env<Value> = Env::new(None);
 
+        self.interrupt.reset();
+        self.instructions = 0;
+        self.call_depth = 0;
+        self.calls.clear();
+        self.propagated = None;
+        self.stack.mark();
+
+        let result = self.exec(exprs, &mut locals);
+
+        // An aborted evaluation leaves the stack as it was before the call
+        match result {
+            Err(Error::Interrupted | Error::BudgetExceeded(_)) => {
+                self.stack.rollback();
+                self.call_depth = 0;
+            }
+            // Forget the values kept for a rollback
+            _ => self.stack.mark(),
+        }
+
+        result
+    }
+
+    fn exec(&mut self, exprs: &[Expr], locals: &mut Env<Value>) -> Result<(), Error> {
         for expr in exprs {
-            println!("{:?}", self.stack);
+            if let Expr::Position(span) = expr {
+                self.position = Some(span.clone());
+                continue;
+            }
+            self.step()?;
+            self.trace(expr, locals, Phase::Before);
             match expr {
                 Expr::Bool(b) => self.stack.push_bool(*b),
                 Expr::Num(n) => self.stack.push_num(*n),
                 Expr::Char(c) => self.stack.push_char(*c),
-                Expr::Symbol(s) => {
-                    if let Some(local) = locals.get(s.as_str()) {
-                        match local.data_ref() {
-                            ValueData::Bool(_)
-                            | ValueData::Char(_)
-                            | ValueData::Num(_)
-                            | ValueData::Native(_) => {
-                                self.stack.push(local.clone());
-                            }
-                            ValueData::Symbol(s) => {
-                                let f = self.definitions.get(&s);
-                                if let Some(f) = f {
-                                    self.call(&f, &mut locals)?;
-                                }
-                            }
+                Expr::String(s) => self.stack.push_string(s),
+                Expr::Quote(items) => self
+                    .stack
+                    .push(ValueData::Quote(items.clone(), locals.clone())),
+                Expr::Local(names) => {
+                    for name in names {
+                        let v = self.stack.pop()?;
+                        locals.set(name.clone(), v);
+                    }
+                }
+                Expr::Field(name) => {
+                    let v = self.stack.pop()?;
+                    let field = v.as_native()?.field(name);
+                    match field {
+                        Some(field) => self.stack.push(field),
+                        None => return Err(Error::UnknownField(v.data_ref().repr(), name.clone())),
+                    }
+                }
+                Expr::Match(arms) => {
+                    let v = self.stack.pop()?;
+                    let mut matched = None;
+                    for (pattern, body) in arms {
+                        if self.matches(pattern, &v, locals) {
+                            matched = Some(body);
+                            break;
                         }
-                    } else if let Some(def) = self.definitions.get(s.as_str()) {
-                        self.call(&def, &mut locals)?;
-                    } else {
-                        return Err(Error::UnknownSymbol(s.to_string()));
+                    }
+                    match matched {
+                        Some(body) => self.exec(body, locals)?,
+                        None => return Err(Error::NoMatch(v.data_ref().repr())),
                     }
                 }
+                Expr::Symbol(s) => self.call_symbol(s, locals)?,
+                Expr::Position(_) => unreachable!(),
             }
+
+            self.trace(expr, locals, Phase::After);
+            self.check_memory()?;
         }
 
         Ok(())
     }
 
+    fn call_symbol(&mut self, s: &str, locals: &mut Env<Value>) -> Result<(), Error> {
+        if let Some(local) = locals.get(s) {
+            match local.data_ref() {
+                ValueData::Bool(_)
+                | ValueData::Char(_)
+                | ValueData::Num(_)
+                | ValueData::Quote(..)
+                | ValueData::Native(_) => {
+                    self.stack.push(local.clone());
+                }
+                ValueData::Symbol(s) => {
+                    let f = self.definitions.get(s);
+                    if let Some(f) = f {
+                        self.call(&f, locals)?;
+                    }
+                }
+            }
+        } else if let Some(def) = self.definitions.get(s) {
+            self.call(&def, locals)?;
+        } else {
+            return Err(Error::UnknownSymbol(s.to_string()));
+        }
+        Ok(())
+    }
+
+    /// Run a quotation, or the word named by a symbol, from a native word
+    pub(crate) fn apply(&mut self, callable: &Value, locals: &mut Env<Value>) -> Result<(), Error> {
+        match callable.data_ref() {
+            ValueData::Quote(items, env) => {
+                self.enter()?;
+                let result = self.exec(items, &mut env.child());
+                self.call_depth -= 1;
+                result
+            }
+            ValueData::Symbol(name) => self.call_symbol(name, locals),
+            _ => Err(Error::CastError("Quote".to_string())),
+        }
+    }
+
+    /// Raise an `Err` to be returned by the enclosing word
+    pub(crate) fn propagate(&mut self, result: Value) -> Error {
+        let repr = result.data_ref().repr();
+        self.propagated = Some(result);
+        Error::Propagated(repr)
+    }
+
+    /// The `Err` of the `Error::Propagated` being raised, once caught
+    pub(crate) fn take_propagated(&mut self) -> Option<Value> {
+        self.propagated.take()
+    }
+
+    pub(crate) fn apply_word(&mut self, name: &str, locals: &mut Env<Value>) -> Result<(), Error> {
+        self.step()?;
+        self.call_symbol(name, locals)
+    }
+
     fn call(&mut self, f: &Function, locals: &mut Env<Value>) -> Result<(), Error> {
         match f {
-            Function::Fox { items, .. } => {
-                self.eval(items.as_slice());
+            Function::Fox { name, items, .. } => {
+                self.enter()?;
+                let calls = self.calls.len();
+                if self.trace.is_some() {
+                    self.calls.push(CallFrame {
+                        name: name.clone(),
+                        call_site: self.position.clone(),
+                    });
+                }
+                let position = self.position.clone();
+                let mut locals = Env::new(None);
+                let result = self.exec(items.as_slice(), &mut locals);
+                self.call_depth -= 1;
+                self.calls.truncate(calls);
+                // Errors raised in the word keep their own position
+                if matches!(result, Ok(()) | Err(Error::Propagated(_))) {
+                    self.position = position;
+                }
+                match result {
+                    Err(Error::Propagated(_)) => {
+                        if let Some(err) = self.propagated.take() {
+                            self.stack.push(err);
+                        }
+                        Ok(())
+                    }
+                    result => result,
+                }
+            }
+            Function::Native { handler, .. } => handler(&mut CallContext::new(self, locals)),
+        }
+    }
+
+    /// Run a generator on its own stack until it yields a value or returns.
+    ///
+    /// Bodies are run as frames, so `yield` can suspend them. Words, quotations run by `??`,
+    /// `apply`, `for-each` and `try` and match arms get their own frame, anything else runs
+    /// as usual and can't yield.
+    pub(crate) fn resume(&mut self, generator: &mut Generator) -> Result<Option<Value>, Error> {
+        std::mem::swap(&mut self.stack, &mut generator.stack);
+        let result = self.run_frames(&mut generator.frames);
+        std::mem::swap(&mut self.stack, &mut generator.stack);
+
+        if result.is_err() {
+            generator.frames.clear();
+        }
+        result
+    }
+
+    fn run_frames(&mut self, frames: &mut Vec<Frame>) -> Result<Option<Value>, Error> {
+        let depth = self.call_depth;
+        loop {
+            let Some(frame) = frames.last_mut() else {
+                return Ok(None);
+            };
+            let result = if !frame.is_done() {
+                let exprs = frame.exprs.clone();
+                frame.pc += 1;
+                self.frame_step(&exprs[frame.pc - 1], frames, depth)
+            } else if let Some(Action::ForEach { .. }) = frame.action {
+                self.for_each_step(frames, depth).map(|()| None)
+            } else {
+                if frame.function {
+                    self.position = frame.call_site.clone();
+                }
+                frames.pop();
+                continue;
+            };
+
+            match result {
+                Ok(Some(value)) => return Ok(Some(value)),
+                Ok(None) => self.check_memory()?,
+                Err(e) => self.unwind(frames, e, depth)?,
+            }
+        }
+    }
+
+    /// Hand `error` to the innermost `try` or, when raised by `try?`, to the enclosing word.
+    /// Like `call`, the word returns the error as its result.
+    fn unwind(&mut self, frames: &mut Vec<Frame>, error: Error, depth: usize) -> Result<(), Error> {
+        if matches!(error, Error::BudgetExceeded(_) | Error::Interrupted) {
+            return Err(error);
+        }
+        let propagated = matches!(error, Error::Propagated(_));
+        let boundary = frames.iter().rposition(|f| {
+            matches!(f.action, Some(Action::Catch { .. })) || (propagated && f.function)
+        });
+        let Some(boundary) = boundary else {
+            return Err(error);
+        };
+        frames.truncate(boundary + 1);
+        let frame = frames.pop().expect("the frame catching the error");
+
+        match frame.action {
+            Some(Action::Catch { stack, handler }) => {
+                self.stack = stack;
+                let error = error_value(error, self.propagated.take());
+                self.stack.push(error);
+                self.push_quote(frames, &handler, depth)
+            }
+            _ => {
+                self.position = frame.call_site;
+                if let Some(err) = self.propagated.take() {
+                    self.stack.push(err);
+                }
                 Ok(())
             }
-            Function::Native { handler, .. } => handler(&self.definitions, locals, &mut self.stack),
         }
     }
+
+    /// Run the quotation of the `for-each` frame on top on the next value, or drop the frame
+    fn for_each_step(&mut self, frames: &mut Vec<Frame>, depth: usize) -> Result<(), Error> {
+        let Some(Action::ForEach { seq, quote }) =
+            frames.last_mut().and_then(|f| f.action.as_mut())
+        else {
+            unreachable!("the top frame runs `for-each`")
+        };
+        let mut locals = Env::new(None);
+        match seq.next(&mut CallContext::new(self, &mut locals))? {
+            Some(v) => {
+                let quote = quote.clone();
+                self.stack.push(v);
+                self.push_quote(frames, &quote, depth)
+            }
+            None => {
+                frames.pop();
+                Ok(())
+            }
+        }
+    }
+
+    /// Run one expression of the top frame, returning the value passed to `yield`
+    fn frame_step(
+        &mut self,
+        expr: &Expr,
+        frames: &mut Vec<Frame>,
+        depth: usize,
+    ) -> Result<Option<Value>, Error> {
+        let locals = Self::frame_locals(frames);
+        match expr {
+            Expr::Position(span) => self.position = Some(span.clone()),
+            Expr::Match(arms) => {
+                self.step()?;
+                self.trace(expr, locals, Phase::Before);
+                let v = self.stack.pop()?;
+                let body = arms
+                    .iter()
+                    .find(|(pattern, _)| self.matches(pattern, &v, locals))
+                    .map(|(_, body)| body);
+                match body {
+                    Some(body) => self.push_frame(frames, Frame::new(body, None, false), depth)?,
+                    None => return Err(Error::NoMatch(v.data_ref().repr())),
+                }
+            }
+            Expr::Symbol(s) if !locals.has(s) => {
+                self.step()?;
+                self.trace(expr, locals, Phase::Before);
+                match self.definitions.get(s) {
+                    Some(Function::Fox { items, .. }) => {
+                        // Finished frames are dropped first so endless recursion runs in
+                        // constant space, the call returning where theirs would have
+                        let mut call_site = self.position.clone();
+                        while frames.last().is_some_and(Frame::is_finished) {
+                            let done = frames.pop().expect("a finished frame");
+                            if done.function {
+                                call_site = done.call_site;
+                            }
+                        }
+                        let mut frame = Frame::new(&items, Some(Env::new(None)), true);
+                        frame.call_site = call_site;
+                        self.push_frame(frames, frame, depth)?;
+                    }
+                    Some(Function::Native {
+                        intrinsic: Some(intrinsic),
+                        ..
+                    }) => match intrinsic {
+                        Intrinsic::Yield => return Ok(Some(self.stack.pop()?)),
+                        Intrinsic::Choose => {
+                            let otherwise = self.stack.pop()?;
+                            let then = self.stack.pop()?;
+                            let cond = self.stack.pop()?.as_bool()?;
+                            let quote = if cond { then } else { otherwise };
+                            self.push_quote(frames, &quote, depth)?;
+                        }
+                        Intrinsic::Apply => {
+                            let quote = self.stack.pop()?;
+                            self.push_quote(frames, &quote, depth)?;
+                        }
+                        Intrinsic::ForEach => {
+                            let quote = self.stack.pop()?;
+                            let seq = to_seq(&self.stack.pop()?)?;
+                            let frame = Frame::action(Action::ForEach { seq, quote });
+                            self.push_frame(frames, frame, depth)?;
+                        }
+                        Intrinsic::Try => {
+                            let handler = self.stack.pop()?;
+                            let body = self.stack.pop()?;
+                            let stack = self.stack.clone();
+                            let frame = Frame::action(Action::Catch { stack, handler });
+                            self.push_frame(frames, frame, depth)?;
+                            self.push_quote(frames, &body, depth)?;
+                        }
+                    },
+                    Some(f) => self.call(&f, locals)?,
+                    None => return Err(Error::UnknownSymbol(s.to_string())),
+                }
+            }
+            _ => self.exec(std::slice::from_ref(expr), locals)?,
+        }
+        Ok(None)
+    }
+
+    /// Locals of the innermost word or quotation, match arms have none of their own
+    fn frame_locals(frames: &mut [Frame]) -> &mut Env<Value> {
+        frames
+            .iter_mut()
+            .rev()
+            .find_map(|f| f.locals.as_mut())
+            .expect("the root frame of a generator has locals")
+    }
+
+    fn push_quote(
+        &mut self,
+        frames: &mut Vec<Frame>,
+        callable: &Value,
+        depth: usize,
+    ) -> Result<(), Error> {
+        match callable.data_ref() {
+            ValueData::Quote(items, env) => {
+                let frame = Frame::new(items, Some(env.child()), false);
+                self.push_frame(frames, frame, depth)
+            }
+            _ => self.apply(callable, Self::frame_locals(frames)),
+        }
+    }
+
+    fn push_frame(
+        &mut self,
+        frames: &mut Vec<Frame>,
+        frame: Frame,
+        depth: usize,
+    ) -> Result<(), Error> {
+        let max = self.max_call_depth();
+        if depth + frames.len() > max {
+            return Err(Error::BudgetExceeded(Limit::CallDepth(max)));
+        }
+        frames.push(frame);
+        Ok(())
+    }
+
+    /// Check `value` against `pattern`, pushing the fields of a matched variant
+    fn matches(&mut self, pattern: &Pattern, value: &Value, locals: &mut Env<Value>) -> bool {
+        match pattern {
+            Pattern::Wildcard => true,
+            Pattern::Bind(name) => {
+                locals.set(name.clone(), value.clone());
+                true
+            }
+            Pattern::Value(v) => v == value,
+            Pattern::Variant(name) => {
+                let variant = value.as_native().ok().and_then(|o| o.variant());
+                match variant {
+                    Some((variant, fields)) if variant == name => {
+                        for field in fields {
+                            self.stack.push(field);
+                        }
+                        true
+                    }
+                    _ => false,
+                }
+            }
+        }
+    }
+
+    fn trace(&mut self, expr: &Expr, locals: &Env<Value>, phase: Phase) {
+        if let Some(hook) = &mut self.trace {
+            hook(&Trace {
+                expr,
+                phase,
+                stack: &self.stack,
+                locals,
+                depth: self.call_depth,
+                position: self.position.clone(),
+                calls: &self.calls,
+            });
+        }
+    }
+
+    pub(crate) fn step(&mut self) -> Result<(), Error> {
+        if self.interrupt.is_interrupted() {
+            return Err(Error::Interrupted);
+        }
+
+        self.instructions += 1;
+        match self.budget.max_instructions {
+            Some(max) if self.instructions > max => {
+                Err(Error::BudgetExceeded(Limit::Instructions(max)))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn enter(&mut self) -> Result<(), Error> {
+        let max = self.max_call_depth();
+        if self.call_depth >= max {
+            return Err(Error::BudgetExceeded(Limit::CallDepth(max)));
+        }
+        self.call_depth += 1;
+        Ok(())
+    }
+
+    fn max_call_depth(&self) -> usize {
+        self.budget.max_call_depth.unwrap_or(DEFAULT_CALL_DEPTH)
+    }
+
+    fn check_memory(&mut self) -> Result<(), Error> {
+        if let Some(max) = self.budget.max_stack_depth {
+            if self.stack.len() > max {
+                return Err(Error::BudgetExceeded(Limit::StackDepth(max)));
+            }
+        }
+        if let Some(max) = self.budget.max_values {
+            // Counting walks the whole stack, so it is only done once in a while
+            self.memory_checks += 1;
+            if self.memory_checks.is_multiple_of(VALUES_CHECK_INTERVAL)
+                && self.stack.value_count() > max
+            {
+                return Err(Error::BudgetExceeded(Limit::Values(max)));
+            }
+        }
+        Ok(())
+    }
 }
@@ -1,6 +1,6 @@
 use std::collections::HashMap;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Env<T> {
     env: HashMap<String, T>,
     parent: Option<Box<Env<T>>>,
@@ -14,8 +14,13 @@ impl<T: Clone> Env<T> {
         }
     }
 
+    /// New empty scope on top of a copy of this one
+    pub fn child(&self) -> Self {
+        Env::new(Some(Box::new(self.clone())))
+    }
+
     pub fn has(&self, key: &str) -> bool {
-        self.env.contains_key(key) || self.parent.as_ref().map_or(false, |p| p.has(key))
+        self.env.contains_key(key) || self.parent.as_ref().is_some_and(|p| p.has(key))
     }
 
     pub fn get(&self, key: &str) -> Option<T> {
@@ -28,4 +33,11 @@ impl<T: Clone> Env<T> {
     pub fn set(&mut self, key: String, value: T) {
         self.env.insert(key, value);
     }
+
+    /// Entries defined in this scope, sorted by name
+    pub fn entries(&self) -> Vec<(&str, &T)> {
+        let mut entries: Vec<(&str, &T)> = self.env.iter().map(|(k, v)| (k.as_str(), v)).collect();
+        entries.sort_by_key(|(k, _)| *k);
+        entries
+    }
 }
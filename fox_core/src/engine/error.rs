@@ -1,5 +1,8 @@
 use thiserror::Error;
 
+use crate::engine::budget::Limit;
+use crate::parsing::lexer::Span;
+
 #[derive(Debug, Error)]
 pub enum Error {
     #[error("Stack error: {0}")]
@@ -8,4 +11,25 @@ pub enum Error {
     CastError(String),
     #[error("The symbol `{0}` is not defined")]
     UnknownSymbol(String),
+    #[error("Execution budget exceeded: {0}")]
+    BudgetExceeded(Limit),
+    #[error("Evaluation was interrupted")]
+    Interrupted,
+    #[error("Permission denied: `{0}` {1}")]
+    PermissionDenied(String, String),
+    #[error("IO error: {0}")]
+    IoError(String),
+    #[error("No match arm for `{0}`")]
+    NoMatch(String),
+    #[error("`{0}` has no field `{1}`")]
+    UnknownField(String, String),
+    #[error("`yield` can only be used in a generator")]
+    YieldOutsideGenerator,
+    /// Raised by `panic` and assertions, with the position of the failing word
+    #[error("{0}")]
+    User(String, Option<Span>),
+    /// An `Err` returned early by `try?`, caught at the boundary of the enclosing word.
+    /// Holds its repr, the engine keeps the value itself.
+    #[error("Unhandled error: {0}")]
+    Propagated(String),
 }
@@ -1,26 +1,66 @@
-#[derive(Debug, Clone)]
+use crate::engine::value::Value;
+use crate::parsing::lexer::Span;
+
+#[derive(Debug, Clone, PartialEq)]
 pub enum Expr {
     Bool(bool),
     Num(f64),
     Char(char),
+    String(String),
     Symbol(String),
+    Local(Vec<String>),
+    Quote(Vec<Expr>),
+    /// Pushes a field of the record on top of the stack
+    Field(String),
+    Match(Vec<(Pattern, Vec<Expr>)>),
+    /// Source position of the next expression, not counted as an instruction
+    Position(Span),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Pattern {
+    Variant(String),
+    Value(Value),
+    Bind(String),
+    Wildcard,
 }
 
 impl Expr {
     pub fn bool(b: bool) -> Self {
-        Self::Bool(b).into()
+        Self::Bool(b)
     }
 
     pub fn char(c: char) -> Self {
-        Self::Char(c).into()
+        Self::Char(c)
     }
 
     pub fn num(n: f64) -> Self {
-        Self::Num(n).into()
+        Self::Num(n)
+    }
+
+    pub fn string(s: impl Into<String>) -> Self {
+        Self::String(s.into())
     }
 
     pub fn symbol(s: impl Into<String>) -> Self {
-        Self::Symbol(s.into()).into()
+        Self::Symbol(s.into())
+    }
+
+    pub fn local(names: &[&str]) -> Self {
+        Self::Local(names.iter().map(|n| n.to_string()).collect())
+    }
+
+    pub fn quote(items: Vec<Expr>) -> Self {
+        Self::Quote(items)
+    }
+
+    fn repr_all(exprs: &[Expr]) -> String {
+        let items: Vec<String> = exprs
+            .iter()
+            .filter(|e| !matches!(e, Expr::Position(_)))
+            .map(Expr::repr)
+            .collect();
+        items.join(" ")
     }
 
     pub fn repr(&self) -> String {
@@ -28,7 +68,31 @@ impl Expr {
             Expr::Bool(v) => v.to_string(),
             Expr::Num(v) => v.to_string(),
             Expr::Char(v) => v.to_string(),
+            Expr::String(v) => format!("{v:?}"),
             Expr::Symbol(v) => v.to_string(),
+            Expr::Local(v) => format!("-> {}", v.join(",")),
+            Expr::Quote(v) => format!("{{ {} }}", Expr::repr_all(v)),
+            Expr::Field(v) => format!(".{v}"),
+            Expr::Match(arms) => {
+                let arms: Vec<String> = arms
+                    .iter()
+                    .map(|(pattern, body)| {
+                        format!("| {} {{ {} }}", pattern.repr(), Expr::repr_all(body))
+                    })
+                    .collect();
+                format!("match {}", arms.join(" "))
+            }
+            Expr::Position(_) => String::new(),
+        }
+    }
+}
+
+impl Pattern {
+    pub fn repr(&self) -> String {
+        match self {
+            Pattern::Variant(v) | Pattern::Bind(v) => v.to_string(),
+            Pattern::Value(v) => v.data_ref().repr(),
+            Pattern::Wildcard => "_".to_string(),
         }
     }
 }
@@ -1,22 +1,40 @@
-use crate::engine::env::Env;
+use crate::engine::capability::Capability;
+use crate::engine::context::CallContext;
 use crate::engine::error::Error;
 use crate::engine::expr::Expr;
-use crate::engine::stack::Stack;
-use crate::engine::value::Value;
-use std::fmt::{Debug, Formatter, Write};
+use crate::engine::typing::StackEffect;
+use std::fmt::{Debug, Formatter};
+use std::rc::Rc;
+
+pub type NativeHandler = Rc<dyn Fn(&mut CallContext) -> Result<(), Error>>;
 
 #[derive(Clone)]
 pub enum Function {
     Fox {
         name: String,
         items: Vec<Expr>,
+        signature: Option<StackEffect>,
     },
     Native {
         name: String,
-        handler: fn(&Env<Function>, &Env<Value>, &mut Stack) -> Result<(), Error>,
+        handler: NativeHandler,
+        signature: Option<StackEffect>,
+        intrinsic: Option<Intrinsic>,
     },
 }
 
+/// Natives run by the engine itself inside a generator, so a `yield` in the quotations
+/// they run can suspend it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Intrinsic {
+    Yield,
+    /// `cond then otherwise ??`
+    Choose,
+    Apply,
+    ForEach,
+    Try,
+}
+
 impl Debug for Function {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         f.write_str("Function")
@@ -25,32 +43,66 @@ impl Debug for Function {
 
 impl Function {
     pub fn fox(name: String, items: Vec<Expr>) -> Self {
-        Function::Fox { name, items }
+        Function::Fox {
+            name,
+            items,
+            signature: None,
+        }
     }
 
     pub fn native(
         name: impl Into<String>,
-        handler: fn(&Env<Function>, &Env<Value>, &mut Stack) -> Result<(), Error>,
+        handler: impl Fn(&mut CallContext) -> Result<(), Error> + 'static,
     ) -> Self
 where {
         Function::Native {
             name: name.into(),
-            handler,
+            handler: Rc::new(handler),
+            signature: None,
+            intrinsic: None,
         }
     }
 
-    pub fn call(
-        &self,
-        definitions: &Env<Function>,
-        locals: &Env<Value>,
-        stack: &mut Stack,
-    ) -> Result<(), Error> {
+    pub(crate) fn with_intrinsic(mut self, tag: Intrinsic) -> Self {
+        if let Function::Native { intrinsic, .. } = &mut self {
+            *intrinsic = Some(tag);
+        }
+        self
+    }
+
+    /// A native that always fails because `capability` was not granted to the engine.
+    pub fn denied(name: impl Into<String>, capability: Capability) -> Self {
+        let name = name.into();
+        let word = name.clone();
+        Function::native(name, move |_| {
+            Err(Error::PermissionDenied(
+                word.clone(),
+                format!("requires the `{capability}` capability"),
+            ))
+        })
+    }
+
+    pub fn name(&self) -> &str {
         match self {
-            Function::Fox { name: _, items: _ } => {
-                // Implement the behavior for Fox functions
-                unimplemented!();
+            Function::Fox { name, .. } => name,
+            Function::Native { name, .. } => name,
+        }
+    }
+
+    pub fn signature(&self) -> Option<&StackEffect> {
+        match self {
+            Function::Fox { signature, .. } | Function::Native { signature, .. } => {
+                signature.as_ref()
+            }
+        }
+    }
+
+    pub fn with_signature(mut self, effect: StackEffect) -> Self {
+        match &mut self {
+            Function::Fox { signature, .. } | Function::Native { signature, .. } => {
+                *signature = Some(effect)
             }
-            Function::Native { name, handler } => handler(definitions, locals, stack),
         }
+        self
     }
 }
@@ -0,0 +1,93 @@
+use std::rc::Rc;
+
+use crate::engine::context::CallContext;
+use crate::engine::env::Env;
+use crate::engine::error::Error;
+use crate::engine::expr::Expr;
+use crate::engine::stack::Stack;
+use crate::engine::value::Value;
+use crate::parsing::lexer::Span;
+use crate::stdlib::seq::{Seq, SeqSource};
+
+/// Fox code suspended at a `yield`, resumed each time a value is pulled.
+///
+/// Its frames and stack live outside the Rust call stack so they survive between values.
+#[derive(Debug, Clone)]
+pub struct Generator {
+    pub(crate) frames: Vec<Frame>,
+    pub(crate) stack: Stack,
+}
+
+/// A body being run, with the index of its next expression
+#[derive(Debug, Clone)]
+pub(crate) struct Frame {
+    pub(crate) exprs: Rc<[Expr]>,
+    pub(crate) pc: usize,
+    /// `None` for match arms, which share the locals of the frame below
+    pub(crate) locals: Option<Env<Value>>,
+    /// Word boundary catching the errors propagated by `try?`
+    pub(crate) function: bool,
+    /// Position a word was called from, restored when it returns
+    pub(crate) call_site: Option<Span>,
+    /// Native waiting below the quotations it runs
+    pub(crate) action: Option<Action>,
+}
+
+/// Natives run as frames, so a `yield` in their quotations can suspend the generator
+#[derive(Debug, Clone)]
+pub(crate) enum Action {
+    /// `for-each`, running `quote` on each value pulled from `seq`
+    ForEach { seq: Seq, quote: Value },
+    /// `try`, restoring `stack` and running `handler` if a frame above it fails
+    Catch { stack: Stack, handler: Value },
+}
+
+impl Frame {
+    pub(crate) fn new(exprs: &[Expr], locals: Option<Env<Value>>, function: bool) -> Self {
+        Frame {
+            exprs: exprs.into(),
+            pc: 0,
+            locals,
+            function,
+            call_site: None,
+            action: None,
+        }
+    }
+
+    /// Frame with no body of its own, running `action` through the frames above it
+    pub(crate) fn action(action: Action) -> Self {
+        Frame {
+            action: Some(action),
+            ..Frame::new(&[], None, false)
+        }
+    }
+
+    pub(crate) fn is_done(&self) -> bool {
+        self.pc >= self.exprs.len()
+    }
+
+    /// Done, with nothing left to run once the frames above it return
+    pub(crate) fn is_finished(&self) -> bool {
+        self.is_done() && self.action.is_none()
+    }
+}
+
+impl Generator {
+    /// Generator running `body` on its own stack, seeing a copy of `locals`
+    pub fn new(body: &[Expr], locals: Env<Value>) -> Self {
+        Generator {
+            frames: vec![Frame::new(body, Some(locals), false)],
+            stack: Stack::new(),
+        }
+    }
+}
+
+impl SeqSource for Generator {
+    fn next(&mut self, ctx: &mut CallContext) -> Result<Option<Value>, Error> {
+        ctx.resume(self)
+    }
+
+    fn cloned(&self) -> Box<dyn SeqSource> {
+        Box::new(self.clone())
+    }
+}
@@ -0,0 +1,30 @@
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+
+/// Thread-safe handle used to abort a running evaluation.
+///
+/// The engine checks the flag before every instruction and fails with
+/// [`Error::Interrupted`](crate::engine::error::Error::Interrupted) once it is raised.
+/// The flag is lowered when an evaluation starts, so it only stops the running one.
+#[derive(Debug, Clone, Default)]
+pub struct InterruptHandle(Arc<AtomicBool>);
+
+impl InterruptHandle {
+    pub fn new() -> Self {
+        InterruptHandle::default()
+    }
+
+    pub fn interrupt(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_interrupted(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+
+    pub(crate) fn reset(&self) {
+        self.0.store(false, Ordering::SeqCst);
+    }
+}
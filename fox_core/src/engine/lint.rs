@@ -0,0 +1,319 @@
+//! Style and correctness checks over a parsed file
+//!
+//! A lint is silenced by a `# allow(rule, ...)` comment on its line or on the line above.
+
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use crate::engine::compile::qualified;
+use crate::engine::debug::LineIndex;
+use crate::engine::env::Env;
+use crate::engine::function::Function;
+use crate::engine::typing::{Signatures, StackEffect};
+use crate::parsing::lexer::{self, Span, Token};
+use crate::parsing::parser::{Ast, Definition, Expr};
+
+/// Native words by full name, with their effect when declared
+pub type Natives = HashMap<String, Option<StackEffect>>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Rule {
+    /// `swap swap` does nothing
+    SwapSwap,
+    /// `dup drop` does nothing
+    DupDrop,
+    /// A local bound by `->` is never used
+    UnusedLocal,
+    /// A word is defined twice, or redefines a native word
+    ShadowedDefinition,
+    /// The body of a word doesn't take or leave as many values as declared
+    EffectMismatch,
+}
+
+impl Rule {
+    pub const ALL: [Rule; 5] = [
+        Rule::SwapSwap,
+        Rule::DupDrop,
+        Rule::UnusedLocal,
+        Rule::ShadowedDefinition,
+        Rule::EffectMismatch,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Rule::SwapSwap => "swap-swap",
+            Rule::DupDrop => "dup-drop",
+            Rule::UnusedLocal => "unused-local",
+            Rule::ShadowedDefinition => "shadowed-definition",
+            Rule::EffectMismatch => "effect-mismatch",
+        }
+    }
+}
+
+impl Display for Rule {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for Rule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Rule::ALL
+            .into_iter()
+            .find(|rule| rule.name() == s)
+            .ok_or_else(|| format!("Unknown lint `{s}`"))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Lint {
+    pub rule: Rule,
+    pub span: Span,
+    pub message: String,
+    pub fix: Option<Fix>,
+}
+
+/// Replacement of the source in `span`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fix {
+    pub span: Span,
+    pub replacement: String,
+}
+
+pub fn natives(definitions: &Env<Function>) -> Natives {
+    definitions
+        .entries()
+        .into_iter()
+        .map(|(name, f)| (name.to_string(), f.signature().cloned()))
+        .collect()
+}
+
+/// Lints of a file in source order, except the ones allowed by a comment
+pub fn lint(source: &str, ast: &Ast, natives: &Natives) -> Vec<Lint> {
+    let mut linter = Linter {
+        chars: source.chars().collect(),
+        lines: LineIndex::new(source),
+        natives,
+        lints: vec![],
+    };
+    linter.definitions(ast);
+    for def in &ast.defs {
+        if let Definition::Function(f) = def {
+            linter.body(&f.body, &[]);
+        }
+    }
+    for test in &ast.tests {
+        linter.body(&test.body, &[]);
+    }
+    linter.body(&ast.body, &[]);
+
+    let allowed = allowed(source);
+    let mut lints: Vec<Lint> = linter
+        .lints
+        .into_iter()
+        .filter(|lint| {
+            let line = linter.lines.line(lint.span.start);
+            !allowed.contains(&(line, lint.rule)) && !allowed.contains(&(line - 1, lint.rule))
+        })
+        .collect();
+    lints.sort_by_key(|lint| lint.span.start);
+    lints
+}
+
+/// Apply the fixes of some lints, skipping the ones overlapping an earlier fix
+///
+/// Spaces left around removed code are removed too.
+pub fn fix(source: &str, lints: &[Lint]) -> String {
+    let mut fixes: Vec<&Fix> = lints.iter().filter_map(|lint| lint.fix.as_ref()).collect();
+    fixes.sort_by_key(|fix| fix.span.start);
+
+    let mut chars: Vec<char> = source.chars().collect();
+    let mut applied: Vec<&Fix> = vec![];
+    for fix in fixes {
+        if applied
+            .last()
+            .is_none_or(|last| last.span.end <= fix.span.start)
+        {
+            applied.push(fix);
+        }
+    }
+    for fix in applied.into_iter().rev() {
+        let (mut start, mut end) = (fix.span.start, fix.span.end);
+        if fix.replacement.is_empty() {
+            while chars.get(end) == Some(&' ') {
+                end += 1;
+            }
+            if end == chars.len() || chars[end] == '\n' {
+                while start > 0 && chars[start - 1] == ' ' {
+                    start -= 1;
+                }
+            }
+        }
+        chars.splice(start..end, fix.replacement.chars());
+    }
+    chars.into_iter().collect()
+}
+
+/// Lines starting at 1 and the rules allowed on them
+fn allowed(source: &str) -> Vec<(usize, Rule)> {
+    let Ok(tokens) = lexer::lex_with_comments(source) else {
+        return vec![];
+    };
+    let lines = LineIndex::new(source);
+    tokens
+        .into_iter()
+        .filter_map(|(token, span)| match token {
+            Token::Comment(text) => {
+                let rules = text
+                    .trim()
+                    .strip_prefix("allow(")?
+                    .strip_suffix(')')?
+                    .to_string();
+                Some((lines.line(span.start), rules))
+            }
+            _ => None,
+        })
+        .flat_map(|(line, rules)| {
+            rules
+                .split(',')
+                .filter_map(|rule| rule.trim().parse().ok())
+                .map(|rule| (line, rule))
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+struct Linter<'a> {
+    chars: Vec<char>,
+    lines: LineIndex,
+    natives: &'a Natives,
+    lints: Vec<Lint>,
+}
+
+impl Linter<'_> {
+    fn push(&mut self, rule: Rule, span: Span, message: String, fix: Option<Fix>) {
+        self.lints.push(Lint {
+            rule,
+            span,
+            message,
+            fix,
+        });
+    }
+
+    fn definitions(&mut self, ast: &Ast) {
+        let natives = self.natives;
+        let mut signatures = Signatures::new(ast, |name| natives.get(name).cloned().flatten());
+        let mut defined: HashMap<&str, usize> = HashMap::new();
+
+        for def in &ast.defs {
+            let Definition::Function(f) = def else {
+                continue;
+            };
+
+            if let Some(first) = defined.insert(&f.name, f.span.start) {
+                let line = self.lines.line(first);
+                let message = format!("`{}` is already defined on line {line}", f.name);
+                self.push(Rule::ShadowedDefinition, f.span.clone(), message, None);
+            } else if natives.contains_key(&f.name) {
+                let message = format!("`{}` redefines a native word", f.name);
+                self.push(Rule::ShadowedDefinition, f.span.clone(), message, None);
+            }
+
+            let Some(declared) = f.ftype.as_ref().map(StackEffect::from) else {
+                continue;
+            };
+            let Some(inferred) = signatures.infer(&f.body) else {
+                continue;
+            };
+            if declared.inputs.len() != inferred.inputs.len()
+                || declared.outputs.len() != inferred.outputs.len()
+            {
+                let message = format!(
+                    "`{}` is declared {declared} but its body is {inferred}",
+                    f.name
+                );
+                self.push(Rule::EffectMismatch, f.span.clone(), message, None);
+            }
+        }
+    }
+
+    /// Check some code, `rest` being the code run after it in the enclosing bodies
+    fn body(&mut self, body: &[Expr], rest: &[&[Expr]]) {
+        for pair in body.windows(2) {
+            self.peephole(&pair[0], &pair[1]);
+        }
+
+        for (i, expr) in body.iter().enumerate() {
+            let rest: Vec<&[Expr]> = std::iter::once(&body[i + 1..])
+                .chain(rest.iter().copied())
+                .collect();
+            match expr {
+                Expr::Local { names, span } => {
+                    let unused: Vec<&str> = names
+                        .iter()
+                        .filter(|name| !name.starts_with('_'))
+                        .filter(|name| !rest.iter().any(|exprs| uses(exprs, name)))
+                        .map(String::as_str)
+                        .collect();
+                    if !unused.is_empty() {
+                        let message = format!("`{}` is never used", unused.join("`, `"));
+                        self.push(Rule::UnusedLocal, span.clone(), message, None);
+                    }
+                }
+                Expr::Quote(items) => self.body(items, &rest),
+                Expr::Match(arms) => {
+                    for arm in arms {
+                        self.body(&arm.body, &rest);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Two adjacent words cancelling each other
+    fn peephole(&mut self, first: &Expr, second: &Expr) {
+        let (Some((a, a_span)), Some((b, b_span))) = (word(first), word(second)) else {
+            return;
+        };
+        let between = &self.chars[a_span.end..b_span.start];
+        if !between.iter().all(|c| c.is_whitespace()) {
+            return;
+        }
+
+        let rule = match (a.as_str(), b.as_str()) {
+            ("swap", "swap") => Rule::SwapSwap,
+            ("dup", "drop") => Rule::DupDrop,
+            _ => return,
+        };
+        let span = a_span.start..b_span.end;
+        let message = format!("`{a} {b}` does nothing");
+        let fix = Fix {
+            span: span.clone(),
+            replacement: String::new(),
+        };
+        self.push(rule, span, message, Some(fix));
+    }
+}
+
+/// Full name and span of a word written in the source
+fn word(expr: &Expr) -> Option<(String, Span)> {
+    match expr {
+        Expr::Term { name, module, span } if !span.is_empty() => {
+            Some((qualified(module, name), span.clone()))
+        }
+        _ => None,
+    }
+}
+
+fn uses(body: &[Expr], local: &str) -> bool {
+    body.iter().any(|expr| match expr {
+        Expr::Term { name, module, .. } => module.is_empty() && name == local,
+        Expr::Quote(items) => uses(items, local),
+        Expr::Match(arms) => arms.iter().any(|arm| uses(&arm.body, local)),
+        _ => false,
+    })
+}
@@ -1,8 +1,25 @@
+pub mod adt;
+pub mod budget;
+pub mod builder;
+pub mod capability;
+pub mod compile;
+pub mod context;
+pub mod convert;
+pub mod debug;
+pub mod doctest;
+// `engine::engine::Engine` is the path embedders use
+#[allow(clippy::module_inception)]
 pub mod engine;
 pub mod env;
 pub mod error;
 pub mod expr;
 pub mod function;
+pub mod generator;
+pub mod interrupt;
+pub mod lint;
+pub mod native;
+#[cfg(feature = "serde")]
+pub mod serialize;
 pub mod stack;
 pub mod typing;
 pub mod value;
@@ -0,0 +1,40 @@
+use crate::engine::convert::{FromFox, IntoReturn};
+use crate::engine::function::Function;
+
+/// Rust functions usable as native words.
+///
+/// Arguments are popped from the stack, the last argument being the topmost value,
+/// and the return value is pushed back.
+pub trait IntoNative<Args> {
+    fn into_native(self, name: &str) -> Function;
+}
+
+macro_rules! impl_into_native {
+    ($($arg:ident),*) => {
+        impl<Func, Ret, $($arg),*> IntoNative<($($arg,)*)> for Func
+        where
+            Func: Fn($($arg),*) -> Ret + 'static,
+            Ret: IntoReturn,
+            $($arg: FromFox,)*
+        {
+            #[allow(non_snake_case, unused_mut, unused_variables)]
+            fn into_native(self, name: &str) -> Function {
+                Function::native(name, move |ctx| {
+                    let stack = ctx.stack();
+                    let arity = <[&str]>::len(&[$(stringify!($arg)),*]);
+                    let mut args = stack.pop_n(arity)?.into_iter();
+                    $(let $arg = $arg::from_fox(args.next().unwrap())?;)*
+                    (self)($($arg),*).push_to(stack)
+                })
+            }
+        }
+    };
+}
+
+impl_into_native!();
+impl_into_native!(A);
+impl_into_native!(A, B);
+impl_into_native!(A, B, C);
+impl_into_native!(A, B, C, D);
+impl_into_native!(A, B, C, D, E);
+impl_into_native!(A, B, C, D, E, F);
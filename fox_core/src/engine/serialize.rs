@@ -0,0 +1,703 @@
+use std::fmt::Formatter;
+
+use std::fmt::Display;
+
+use serde::de::value::{MapDeserializer, SeqDeserializer};
+use serde::de::{
+    self, DeserializeOwned, DeserializeSeed, EnumAccess, IntoDeserializer, MapAccess, SeqAccess,
+    VariantAccess, Visitor,
+};
+use serde::ser::{self, SerializeMap};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::engine::adt::Variant;
+use crate::engine::convert::ToFox;
+use crate::engine::value::{List, Value, ValueData};
+use crate::stdlib::table::Table;
+use crate::stdlib::tuple::Tuple;
+
+/// Lists of `Char` are strings, other lists and tuples are sequences and tables are maps.
+///
+/// `Option` is serialized like serde does, other variants are externally tagged:
+/// `None` becomes `"None"`, `Some 1` becomes `{"Some": 1}` and a record variant
+/// `{"Vec2": {"x": 0, "y": 1}}`.
+impl Serialize for Value {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.data_ref() {
+            ValueData::Bool(b) => serializer.serialize_bool(*b),
+            ValueData::Num(n) if n.fract() == 0.0 && n.abs() < i64::MAX as f64 => {
+                serializer.serialize_i64(*n as i64)
+            }
+            ValueData::Num(n) => serializer.serialize_f64(*n),
+            ValueData::Char(c) => serializer.serialize_char(*c),
+            ValueData::Symbol(s) => serializer.serialize_str(s),
+            ValueData::Quote(..) => Err(ser::Error::custom("quotes can't be serialized")),
+            ValueData::Native(o) => {
+                let any = o.as_any();
+                if let Some(list) = any.downcast_ref::<List>() {
+                    match list.to_string() {
+                        Ok(s) if !list.0.is_empty() => serializer.serialize_str(&s),
+                        _ => serializer.collect_seq(&list.0),
+                    }
+                } else if let Some(tuple) = any.downcast_ref::<Tuple>() {
+                    serializer.collect_seq(&tuple.0)
+                } else if let Some(table) = any.downcast_ref::<Table>() {
+                    let mut map = serializer.serialize_map(Some(table.0.len()))?;
+                    for (k, v) in &table.0 {
+                        map.serialize_entry(k, v)?;
+                    }
+                    map.end()
+                } else if let Some(variant) = any.downcast_ref::<Variant>() {
+                    serialize_variant(variant, serializer)
+                } else {
+                    Err(ser::Error::custom(format!(
+                        "`{}` can't be serialized",
+                        o.repr()
+                    )))
+                }
+            }
+        }
+    }
+}
+
+fn serialize_variant<S: Serializer>(variant: &Variant, serializer: S) -> Result<S::Ok, S::Error> {
+    let tuple = variant.fields.iter().all(|(name, _)| name.starts_with('_'));
+
+    match (variant.type_name.as_str(), variant.name.as_str()) {
+        ("Option", "None") => return serializer.serialize_none(),
+        ("Option", "Some") if variant.fields.len() == 1 => {
+            return serializer.serialize_some(&variant.fields[0].1)
+        }
+        _ if variant.fields.is_empty() => return serializer.serialize_str(&variant.name),
+        _ => {}
+    }
+
+    let mut map = serializer.serialize_map(Some(1))?;
+    match variant.fields.as_slice() {
+        [(_, v)] if tuple => map.serialize_entry(&variant.name, v)?,
+        fields if tuple => {
+            let values: Vec<&Value> = fields.iter().map(|(_, v)| v).collect();
+            map.serialize_entry(&variant.name, &values)?
+        }
+        fields => map.serialize_entry(&variant.name, &Record(fields))?,
+    }
+    map.end()
+}
+
+struct Record<'a>(&'a [(String, Value)]);
+
+impl Serialize for Record<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.0.len()))?;
+        for (k, v) in self.0 {
+            map.serialize_entry(k, v)?;
+        }
+        map.end()
+    }
+}
+
+/// Strings become lists of `Char`, sequences lists, maps tables and `null` is `None`
+impl<'de> Deserialize<'de> for Value {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(ValueVisitor)
+    }
+}
+
+struct ValueVisitor;
+
+impl<'de> Visitor<'de> for ValueVisitor {
+    type Value = Value;
+
+    fn expecting(&self, f: &mut Formatter) -> std::fmt::Result {
+        write!(f, "a Fox value")
+    }
+
+    fn visit_bool<E: de::Error>(self, v: bool) -> Result<Value, E> {
+        Ok(v.to_fox())
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<Value, E> {
+        Ok(v.to_fox())
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<Value, E> {
+        Ok(v.to_fox())
+    }
+
+    fn visit_f64<E: de::Error>(self, v: f64) -> Result<Value, E> {
+        Ok(v.to_fox())
+    }
+
+    fn visit_char<E: de::Error>(self, v: char) -> Result<Value, E> {
+        Ok(v.to_fox())
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Value, E> {
+        Ok(v.to_fox())
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<Value, E> {
+        Ok(None::<Value>.to_fox())
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<Value, E> {
+        Ok(None::<Value>.to_fox())
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<Value, D::Error> {
+        Ok(Some(Value::deserialize(deserializer)?).to_fox())
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Value, A::Error> {
+        let mut items = vec![];
+        while let Some(item) = seq.next_element()? {
+            items.push(item);
+        }
+        Ok(List(items).to_fox())
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Value, A::Error> {
+        let mut table = Table::default();
+        while let Some((k, v)) = map.next_entry()? {
+            table.set(k, v);
+        }
+        Ok(table.to_fox())
+    }
+}
+
+/// Failure to convert between a Rust value and a Fox one
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("{0}")]
+pub struct Error(String);
+
+impl ser::Error for Error {
+    fn custom<T: Display>(msg: T) -> Self {
+        Error(msg.to_string())
+    }
+}
+
+impl de::Error for Error {
+    fn custom<T: Display>(msg: T) -> Self {
+        Error(msg.to_string())
+    }
+}
+
+/// Convert a serializable Rust value to a Fox value, without going through another format
+///
+/// Strings become lists of `Char`, sequences lists, tuples tuples, maps and structs tables,
+/// and `Option`s and enums variants.
+pub fn to_value<T: Serialize + ?Sized>(value: &T) -> Result<Value, Error> {
+    value.serialize(ValueSerializer)
+}
+
+/// Convert a Fox value to a deserializable Rust value, the converse of [`to_value`]
+pub fn from_value<T: DeserializeOwned>(value: Value) -> Result<T, Error> {
+    T::deserialize(value)
+}
+
+struct ValueSerializer;
+
+impl Serializer for ValueSerializer {
+    type Ok = Value;
+    type Error = Error;
+    type SerializeSeq = Items;
+    type SerializeTuple = Items;
+    type SerializeTupleStruct = Items;
+    type SerializeTupleVariant = Fields;
+    type SerializeMap = Entries;
+    type SerializeStruct = Entries;
+    type SerializeStructVariant = Fields;
+
+    fn serialize_bool(self, v: bool) -> Result<Value, Error> {
+        Ok(v.to_fox())
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<Value, Error> {
+        Ok(v.to_fox())
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<Value, Error> {
+        Ok(v.to_fox())
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<Value, Error> {
+        Ok(v.to_fox())
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<Value, Error> {
+        Ok(v.to_fox())
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<Value, Error> {
+        Ok(v.to_fox())
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<Value, Error> {
+        Ok(v.to_fox())
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<Value, Error> {
+        Ok(v.to_fox())
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<Value, Error> {
+        Ok(v.to_fox())
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<Value, Error> {
+        Ok(v.to_fox())
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<Value, Error> {
+        Ok(v.to_fox())
+    }
+
+    fn serialize_char(self, v: char) -> Result<Value, Error> {
+        Ok(v.to_fox())
+    }
+
+    fn serialize_str(self, v: &str) -> Result<Value, Error> {
+        Ok(v.to_fox())
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<Value, Error> {
+        Ok(List(v.iter().map(|b| b.to_fox()).collect()).to_fox())
+    }
+
+    fn serialize_none(self) -> Result<Value, Error> {
+        Ok(None::<Value>.to_fox())
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<Value, Error> {
+        Ok(Some(value.serialize(self)?).to_fox())
+    }
+
+    fn serialize_unit(self) -> Result<Value, Error> {
+        Ok(Tuple(vec![]).to_fox())
+    }
+
+    fn serialize_unit_struct(self, _: &'static str) -> Result<Value, Error> {
+        self.serialize_unit()
+    }
+
+    fn serialize_unit_variant(
+        self,
+        name: &'static str,
+        _: u32,
+        variant: &'static str,
+    ) -> Result<Value, Error> {
+        Ok(Variant::tuple(name, variant, vec![]).to_fox())
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _: &'static str,
+        value: &T,
+    ) -> Result<Value, Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        name: &'static str,
+        _: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<Value, Error> {
+        let value = value.serialize(self)?;
+        Ok(Variant::tuple(name, variant, vec![value]).to_fox())
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<Items, Error> {
+        Ok(Items {
+            items: Vec::with_capacity(len.unwrap_or(0)),
+            tuple: false,
+        })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<Items, Error> {
+        Ok(Items {
+            items: Vec::with_capacity(len),
+            tuple: true,
+        })
+    }
+
+    fn serialize_tuple_struct(self, _: &'static str, len: usize) -> Result<Items, Error> {
+        self.serialize_tuple(len)
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        name: &'static str,
+        _: u32,
+        variant: &'static str,
+        _: usize,
+    ) -> Result<Fields, Error> {
+        Ok(Fields(Variant::tuple(name, variant, vec![])))
+    }
+
+    fn serialize_map(self, _: Option<usize>) -> Result<Entries, Error> {
+        Ok(Entries::default())
+    }
+
+    fn serialize_struct(self, _: &'static str, _: usize) -> Result<Entries, Error> {
+        Ok(Entries::default())
+    }
+
+    fn serialize_struct_variant(
+        self,
+        name: &'static str,
+        _: u32,
+        variant: &'static str,
+        _: usize,
+    ) -> Result<Fields, Error> {
+        Ok(Fields(Variant::tuple(name, variant, vec![])))
+    }
+}
+
+/// Items of a list, or of a tuple
+struct Items {
+    items: Vec<Value>,
+    tuple: bool,
+}
+
+impl Items {
+    fn push<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.items.push(to_value(value)?);
+        Ok(())
+    }
+
+    fn build(self) -> Result<Value, Error> {
+        Ok(match self.tuple {
+            true => Tuple(self.items).to_fox(),
+            false => List(self.items).to_fox(),
+        })
+    }
+}
+
+impl ser::SerializeSeq for Items {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Value, Error> {
+        self.build()
+    }
+}
+
+impl ser::SerializeTuple for Items {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Value, Error> {
+        self.build()
+    }
+}
+
+impl ser::SerializeTupleStruct for Items {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Value, Error> {
+        self.build()
+    }
+}
+
+/// Entries of a table, whose keys are strings for a struct
+#[derive(Default)]
+struct Entries {
+    table: Table,
+    key: Option<Value>,
+}
+
+impl ser::SerializeMap for Entries {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), Error> {
+        self.key = Some(to_value(key)?);
+        Ok(())
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        let key = self
+            .key
+            .take()
+            .ok_or_else(|| ser::Error::custom("map value without a key"))?;
+        self.table.set(key, to_value(value)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<Value, Error> {
+        Ok(self.table.to_fox())
+    }
+}
+
+impl ser::SerializeStruct for Entries {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        self.table.set(key.to_fox(), to_value(value)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<Value, Error> {
+        Ok(self.table.to_fox())
+    }
+}
+
+/// Fields of a variant, named `_0`, `_1`, ... for a tuple variant
+struct Fields(Variant);
+
+impl ser::SerializeTupleVariant for Fields {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        let name = format!("_{}", self.0.fields.len());
+        self.0.fields.push((name, to_value(value)?));
+        Ok(())
+    }
+
+    fn end(self) -> Result<Value, Error> {
+        Ok(self.0.to_fox())
+    }
+}
+
+impl ser::SerializeStructVariant for Fields {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        self.0.fields.push((key.to_string(), to_value(value)?));
+        Ok(())
+    }
+
+    fn end(self) -> Result<Value, Error> {
+        Ok(self.0.to_fox())
+    }
+}
+
+impl<'de> IntoDeserializer<'de, Error> for Value {
+    type Deserializer = Value;
+
+    fn into_deserializer(self) -> Value {
+        self
+    }
+}
+
+/// Reads a value as the Rust type asks for it, so an empty list can be an empty string
+/// and `Some 1` an `Option` or any enum variant
+impl<'de> Deserializer<'de> for Value {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self.data_ref() {
+            ValueData::Bool(b) => visitor.visit_bool(*b),
+            ValueData::Num(n) if n.fract() == 0.0 && n.abs() < i64::MAX as f64 => {
+                visitor.visit_i64(*n as i64)
+            }
+            ValueData::Num(n) => visitor.visit_f64(*n),
+            ValueData::Char(c) => visitor.visit_char(*c),
+            ValueData::Symbol(s) => visitor.visit_str(s),
+            ValueData::Quote(..) => Err(de::Error::custom("quotes can't be deserialized")),
+            ValueData::Native(o) => {
+                let any = o.as_any();
+                if let Some(list) = any.downcast_ref::<List>() {
+                    match list.to_string() {
+                        Ok(s) if !list.0.is_empty() => visitor.visit_string(s),
+                        _ => visit_items(list.0.clone(), visitor),
+                    }
+                } else if let Some(tuple) = any.downcast_ref::<Tuple>() {
+                    visit_items(tuple.0.clone(), visitor)
+                } else if let Some(table) = any.downcast_ref::<Table>() {
+                    let mut map = MapDeserializer::new(table.0.clone().into_iter());
+                    let value = visitor.visit_map(&mut map)?;
+                    map.end()?;
+                    Ok(value)
+                } else if let Some(variant) = any.downcast_ref::<Variant>() {
+                    match (variant.type_name.as_str(), variant.fields.as_slice()) {
+                        ("Option", []) => visitor.visit_none(),
+                        ("Option", [(_, v)]) => visitor.visit_some(v.clone()),
+                        _ => visitor.visit_enum(Enum(variant.clone())),
+                    }
+                } else {
+                    Err(de::Error::custom(format!(
+                        "`{}` can't be deserialized",
+                        o.repr()
+                    )))
+                }
+            }
+        }
+    }
+
+    fn deserialize_str<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.deserialize_string(visitor)
+    }
+
+    fn deserialize_string<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self.as_native_typed::<List>().map(List::to_string) {
+            Ok(Ok(s)) => visitor.visit_string(s),
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_identifier<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.deserialize_string(visitor)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        if let Ok(list) = self.as_native_typed::<List>() {
+            return visit_items(list.0.clone(), visitor);
+        }
+        match self.as_native_typed::<Tuple>() {
+            Ok(tuple) => visit_items(tuple.0.clone(), visitor),
+            Err(_) => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(self, _: usize, visitor: V) -> Result<V::Value, Error> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        _: usize,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self.as_native_typed::<Variant>() {
+            Ok(variant) if variant.type_name == "Option" => match variant.fields.as_slice() {
+                [] => visitor.visit_none(),
+                [(_, v)] => visitor.visit_some(v.clone()),
+                _ => Err(de::Error::custom("`Some` holds a single value")),
+            },
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self.as_native_typed::<Tuple>() {
+            Ok(tuple) if tuple.0.is_empty() => visitor.visit_unit(),
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.deserialize_unit(visitor)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    /// Unit variants can also be written as a string or a symbol
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        _: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        if let Ok(variant) = self.as_native_typed::<Variant>() {
+            return visitor.visit_enum(Enum(variant.clone()));
+        }
+        let name = match self.data_ref() {
+            ValueData::Symbol(s) => s.clone(),
+            _ => self
+                .as_native_typed::<List>()
+                .and_then(List::to_string)
+                .map_err(|_| de::Error::custom("expected a variant"))?,
+        };
+        visitor.visit_enum(IntoDeserializer::<Error>::into_deserializer(name))
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char bytes byte_buf map struct
+        ignored_any
+    }
+}
+
+fn visit_items<'de, V: Visitor<'de>>(items: Vec<Value>, visitor: V) -> Result<V::Value, Error> {
+    let mut seq = SeqDeserializer::new(items.into_iter());
+    let value = visitor.visit_seq(&mut seq)?;
+    seq.end()?;
+    Ok(value)
+}
+
+struct Enum(Variant);
+
+impl<'de> EnumAccess<'de> for Enum {
+    type Error = Error;
+    type Variant = Self;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(self, seed: V) -> Result<(V::Value, Self), Error> {
+        let name = IntoDeserializer::<Error>::into_deserializer(self.0.name.clone());
+        Ok((seed.deserialize(name)?, self))
+    }
+}
+
+impl<'de> VariantAccess<'de> for Enum {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<(), Error> {
+        match self.0.fields.len() {
+            0 => Ok(()),
+            n => Err(de::Error::invalid_length(n, &"a unit variant")),
+        }
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value, Error> {
+        match self.0.fields.as_slice() {
+            [(_, v)] => seed.deserialize(v.clone()),
+            fields => Err(de::Error::invalid_length(fields.len(), &"a single field")),
+        }
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, _: usize, visitor: V) -> Result<V::Value, Error> {
+        let values = self.0.fields.into_iter().map(|(_, v)| v).collect();
+        visit_items(values, visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        _: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        let mut map = MapDeserializer::new(self.0.fields.into_iter());
+        let value = visitor.visit_map(&mut map)?;
+        map.end()?;
+        Ok(value)
+    }
+}
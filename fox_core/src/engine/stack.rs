@@ -1,14 +1,51 @@
+use crate::engine::convert::{FromFox, ToFox};
 use crate::engine::error::Error;
-use crate::engine::value::{NativeObject, Value, ValueData};
+use crate::engine::value::{List, NativeObject, Value, ValueData};
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Stack {
     stack: Vec<Value>,
+    /// Values below this depth were on the stack when `mark` was called
+    mark: usize,
+    /// Values popped from below the mark, the last one popped first
+    popped: Vec<Value>,
+}
+
+impl Default for Stack {
+    fn default() -> Self {
+        Stack::new()
+    }
 }
 
 impl Stack {
     pub fn new() -> Self {
-        Stack { stack: Vec::new() }
+        Stack {
+            stack: Vec::new(),
+            mark: 0,
+            popped: Vec::new(),
+        }
+    }
+
+    /// Remember the current values, so `rollback` can restore them
+    pub(crate) fn mark(&mut self) {
+        self.mark = self.stack.len();
+        self.popped.clear();
+    }
+
+    /// Put the stack back as it was on the last `mark`
+    pub(crate) fn rollback(&mut self) {
+        self.stack.truncate(self.mark);
+        self.stack.extend(self.popped.drain(..).rev());
+        self.mark = self.stack.len();
+    }
+
+    /// Keep the values about to be removed from below the mark
+    fn save(&mut self, len: usize) {
+        if len < self.mark {
+            self.popped
+                .extend(self.stack[len..self.mark].iter().rev().cloned());
+            self.mark = len;
+        }
     }
 
     pub fn push(&mut self, value: impl Into<Value>) {
@@ -16,11 +53,41 @@ impl Stack {
     }
 
     pub fn pop(&mut self) -> Result<Value, Error> {
+        self.save(self.stack.len().saturating_sub(1));
         self.stack
             .pop()
             .ok_or(Error::StackError("Stack is empty".into()))
     }
 
+    /// Pop a value and convert it
+    pub fn pop_as<T: FromFox>(&mut self) -> Result<T, Error> {
+        T::from_fox(self.pop()?)
+    }
+
+    /// Push every value in order, the last one ends up on top
+    pub fn push_many<T: ToFox>(&mut self, values: impl IntoIterator<Item = T>) {
+        for value in values {
+            self.push(value.to_fox());
+        }
+    }
+
+    /// Pop the `n` topmost values, in the order they were pushed
+    pub fn pop_n(&mut self, n: usize) -> Result<Vec<Value>, Error> {
+        if n > self.stack.len() {
+            return Err(Error::StackError(format!(
+                "Expected {n} values, found {}",
+                self.stack.len()
+            )));
+        }
+        self.save(self.stack.len() - n);
+        Ok(self.stack.split_off(self.stack.len() - n))
+    }
+
+    pub fn truncate(&mut self, len: usize) {
+        self.save(len);
+        self.stack.truncate(len);
+    }
+
     pub fn peek(&self) -> Result<Value, Error> {
         self.stack
             .last()
@@ -28,6 +95,28 @@ impl Stack {
             .cloned()
     }
 
+    pub fn len(&self) -> usize {
+        self.stack.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.stack.is_empty()
+    }
+
+    /// Values from the bottom to the top of the stack
+    pub fn values(&self) -> &[Value] {
+        &self.stack
+    }
+
+    pub fn repr(&self) -> String {
+        let values: Vec<String> = self.stack.iter().map(|v| v.data_ref().repr()).collect();
+        format!("[{}]", values.join(" "))
+    }
+
+    pub fn value_count(&self) -> usize {
+        self.stack.iter().map(Value::size).sum()
+    }
+
     pub fn push_bool(&mut self, b: bool) {
         self.push(ValueData::Bool(b));
     }
@@ -44,6 +133,14 @@ impl Stack {
         self.push(ValueData::Symbol(s));
     }
 
+    pub fn pop_string(&mut self) -> Result<String, Error> {
+        self.pop()?.as_native_typed::<List>()?.to_string()
+    }
+
+    pub fn push_string(&mut self, s: &str) {
+        self.push_native(List::from_string(s));
+    }
+
     pub fn push_native(&mut self, obj: impl NativeObject) {
         self.push(ValueData::Native(Box::new(obj)));
     }
@@ -1,6 +1,441 @@
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
 
+use crate::engine::compile::qualified;
+use crate::engine::error::Error;
+use crate::engine::value::Value;
+use crate::parsing::lexer::Literal;
+use crate::parsing::parser::{
+    Ast, Definition, Expr, FunctionDefinition, FunctionType, ParamType, TypeDefinition,
+    VariantDefinition,
+};
 
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Type {
+    /// A concrete type such as `Int` or `Char`
+    Named(String),
+    /// A generic type variable
+    Var(String),
+    List(Box<Type>),
+    Quote(StackEffect),
+    /// A generic type applied to its parameters, such as `t Option`
+    Applied(Vec<Type>, String),
+}
 
-pub struct Type {
+/// Type of a word, the values it consumes and produces, top of the stack last
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct StackEffect {
+    pub inputs: Vec<Type>,
+    pub outputs: Vec<Type>,
+}
 
-}
\ No newline at end of file
+impl StackEffect {
+    pub fn new(inputs: Vec<Type>, outputs: Vec<Type>) -> Self {
+        StackEffect { inputs, outputs }
+    }
+}
+
+impl Type {
+    pub fn named(name: impl Into<String>) -> Self {
+        Type::Named(name.into())
+    }
+
+    pub fn var(name: impl Into<String>) -> Self {
+        Type::Var(name.into())
+    }
+
+    pub fn applied(params: Vec<Type>, name: impl Into<String>) -> Self {
+        Type::Applied(params, name.into())
+    }
+
+    pub fn quote(inputs: Vec<Type>, outputs: Vec<Type>) -> Self {
+        Type::Quote(StackEffect::new(inputs, outputs))
+    }
+}
+
+impl Display for Type {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Type::Named(n) | Type::Var(n) => write!(f, "{n}"),
+            Type::List(t) => write!(f, "[{t}]"),
+            Type::Quote(e) => write!(f, "{e}"),
+            Type::Applied(params, name) => {
+                for param in params {
+                    match param {
+                        Type::Applied(..) => write!(f, "({param}) ")?,
+                        _ => write!(f, "{param} ")?,
+                    }
+                }
+                write!(f, "{name}")
+            }
+        }
+    }
+}
+
+impl Display for StackEffect {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let join = |types: &[Type]| {
+            types
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+
+        match (self.inputs.as_slice(), self.outputs.as_slice()) {
+            (i, []) => write!(f, "({})", join(i)),
+            ([], o) => write!(f, "(-> {})", join(o)),
+            (i, o) => write!(f, "({} -> {})", join(i), join(o)),
+        }
+    }
+}
+
+impl From<&FunctionType> for StackEffect {
+    fn from(ftype: &FunctionType) -> Self {
+        let convert = |params: &[ParamType]| {
+            params
+                .iter()
+                .map(|p| match p {
+                    ParamType::Value(v) => Type::Named(v.name.clone()),
+                    ParamType::Function(f) => Type::Quote(f.into()),
+                })
+                .collect()
+        };
+
+        StackEffect::new(convert(&ftype.inputs), convert(&ftype.output))
+    }
+}
+
+/// Rust types with a known Fox type
+pub trait Typed {
+    fn fox_type() -> Type;
+}
+
+macro_rules! impl_typed {
+    ($($t:ty => $name:literal),*) => {
+        $(impl Typed for $t {
+            fn fox_type() -> Type {
+                Type::named($name)
+            }
+        })*
+    };
+}
+
+impl_typed!(
+    bool => "Bool",
+    char => "Char",
+    i8 => "Int",
+    i16 => "Int",
+    i32 => "Int",
+    i64 => "Int",
+    isize => "Int",
+    u8 => "Int",
+    u16 => "Int",
+    u32 => "Int",
+    u64 => "Int",
+    usize => "Int",
+    f32 => "Float",
+    f64 => "Float",
+    String => "String",
+    &str => "String"
+);
+
+impl<T: Typed> Typed for Vec<T> {
+    fn fox_type() -> Type {
+        Type::List(Box::new(T::fox_type()))
+    }
+}
+
+impl<T: Typed> Typed for Option<T> {
+    fn fox_type() -> Type {
+        Type::applied(vec![T::fox_type()], "Option")
+    }
+}
+
+impl Typed for Value {
+    fn fox_type() -> Type {
+        Type::var("t")
+    }
+}
+
+/// Types pushed on the stack by a native return value
+pub trait ReturnTypes {
+    fn fox_types() -> Vec<Type>;
+}
+
+impl ReturnTypes for () {
+    fn fox_types() -> Vec<Type> {
+        vec![]
+    }
+}
+
+impl<T: Typed> ReturnTypes for T {
+    fn fox_types() -> Vec<Type> {
+        vec![T::fox_type()]
+    }
+}
+
+impl<T: ReturnTypes> ReturnTypes for Result<T, Error> {
+    fn fox_types() -> Vec<Type> {
+        T::fox_types()
+    }
+}
+
+/// Effect of the constructor of a variant, such as `(Float -> Shape)`
+pub fn constructor_effect(t: &TypeDefinition, variant: &VariantDefinition) -> StackEffect {
+    let inputs = variant
+        .items
+        .iter()
+        .map(|(_, name)| match t.vars.contains(name) {
+            true => Type::var(name),
+            false => Type::named(name),
+        })
+        .collect();
+    let output = match t.vars.as_slice() {
+        [] => Type::named(&t.name),
+        vars => Type::applied(vars.iter().map(Type::var).collect(), &t.name),
+    };
+
+    StackEffect::new(inputs, vec![output])
+}
+
+type Lookup<'a> = Box<dyn Fn(&str) -> Option<StackEffect> + 'a>;
+
+/// Effects of the words of a file, declared or inferred from their body
+///
+/// Words not defined by the file are looked up in `known` by full name.
+pub struct Signatures<'a> {
+    functions: HashMap<&'a str, &'a FunctionDefinition>,
+    constructors: HashMap<&'a str, StackEffect>,
+    known: Lookup<'a>,
+    cache: HashMap<String, Option<StackEffect>>,
+}
+
+impl<'a> Signatures<'a> {
+    pub fn new(ast: &'a Ast, known: impl Fn(&str) -> Option<StackEffect> + 'a) -> Self {
+        let mut functions = HashMap::new();
+        let mut constructors = HashMap::new();
+        for def in &ast.defs {
+            match def {
+                Definition::Function(f) => {
+                    functions.insert(f.name.as_str(), f);
+                }
+                Definition::Type(t) => {
+                    for v in &t.variants {
+                        constructors.insert(v.name.as_str(), constructor_effect(t, v));
+                    }
+                }
+            }
+        }
+
+        Signatures {
+            functions,
+            constructors,
+            known: Box::new(known),
+            cache: HashMap::new(),
+        }
+    }
+
+    pub fn get(&mut self, name: &str) -> Option<StackEffect> {
+        if let Some(effect) = self.cache.get(name) {
+            return effect.clone();
+        }
+        if let Some(effect) = self.constructors.get(name) {
+            return Some(effect.clone());
+        }
+        let Some(f) = self.functions.get(name).copied() else {
+            return (self.known)(name);
+        };
+
+        // Recursive words are left unknown
+        self.cache.insert(name.to_string(), None);
+        let effect = match &f.ftype {
+            Some(ftype) => Some(ftype.into()),
+            None => self.infer(&f.body),
+        };
+        self.cache.insert(name.to_string(), effect.clone());
+        effect
+    }
+
+    /// Infer the effect of some code, `None` when it uses a word of unknown effect
+    /// or a value doesn't fit the type expected by a word
+    pub fn infer(&mut self, body: &[Expr]) -> Option<StackEffect> {
+        let mut inference = Inference {
+            signatures: self,
+            substitution: HashMap::new(),
+            fresh: 0,
+        };
+        let effect = inference.body(body, &HashMap::new())?;
+        Some(inference.finish(effect))
+    }
+}
+
+struct Inference<'s, 'a> {
+    signatures: &'s mut Signatures<'a>,
+    substitution: HashMap<String, Type>,
+    fresh: usize,
+}
+
+impl Inference<'_, '_> {
+    /// Names of fresh variables can't clash with the ones written in code
+    fn fresh(&mut self) -> Type {
+        self.fresh += 1;
+        Type::Var(format!("'{}", self.fresh))
+    }
+
+    fn body(&mut self, body: &[Expr], locals: &HashMap<String, Type>) -> Option<StackEffect> {
+        let mut locals = locals.clone();
+        let mut inputs = vec![];
+        let mut stack = vec![];
+
+        for expr in body {
+            match expr {
+                Expr::Literal(literal) => stack.push(Type::named(match literal {
+                    Literal::Int(_) => "Int",
+                    Literal::Float(_) => "Float",
+                    Literal::Char(_) => "Char",
+                    Literal::String(_) => "String",
+                })),
+                Expr::Term { name, module, .. }
+                    if module.is_empty() && locals.contains_key(name) =>
+                {
+                    stack.push(locals[name].clone())
+                }
+                Expr::Term { name, module, .. } => {
+                    let effect = self.signatures.get(&qualified(module, name))?;
+                    let effect = self.instantiate(&effect);
+                    for expected in effect.inputs.iter().rev() {
+                        let actual = self.pop(&mut stack, &mut inputs);
+                        self.unify(expected, &actual)?;
+                    }
+                    stack.extend(effect.outputs);
+                }
+                Expr::Local { names, .. } => {
+                    for name in names {
+                        let t = self.pop(&mut stack, &mut inputs);
+                        locals.insert(name.clone(), t);
+                    }
+                }
+                Expr::Quote(items) => {
+                    let effect = self.body(items, &locals)?;
+                    stack.push(Type::Quote(effect));
+                }
+                Expr::Field(_) | Expr::Match(_) => return None,
+            }
+        }
+
+        Some(StackEffect::new(inputs, stack))
+    }
+
+    /// Values missing from the stack become inputs of the body
+    fn pop(&mut self, stack: &mut Vec<Type>, inputs: &mut Vec<Type>) -> Type {
+        match stack.pop() {
+            Some(t) => t,
+            None => {
+                let t = self.fresh();
+                inputs.insert(0, t.clone());
+                t
+            }
+        }
+    }
+
+    /// Give the variables of an effect fresh names, each use of a word being independent
+    fn instantiate(&mut self, effect: &StackEffect) -> StackEffect {
+        let mut names = HashMap::new();
+        let mut rename = |t: &Type| {
+            t.map_vars(&mut |v| {
+                names
+                    .entry(v.to_string())
+                    .or_insert_with(|| self.fresh())
+                    .clone()
+            })
+        };
+        StackEffect::new(
+            effect.inputs.iter().map(&mut rename).collect(),
+            effect.outputs.iter().map(&mut rename).collect(),
+        )
+    }
+
+    fn resolve(&self, t: &Type) -> Type {
+        t.map_vars(&mut |v| match self.substitution.get(v) {
+            Some(t) => self.resolve(t),
+            None => Type::var(v),
+        })
+    }
+
+    fn unify(&mut self, expected: &Type, actual: &Type) -> Option<()> {
+        match (self.resolve(expected), self.resolve(actual)) {
+            (Type::Var(a), Type::Var(b)) if a == b => Some(()),
+            (Type::Var(v), t) | (t, Type::Var(v)) => {
+                if t.has_var(&v) {
+                    return None;
+                }
+                self.substitution.insert(v, t);
+                Some(())
+            }
+            (Type::Named(a), Type::Named(b)) => (a == b).then_some(()),
+            (Type::List(a), Type::List(b)) => self.unify(&a, &b),
+            (Type::Applied(a, name), Type::Applied(b, other))
+                if name == other && a.len() == b.len() =>
+            {
+                a.iter().zip(&b).try_for_each(|(a, b)| self.unify(a, b))
+            }
+            (Type::Quote(a), Type::Quote(b))
+                if a.inputs.len() == b.inputs.len() && a.outputs.len() == b.outputs.len() =>
+            {
+                let inputs = a.inputs.iter().zip(&b.inputs);
+                let outputs = a.outputs.iter().zip(&b.outputs);
+                inputs
+                    .chain(outputs)
+                    .try_for_each(|(a, b)| self.unify(a, b))
+            }
+            _ => None,
+        }
+    }
+
+    /// Apply the substitution and name the remaining variables `a`, `b`, ...
+    fn finish(&self, effect: StackEffect) -> StackEffect {
+        let mut names: HashMap<String, Type> = HashMap::new();
+        let mut rename = |t: &Type| {
+            self.resolve(t).map_vars(&mut |v| {
+                let next = (b'a' + names.len() as u8 % 26) as char;
+                names
+                    .entry(v.to_string())
+                    .or_insert_with(|| Type::var(next))
+                    .clone()
+            })
+        };
+        StackEffect::new(
+            effect.inputs.iter().map(&mut rename).collect(),
+            effect.outputs.iter().map(&mut rename).collect(),
+        )
+    }
+}
+
+impl Type {
+    /// Replace every variable of the type
+    fn map_vars(&self, f: &mut dyn FnMut(&str) -> Type) -> Type {
+        match self {
+            Type::Named(_) => self.clone(),
+            Type::Var(v) => f(v),
+            Type::List(t) => Type::List(Box::new(t.map_vars(f))),
+            Type::Quote(e) => Type::Quote(StackEffect::new(
+                e.inputs.iter().map(|t| t.map_vars(f)).collect(),
+                e.outputs.iter().map(|t| t.map_vars(f)).collect(),
+            )),
+            Type::Applied(params, name) => {
+                Type::Applied(params.iter().map(|t| t.map_vars(f)).collect(), name.clone())
+            }
+        }
+    }
+
+    fn has_var(&self, var: &str) -> bool {
+        match self {
+            Type::Named(_) => false,
+            Type::Var(v) => v == var,
+            Type::List(t) => t.has_var(var),
+            Type::Quote(e) => e.inputs.iter().chain(&e.outputs).any(|t| t.has_var(var)),
+            Type::Applied(params, _) => params.iter().any(|t| t.has_var(var)),
+        }
+    }
+}
@@ -2,7 +2,10 @@ use std::any::Any;
 use std::fmt::Debug;
 use std::rc::Rc;
 
+use crate::engine::env::Env;
 use crate::engine::error::Error;
+use crate::engine::expr::Expr;
+use crate::stdlib::seq::{Items, Seq};
 
 #[derive(Debug, Clone)]
 pub struct Value(Rc<ValueData>);
@@ -13,27 +16,13 @@ impl From<ValueData> for Value {
     }
 }
 
-impl Value {
-    fn bool(b: bool) -> Value {
-        ValueData::Bool(b).into()
-    }
-
-    fn char(c: char) -> Value {
-        ValueData::Char(c).into()
-    }
-
-    fn num(n: f64) -> Value {
-        ValueData::Num(n).into()
-    }
-
-    fn symbol(s: impl Into<String>) -> Value {
-        ValueData::Symbol(s.into()).into()
-    }
-
-    fn native(o: Box<dyn NativeObject>) -> Value {
-        ValueData::Native(o).into()
+impl PartialEq for Value {
+    fn eq(&self, other: &Self) -> bool {
+        self.data_ref() == other.data_ref()
     }
+}
 
+impl Value {
     pub fn data_ref(&self) -> &ValueData {
         self.0.as_ref()
     }
@@ -42,6 +31,14 @@ impl Value {
         Rc::make_mut(&mut self.0)
     }
 
+    /// Number of values held by this value, itself included.
+    pub fn size(&self) -> usize {
+        match self.data_ref() {
+            ValueData::Native(o) => o.size(),
+            _ => 1,
+        }
+    }
+
     pub fn as_bool(&self) -> Result<bool, Error> {
         match self.data_ref() {
             ValueData::Bool(b) => Ok(*b),
@@ -70,6 +67,14 @@ impl Value {
         }
     }
 
+    /// Code of a quotation, with the locals it was written among
+    pub fn as_quote(&self) -> Result<(&[Expr], &Env<Value>), Error> {
+        match self.data_ref() {
+            ValueData::Quote(q, env) => Ok((q.as_slice(), env)),
+            _ => Err(Error::CastError("Quote".to_string())),
+        }
+    }
+
     pub fn as_native(&self) -> Result<&dyn NativeObject, Error> {
         match self.data_ref() {
             ValueData::Native(o) => Ok(o.as_ref()),
@@ -136,6 +141,30 @@ impl<A: AsAny> Cast for A {
 pub trait NativeObject: Debug + AsAny {
     fn repr(&self) -> String;
     fn cloned(&self) -> Box<dyn NativeObject>;
+
+    fn size(&self) -> usize {
+        1
+    }
+
+    /// Name and fields of the variant, used by `match`
+    fn variant(&self) -> Option<(&str, Vec<Value>)> {
+        None
+    }
+
+    /// Field accessed with `.name`
+    fn field(&self, _name: &str) -> Option<Value> {
+        None
+    }
+
+    /// Values iterated by `map`, `filter`, `collect`, ...
+    fn seq(&self) -> Option<Seq> {
+        None
+    }
+
+    /// Whether `other` holds the same value, distinct natives are never equal by default
+    fn eq(&self, _other: &dyn NativeObject) -> bool {
+        false
+    }
 }
 
 impl Clone for Box<dyn NativeObject> {
@@ -150,9 +179,27 @@ pub enum ValueData {
     Num(f64),
     Char(char),
     Symbol(String),
+    /// Quotations see the locals in scope where they are written, wherever they are run
+    Quote(Vec<Expr>, Env<Value>),
     Native(Box<dyn NativeObject>),
 }
 
+impl PartialEq for ValueData {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (ValueData::Bool(a), ValueData::Bool(b)) => a == b,
+            (ValueData::Num(a), ValueData::Num(b)) => a == b,
+            (ValueData::Char(a), ValueData::Char(b)) => a == b,
+            (ValueData::Symbol(a), ValueData::Symbol(b)) => a == b,
+            (ValueData::Quote(a, _), ValueData::Quote(b, _)) => a == b,
+            (ValueData::Native(a), ValueData::Native(b)) => {
+                NativeObject::eq(a.as_ref(), b.as_ref())
+            }
+            _ => false,
+        }
+    }
+}
+
 impl ValueData {
     pub fn repr(&self) -> String {
         match self {
@@ -160,7 +207,49 @@ impl ValueData {
             ValueData::Num(v) => v.to_string(),
             ValueData::Char(v) => v.to_string(),
             ValueData::Symbol(v) => v.to_string(),
+            ValueData::Quote(v, _) => Expr::Quote(v.clone()).repr(),
             ValueData::Native(v) => v.repr(),
         }
     }
 }
+
+/// Lists are built in, strings being lists of `Char`
+#[derive(Debug, Clone)]
+pub struct List(pub Vec<Value>);
+
+impl List {
+    pub fn from_string(s: &str) -> Self {
+        List(s.chars().map(|c| ValueData::Char(c).into()).collect())
+    }
+
+    pub fn to_string(&self) -> Result<String, Error> {
+        self.0.iter().map(Value::as_char).collect()
+    }
+}
+
+impl NativeObject for List {
+    fn repr(&self) -> String {
+        let items: Vec<String> = self.0.iter().map(|it| it.data_ref().repr()).collect();
+        format!("[{}]", items.join(", "))
+    }
+
+    fn cloned(&self) -> Box<dyn NativeObject> {
+        let cloned: List = Clone::clone(self);
+        Box::new(cloned)
+    }
+
+    fn size(&self) -> usize {
+        1 + self.0.iter().map(Value::size).sum::<usize>()
+    }
+
+    fn eq(&self, other: &dyn NativeObject) -> bool {
+        other
+            .as_any()
+            .downcast_ref::<List>()
+            .is_some_and(|other| self.0 == other.0)
+    }
+
+    fn seq(&self) -> Option<Seq> {
+        Some(Seq::new(Items::new(self.0.clone())))
+    }
+}
@@ -1,7 +1,7 @@
 extern crate core;
 
 pub mod engine;
-// pub mod parsing;
+pub mod parsing;
 pub mod stdlib;
 
 // use std::fs;
@@ -0,0 +1,132 @@
+//! Concrete syntax tree keeping every token of a source, comments included
+
+use chumsky::prelude::*;
+
+use crate::parsing::lexer::{self, Span, Spanned, Token};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Leaf {
+    pub token: Token,
+    pub span: Span,
+    /// Line of the token, starting at 0
+    pub line: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Node {
+    Leaf(Leaf),
+    /// Nodes between `()`, `{}` or `[]`, `close` is missing if left open
+    Group {
+        open: Leaf,
+        nodes: Vec<Node>,
+        close: Option<Leaf>,
+    },
+}
+
+impl Node {
+    pub fn first_line(&self) -> usize {
+        match self {
+            Node::Leaf(leaf) | Node::Group { open: leaf, .. } => leaf.line,
+        }
+    }
+
+    pub fn last_line(&self) -> usize {
+        match self {
+            Node::Leaf(leaf) => leaf.line,
+            Node::Group {
+                close: Some(close), ..
+            } => close.line,
+            Node::Group { open, nodes, .. } => nodes.last().map_or(open.line, Node::last_line),
+        }
+    }
+
+    pub fn token(&self) -> Option<&Token> {
+        match self {
+            Node::Leaf(leaf) => Some(&leaf.token),
+            Node::Group { .. } => None,
+        }
+    }
+
+    pub fn is_comment(&self) -> bool {
+        matches!(self.token(), Some(Token::Comment(_)))
+    }
+}
+
+/// A top level definition or line of code
+pub type Item = Vec<Node>;
+
+/// Split a source in items, a comment at column 0 being an item of its own unless
+/// followed by more code of the same item
+pub fn parse(source: &str) -> Result<Vec<Item>, Vec<Simple<char>>> {
+    let tokens = lexer::lex_with_comments(source)?;
+
+    let chars: Vec<char> = source.chars().collect();
+    let column = |span: &Span| {
+        chars[..span.start]
+            .iter()
+            .rev()
+            .take_while(|c| **c != '\n')
+            .count()
+    };
+
+    let mut line = 0;
+    let mut last = 0;
+    let mut leaves = tokens.into_iter().map(|(token, span): Spanned<Token>| {
+        line += chars[last..span.start]
+            .iter()
+            .filter(|c| **c == '\n')
+            .count();
+        last = span.start;
+        Leaf { token, span, line }
+    });
+
+    let mut items: Vec<Item> = vec![];
+    loop {
+        let (nodes, end) = group(&mut leaves, None);
+        let continued =
+            nodes
+                .iter()
+                .find(|node| !node.is_comment())
+                .is_some_and(|node| match node {
+                    Node::Leaf(leaf) | Node::Group { open: leaf, .. } => column(&leaf.span) > 0,
+                });
+        match items.last_mut() {
+            Some(item) if continued => item.extend(nodes),
+            _ if !nodes.is_empty() => items.push(nodes),
+            _ => {}
+        }
+        if end.is_none() {
+            break;
+        }
+    }
+    Ok(items)
+}
+
+/// Nodes up to `close`, or a `Token::Dedent` at the top level
+fn group(
+    leaves: &mut impl Iterator<Item = Leaf>,
+    close: Option<Token>,
+) -> (Vec<Node>, Option<Leaf>) {
+    let mut nodes = vec![];
+    while let Some(leaf) = leaves.next() {
+        let closing = match leaf.token {
+            Token::LParen => Token::RParen,
+            Token::LBrace => Token::RBrace,
+            Token::LBracket => Token::RBracket,
+            Token::Dedent if close.is_none() => return (nodes, Some(leaf)),
+            _ if Some(&leaf.token) == close.as_ref() => return (nodes, Some(leaf)),
+            _ => {
+                nodes.push(Node::Leaf(leaf));
+                continue;
+            }
+        };
+
+        let (inner, end) = group(leaves, Some(closing));
+        nodes.push(Node::Group {
+            open: leaf,
+            nodes: inner,
+            close: end,
+        });
+    }
+    (nodes, None)
+}
@@ -0,0 +1,290 @@
+//! Canonical layout of Fox source
+//!
+//! Tokens keep their source text and comments are preserved, those ending a line at their
+//! column. Code on one line stays on one line with normalised spacing, code spanning
+//! several lines is reindented: one level for the body of a `def`, each `type` variant and
+//! the content of a delimiter, one more for the code of a match arm.
+
+use crate::parsing::cst::{self, Leaf, Node};
+use crate::parsing::lexer::Token;
+use crate::parsing::{self, ParseError};
+
+const INDENT: &str = "    ";
+
+/// Format a source, refusing one with parse errors
+pub fn format(source: &str) -> Result<String, Vec<ParseError>> {
+    parsing::parse(source)?;
+    let items = cst::parse(source)
+        .map_err(|errors| errors.into_iter().map(ParseError::from).collect::<Vec<_>>())?;
+
+    let mut f = Formatter {
+        chars: source.chars().collect(),
+        out: String::new(),
+    };
+    let mut last_line = None;
+    for item in &items {
+        if last_line.is_some_and(|last| item[0].first_line() > last + 1) {
+            f.blank_line();
+        }
+        f.item(item);
+        f.out.push('\n');
+        last_line = item.last().map(Node::last_line);
+    }
+    Ok(f.out)
+}
+
+struct Formatter {
+    chars: Vec<char>,
+    out: String,
+}
+
+impl Formatter {
+    fn item(&mut self, nodes: &[Node]) {
+        match nodes.iter().find_map(Node::token) {
+            Some(Token::Def) if !nodes[0].is_comment() => self.def(nodes),
+            Some(Token::Type) if !nodes[0].is_comment() => self.type_def(nodes),
+            _ if nodes.iter().all(Node::is_comment) => self.lines(nodes, 0, 0),
+            // Top level code continued on the next lines
+            _ => self.lines(nodes, 0, 1),
+        }
+    }
+
+    /// `def name (type) = body`, the body on the same line only if it fits on it
+    fn def(&mut self, nodes: &[Node]) {
+        let Some(eq) = nodes.iter().position(|n| n.token() == Some(&Token::Eq)) else {
+            return self.lines(nodes, 0, 0);
+        };
+        let (header, body) = nodes.split_at(eq + 1);
+
+        self.newline(0);
+        self.inline(header, 0);
+        if body.is_empty() {
+            return;
+        }
+        if body[0].first_line() == header[eq].first_line() && split_lines(body).len() == 1 {
+            self.out.push(' ');
+            self.inline(body, 0);
+        } else {
+            self.lines(body, 1, 1);
+        }
+    }
+
+    /// `type Name vars =` followed by one `| Variant` per line
+    fn type_def(&mut self, nodes: &[Node]) {
+        let Some(eq) = nodes.iter().position(|n| n.token() == Some(&Token::Eq)) else {
+            return self.lines(nodes, 0, 0);
+        };
+        self.newline(0);
+        self.inline(&nodes[..=eq], 0);
+
+        let mut lines: Vec<&[Node]> = vec![];
+        let mut start = eq + 1;
+        for i in eq + 2..nodes.len() {
+            let own_line = nodes[i].first_line() > nodes[i - 1].last_line();
+            let starts = nodes[i].token() == Some(&Token::Pipe)
+                || nodes[i - 1].is_comment()
+                || (nodes[i].is_comment() && own_line);
+            if starts {
+                lines.push(&nodes[start..i]);
+                start = i;
+            }
+        }
+        lines.push(&nodes[start..]);
+
+        for line in lines.into_iter().filter(|line| !line.is_empty()) {
+            self.newline(1);
+            if !line[0].is_comment() && line[0].token() != Some(&Token::Pipe) {
+                self.out.push_str("| ");
+            }
+            self.inline(line, 1);
+        }
+    }
+
+    /// One line of output per line of source, keeping single blank lines
+    ///
+    /// Lines following a match arm `| pattern:` are indented one more level.
+    fn lines(&mut self, nodes: &[Node], first: usize, indent: usize) {
+        let mut in_arm = false;
+        for (i, (blank, line)) in split_lines(nodes).into_iter().enumerate() {
+            if blank {
+                self.blank_line();
+            }
+            let arm = line[0].token() == Some(&Token::Pipe);
+            in_arm |= arm;
+            let indent = match i {
+                0 => first,
+                _ => indent + usize::from(in_arm && !arm),
+            };
+            self.newline(indent);
+            self.inline(line, indent);
+        }
+    }
+
+    /// Nodes separated by single spaces, `indent` being the level of the current line
+    fn inline(&mut self, nodes: &[Node], indent: usize) {
+        for (i, node) in nodes.iter().enumerate() {
+            if i > 0 {
+                let prev = &nodes[i - 1];
+                if prev.is_comment() {
+                    self.newline(indent);
+                } else if node.is_comment() {
+                    self.comment_gap(node);
+                } else if spaced(prev, node) {
+                    self.out.push(' ');
+                }
+            }
+
+            match node {
+                Node::Leaf(leaf) => self.leaf(leaf),
+                Node::Group { open, nodes, close } => self.group(open, nodes, close, indent),
+            }
+        }
+    }
+
+    fn group(&mut self, open: &Leaf, nodes: &[Node], close: &Option<Leaf>, indent: usize) {
+        self.leaf(open);
+        let broken = close.as_ref().is_some_and(|close| close.line > open.line);
+        let braces = open.token == Token::LBrace;
+        let commas = nodes.iter().any(|n| n.token() == Some(&Token::Comma));
+
+        if !broken {
+            if !nodes.is_empty() && braces {
+                self.out.push(' ');
+                self.inline(nodes, indent);
+                self.out.push(' ');
+            } else {
+                self.inline(nodes, indent);
+            }
+        } else if commas {
+            self.elements(nodes, indent + 1);
+            self.newline(indent);
+        } else {
+            self.lines(nodes, indent + 1, indent + 1);
+            self.newline(indent);
+        }
+
+        if let Some(close) = close {
+            self.leaf(close);
+        }
+    }
+
+    /// One element of a list, table, tuple or record per line, with the comments
+    /// following a comma kept on its line
+    fn elements(&mut self, nodes: &[Node], indent: usize) {
+        let mut element: Vec<Node> = vec![];
+        let mut iter = nodes.iter().peekable();
+        while let Some(node) = iter.next() {
+            if node.token() != Some(&Token::Comma) {
+                if node.is_comment() && element.is_empty() {
+                    self.newline(indent);
+                    self.inline(std::slice::from_ref(node), indent);
+                } else {
+                    element.push(node.clone());
+                }
+                continue;
+            }
+
+            self.newline(indent);
+            self.inline(&element, indent);
+            self.out.push(',');
+            element.clear();
+            if let Some(comment) =
+                iter.next_if(|n| n.is_comment() && n.first_line() == node.last_line())
+            {
+                self.comment_gap(comment);
+                self.inline(std::slice::from_ref(comment), indent);
+            }
+        }
+        if !element.is_empty() {
+            self.newline(indent);
+            self.inline(&element, indent);
+        }
+    }
+
+    fn leaf(&mut self, leaf: &Leaf) {
+        let text: String = self.chars[leaf.span.clone()].iter().collect();
+        match leaf.token {
+            Token::Comment(_) => self.out.push_str(text.trim_end()),
+            _ => self.out.push_str(&text),
+        }
+    }
+
+    /// Spaces before a comment ending a line, up to its column in the source so that
+    /// aligned comments stay aligned
+    fn comment_gap(&mut self, comment: &Node) {
+        let source = self.chars[..start(comment)]
+            .iter()
+            .rev()
+            .take_while(|c| **c != '\n')
+            .count();
+        let out = self.out.rsplit('\n').next().unwrap_or("").chars().count();
+        let gap = source.saturating_sub(out).max(1);
+        self.out.push_str(&" ".repeat(gap));
+    }
+
+    /// Start a line, unless already at the start of one
+    fn newline(&mut self, indent: usize) {
+        if !self.out.is_empty() && !self.out.ends_with('\n') {
+            self.out.push('\n');
+        }
+        self.out.push_str(&INDENT.repeat(indent));
+    }
+
+    fn blank_line(&mut self) {
+        if !self.out.ends_with('\n') {
+            self.out.push('\n');
+        }
+        self.out.push('\n');
+    }
+}
+
+/// Nodes grouped by the line they start on, each flagged when following a blank line
+///
+/// A node starting on the line where the previous one ends stays on its line, as do
+/// `else` and `catch`.
+fn split_lines(nodes: &[Node]) -> Vec<(bool, &[Node])> {
+    let mut lines = vec![];
+    let mut start = 0;
+    let mut blank = false;
+    for i in 1..nodes.len() {
+        let (prev, node) = (&nodes[i - 1], &nodes[i]);
+        let joined = node.first_line() <= prev.last_line()
+            || matches!(node.token(), Some(Token::Else | Token::Catch));
+        if !joined || prev.is_comment() {
+            lines.push((blank, &nodes[start..i]));
+            blank = node.first_line() > prev.last_line() + 1;
+            start = i;
+        }
+    }
+    if start < nodes.len() {
+        lines.push((blank, &nodes[start..]));
+    }
+    lines
+}
+
+/// Whether a space goes between two nodes of a line
+fn spaced(prev: &Node, node: &Node) -> bool {
+    match (prev.token(), node.token()) {
+        (_, Some(Token::Comma | Token::Colon)) => false,
+        (Some(Token::Dot | Token::Lambda), _) => false,
+        // A path such as `List.push`, or the field access in `x .name`
+        (_, Some(Token::Dot)) => end(prev) != start(node),
+        _ => true,
+    }
+}
+
+fn start(node: &Node) -> usize {
+    match node {
+        Node::Leaf(leaf) | Node::Group { open: leaf, .. } => leaf.span.start,
+    }
+}
+
+fn end(node: &Node) -> usize {
+    match node {
+        Node::Leaf(leaf)
+        | Node::Group {
+            close: Some(leaf), ..
+        } => leaf.span.end,
+        Node::Group { open, nodes, .. } => nodes.last().map_or(open.span.end, end),
+    }
+}
@@ -1,6 +1,7 @@
 use std::{
     fmt::Display,
     hash::{Hash, Hasher},
+    ops::Range,
 };
 
 use chumsky::{prelude::*, text::digits};
@@ -10,6 +11,7 @@ use eq_float::F64;
 pub enum Token {
     Def,
     Type,
+    Test,
     Eq,
     Lambda,
     Dot,
@@ -28,15 +30,50 @@ pub enum Token {
     Arrow,
     Then,
     Else,
+    Match,
+    Try,
+    Catch,
+    /// Start of a new top level item, a token starting a line outside of any delimiter
+    Dedent,
+    /// Text following `#` up to the end of the line, dropped before parsing
+    Comment(String),
 }
 
-// impl Display for Token {
-//     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-//         match self {
-//             _ => write!(f, "TODO"),
-//         }
-//     }
-// }
+pub type Span = Range<usize>;
+pub type Spanned<T> = (T, Span);
+
+impl Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Token::Def => write!(f, "def"),
+            Token::Type => write!(f, "type"),
+            Token::Test => write!(f, "test"),
+            Token::Eq => write!(f, "="),
+            Token::Lambda => write!(f, "\\"),
+            Token::Dot => write!(f, "."),
+            Token::Comma => write!(f, ","),
+            Token::Colon => write!(f, ":"),
+            Token::Pipe => write!(f, "|"),
+            Token::LowerName(n) => write!(f, "{n}"),
+            Token::UpperName(n) => write!(f, "{n}"),
+            Token::Literal(l) => write!(f, "{l}"),
+            Token::LParen => write!(f, "("),
+            Token::RParen => write!(f, ")"),
+            Token::LBrace => write!(f, "{{"),
+            Token::RBrace => write!(f, "}}"),
+            Token::LBracket => write!(f, "["),
+            Token::RBracket => write!(f, "]"),
+            Token::Arrow => write!(f, "->"),
+            Token::Then => write!(f, "then"),
+            Token::Else => write!(f, "else"),
+            Token::Match => write!(f, "match"),
+            Token::Try => write!(f, "try"),
+            Token::Catch => write!(f, "catch"),
+            Token::Dedent => write!(f, "new line"),
+            Token::Comment(c) => write!(f, "#{c}"),
+        }
+    }
+}
 
 #[derive(Debug, Clone)]
 pub enum Literal {
@@ -46,6 +83,17 @@ pub enum Literal {
     String(String),
 }
 
+impl Display for Literal {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Literal::Int(x) => write!(f, "{x}"),
+            Literal::Float(x) => write!(f, "{x:?}"),
+            Literal::Char(x) => write!(f, "'{x}'"),
+            Literal::String(x) => write!(f, "{x:?}"),
+        }
+    }
+}
+
 impl Hash for Literal {
     fn hash<H: Hasher>(&self, state: &mut H) {
         match self {
@@ -70,39 +118,101 @@ impl PartialEq for Literal {
 }
 impl Eq for Literal {}
 
-pub fn root() -> impl Parser<char, Vec<Token>, Error = Simple<char>> {
-    let token = choice((keyword(), literal(), lower_name(), upper_name())).padded();
+pub fn root() -> impl Parser<char, Vec<Spanned<Token>>, Error = Simple<char>> {
+    // Unknown chars are reported and skipped
+    let token = choice((comment(), literal(), keyword(), lower_name(), upper_name()))
+        .map_with_span(|token, span| (token, span))
+        .recover_with(skip_then_retry_until([]));
+
+    token.padded().repeated().padded().then_ignore(end())
+}
+
+/// Insert a `Token::Dedent` before every token starting a line at column 0,
+/// unless it is nested in `()`, `{}` or `[]`
+///
+/// `def`, `type` and `test` always start a new item, closing any delimiter left open before.
+pub fn layout(source: &str, tokens: Vec<Spanned<Token>>) -> Vec<Spanned<Token>> {
+    // Spans are char offsets
+    let chars: Vec<char> = source.chars().collect();
+    let mut res = Vec::with_capacity(tokens.len());
+    let mut depth = 0usize;
+
+    for (token, span) in tokens {
+        let line_start = span.start > 0 && chars[span.start - 1] == '\n';
+        if line_start && matches!(token, Token::Def | Token::Type | Token::Test) {
+            depth = 0;
+        }
+        if line_start && depth == 0 && !res.is_empty() {
+            res.push((Token::Dedent, span.start..span.start));
+        }
+
+        match token {
+            Token::LParen | Token::LBrace | Token::LBracket => depth += 1,
+            Token::RParen | Token::RBrace | Token::RBracket => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+        res.push((token, span));
+    }
+
+    res
+}
+
+pub fn lex(source: &str) -> Result<Vec<Spanned<Token>>, Vec<Simple<char>>> {
+    match lex_partial(source) {
+        (tokens, errors) if errors.is_empty() => Ok(tokens),
+        (_, errors) => Err(errors),
+    }
+}
 
-    let with_comments = comment()
-        .repeated()
-        .ignore_then(token)
-        .then_ignore(comment().repeated());
+/// Tokens of a source without comments, skipping what can't be lexed
+pub fn lex_partial(source: &str) -> (Vec<Spanned<Token>>, Vec<Simple<char>>) {
+    let (tokens, errors) = root().parse_recovery(source);
+    let tokens = tokens
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|(token, _)| !matches!(token, Token::Comment(_)))
+        .collect();
+    (layout(source, tokens), errors)
+}
 
-    with_comments.repeated().then_ignore(end())
+/// Tokens of a source including comments, a comment at column 0 starting a new item
+pub fn lex_with_comments(source: &str) -> Result<Vec<Spanned<Token>>, Vec<Simple<char>>> {
+    root().parse(source).map(|tokens| layout(source, tokens))
 }
 
 pub fn keyword() -> impl Parser<char, Token, Error = Simple<char>> {
     choice((
-        just("def").map(|_| Token::Def),
-        just("type").map(|_| Token::Type),
-        just("=").map(|_| Token::Eq),
         just("\\").map(|_| Token::Lambda),
         just(".").map(|_| Token::Dot),
         just(",").map(|_| Token::Comma),
         just(":").map(|_| Token::Colon),
-        just("|").map(|_| Token::Pipe),
         just("(").map(|_| Token::LParen),
         just(")").map(|_| Token::RParen),
         just("{").map(|_| Token::LBrace),
         just("}").map(|_| Token::RBrace),
         just("[").map(|_| Token::LBracket),
         just("]").map(|_| Token::RBracket),
-        just("->").map(|_| Token::Arrow),
-        just("then").map(|_| Token::Then),
-        just("else").map(|_| Token::Else),
     ))
 }
 
+/// Keywords are lexed as names first so that `define` or `==` are not split
+fn name_keyword(name: String) -> Token {
+    match name.as_str() {
+        "def" => Token::Def,
+        "type" => Token::Type,
+        "test" => Token::Test,
+        "then" => Token::Then,
+        "else" => Token::Else,
+        "match" => Token::Match,
+        "try" => Token::Try,
+        "catch" => Token::Catch,
+        "=" => Token::Eq,
+        "|" => Token::Pipe,
+        "->" => Token::Arrow,
+        _ => Token::LowerName(name),
+    }
+}
+
 pub fn lower_name() -> impl Parser<char, Token, Error = Simple<char>> {
     filter(|c| match c {
         'a'..='z' => true,
@@ -119,36 +229,24 @@ pub fn lower_name() -> impl Parser<char, Token, Error = Simple<char>> {
         })
         .repeated(),
     )
-    .padded()
     .map(|(head, tail)| [vec![head], tail].concat())
     .collect()
-    .map(Token::LowerName)
+    .map(name_keyword)
 }
 
 pub fn upper_name() -> impl Parser<char, Token, Error = Simple<char>> {
-    filter(|c| match c {
-        'A'..='Z' => true,
-        _ => false,
-    })
-    .then(
-        filter(|c| match c {
-            'a'..='z' => true,
-            'A'..='Z' => true,
-            '0'..='9' => true,
-            _ => false,
-        })
-        .repeated(),
-    )
-    .padded()
-    .map(|(head, tail)| [vec![head], tail].concat())
-    .collect()
-    .map(Token::UpperName)
+    filter(char::is_ascii_uppercase)
+        .then(filter(|c| matches!(c, 'a'..='z' | 'A'..='Z' | '0'..='9')).repeated())
+        .map(|(head, tail)| [vec![head], tail].concat())
+        .collect()
+        .map(Token::UpperName)
 }
 
-pub fn comment() -> impl Parser<char, (), Error = Simple<char>> {
-    just("#")
-        .then(take_until(text::newline().or(end())))
-        .ignored()
+pub fn comment() -> impl Parser<char, Token, Error = Simple<char>> {
+    just('#')
+        .ignore_then(filter(|c: &char| *c != '\n' && *c != '\r').repeated())
+        .collect()
+        .map(Token::Comment)
 }
 
 pub fn literal() -> impl Parser<char, Token, Error = Simple<char>> {
@@ -1,2 +1,144 @@
+use std::{
+    collections::HashMap,
+    fmt::{Display, Formatter},
+    hash::Hash,
+};
+
+use chumsky::{error::SimpleReason, prelude::*, Stream};
+
+use crate::parsing::{
+    lexer::{Span, Token},
+    parser::{Ast, Definition, TokenError},
+};
+
+pub mod cst;
+pub mod format;
 pub mod lexer;
 pub mod parser;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub span: Span,
+    pub message: String,
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} at {}..{}",
+            self.message, self.span.start, self.span.end
+        )
+    }
+}
+
+impl<T: Display + Hash + Eq> From<Simple<T>> for ParseError {
+    fn from(e: Simple<T>) -> Self {
+        let message = match e.reason() {
+            SimpleReason::Custom(msg) => msg.clone(),
+            SimpleReason::Unclosed { delimiter, .. } => format!("Unclosed delimiter `{delimiter}`"),
+            SimpleReason::Unexpected => {
+                let found = e
+                    .found()
+                    .map_or("end of input".to_string(), |t| format!("`{t}`"));
+                let mut expected: Vec<String> = e
+                    .expected()
+                    .map(|t| {
+                        t.as_ref()
+                            .map_or("end of input".to_string(), |t| format!("`{t}`"))
+                    })
+                    .collect();
+                expected.sort();
+                match (e.label(), expected.as_slice()) {
+                    (Some(label), _) => format!("Unexpected {found}, expected {label}"),
+                    (None, []) => format!("Unexpected {found}"),
+                    (None, _) => format!("Unexpected {found}, expected {}", expected.join(" or ")),
+                }
+            }
+        };
+
+        ParseError {
+            span: e.span(),
+            message,
+        }
+    }
+}
+
+impl From<TokenError> for ParseError {
+    fn from(e: TokenError) -> Self {
+        ParseError::from(*e.0)
+    }
+}
+
+/// Lex and parse a whole source file
+pub fn parse(source: &str) -> Result<Ast, Vec<ParseError>> {
+    match parse_partial(source) {
+        (ast, errors) if errors.is_empty() => Ok(ast),
+        (_, errors) => Err(errors),
+    }
+}
+
+/// Lex and parse a whole source file, recovering from errors
+///
+/// The `Ast` holds everything that could be parsed, the errors are sorted by position.
+pub fn parse_partial(source: &str) -> (Ast, Vec<ParseError>) {
+    let (tokens, lex_errors) = lexer::lex_partial(source);
+
+    let len = source.chars().count();
+    let (ast, parse_errors) =
+        parser::root().parse_recovery(Stream::from_iter(len..len + 1, tokens.into_iter()));
+
+    let mut errors: Vec<ParseError> = lex_errors.into_iter().map(ParseError::from).collect();
+    errors.extend(parse_errors.into_iter().map(ParseError::from));
+    errors.sort_by_key(|e| e.span.start);
+
+    let mut ast = ast.unwrap_or(Ast {
+        defs: vec![],
+        tests: vec![],
+        body: vec![],
+    });
+    attach_docs(source, &mut ast);
+    (ast, errors)
+}
+
+/// Give each definition the `##` comment lines right above it, without the `##`
+fn attach_docs(source: &str, ast: &mut Ast) {
+    let chars: Vec<char> = source.chars().collect();
+    let line_of = |offset: usize| chars[..offset].iter().filter(|c| **c == '\n').count();
+
+    let mut docs: HashMap<usize, &str> = HashMap::new();
+    let (tokens, _) = lexer::root().parse_recovery(source);
+    let tokens = tokens.unwrap_or_default();
+    for (token, span) in &tokens {
+        let Token::Comment(text) = token else {
+            continue;
+        };
+        let starts_line = chars[..span.start]
+            .iter()
+            .rev()
+            .take_while(|c| **c != '\n')
+            .all(|c| c.is_whitespace());
+        match text.strip_prefix('#') {
+            Some(doc) if starts_line => {
+                docs.insert(line_of(span.start), doc.strip_prefix(' ').unwrap_or(doc));
+            }
+            _ => {}
+        }
+    }
+
+    let doc = |span: &Span| {
+        let line = line_of(span.start);
+        let start = (0..line)
+            .rev()
+            .take_while(|l| docs.contains_key(l))
+            .last()?;
+        let lines: Vec<&str> = (start..line).map(|l| docs[&l]).collect();
+        Some(lines.join("\n"))
+    };
+    for def in &mut ast.defs {
+        match def {
+            Definition::Function(f) => f.doc = doc(&f.span),
+            Definition::Type(t) => t.doc = doc(&t.span),
+        }
+    }
+}
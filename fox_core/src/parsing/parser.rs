@@ -1,12 +1,14 @@
-use std::collections::HashMap;
-
 use chumsky::prelude::*;
 
-use crate::parsing::lexer::{Literal, Token};
+use crate::parsing::lexer::{Literal, Span, Token};
 
 #[derive(Debug, Clone)]
 pub struct Ast {
     pub defs: Vec<Definition>,
+    /// `test "name" { ... }` blocks, only run by a test runner
+    pub tests: Vec<TestDefinition>,
+    /// Top level code, evaluated once the definitions are loaded
+    pub body: ExprList,
 }
 
 #[derive(Debug, Clone)]
@@ -18,33 +20,51 @@ pub enum Definition {
 #[derive(Debug, PartialEq, Clone)]
 pub struct TypeDefinition {
     pub name: String,
+    /// Span of the name
+    pub span: Span,
     pub vars: Vec<String>,
     pub variants: Vec<VariantDefinition>,
+    /// Text of the `##` comment lines right above the definition
+    pub doc: Option<String>,
 }
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct VariantDefinition {
     pub name: String,
-    pub items: HashMap<String, String>,
+    pub span: Span,
+    /// Fields in declaration order, tuple variants use `_0`, `_1`, ...
+    pub items: Vec<(String, String)>,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct TestDefinition {
+    pub name: String,
+    /// Span of the name
+    pub span: Span,
+    pub body: ExprList,
 }
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct FunctionDefinition {
     pub name: String,
+    /// Span of the name
+    pub span: Span,
     pub ftype: Option<FunctionType>,
     pub body: ExprList,
+    /// Text of the `##` comment lines right above the definition
+    pub doc: Option<String>,
 }
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct FunctionType {
-    inputs: Vec<ParamType>,
-    output: Vec<ParamType>,
+    pub inputs: Vec<ParamType>,
+    pub output: Vec<ParamType>,
 }
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct ValueType {
-    name: String,
-    module: Vec<String>,
+    pub name: String,
+    pub module: Vec<String>,
 }
 
 #[derive(Debug, PartialEq, Clone)]
@@ -58,9 +78,36 @@ pub type ExprList = Vec<Expr>;
 #[derive(Debug, PartialEq, Clone)]
 pub enum Expr {
     Literal(Literal),
-    Term { name: String, module: Vec<String> },
-    Local(Vec<String>),
+    /// `span` is empty for terms produced by desugaring
+    Term {
+        name: String,
+        module: Vec<String>,
+        span: Span,
+    },
+    /// `-> a, b`, the span covering the whole binding
+    Local {
+        names: Vec<String>,
+        span: Span,
+    },
     Quote(ExprList),
+    Field(String),
+    Match(Vec<MatchArm>),
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct MatchArm {
+    pub pattern: Pattern,
+    pub body: ExprList,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Pattern {
+    /// Matches a variant and pushes its fields
+    Variant(String),
+    Literal(Literal),
+    /// Binds the value to a local
+    Bind(String),
+    Wildcard,
 }
 
 impl Expr {
@@ -76,26 +123,104 @@ impl Expr {
             ),
         };
 
-        Self::Term { name, module }
+        Self::Term {
+            name,
+            module,
+            span: 0..0,
+        }
     }
 }
 
-pub fn root() -> impl Parser<Token, Ast, Error = Simple<Token>> {
-    definition()
-        .repeated()
-        .then_ignore(end())
-        .map(|defs| Ast { defs })
+/// Error of the token parsers, boxed to keep their results small
+#[derive(Debug, Clone, PartialEq)]
+pub struct TokenError(pub Box<Simple<Token>>);
+
+impl chumsky::Error<Token> for TokenError {
+    type Span = Span;
+    type Label = &'static str;
+
+    fn expected_input_found<Iter: IntoIterator<Item = Option<Token>>>(
+        span: Span,
+        expected: Iter,
+        found: Option<Token>,
+    ) -> Self {
+        TokenError(Box::new(Simple::expected_input_found(
+            span, expected, found,
+        )))
+    }
+
+    fn unclosed_delimiter(
+        unclosed_span: Span,
+        unclosed: Token,
+        span: Span,
+        expected: Token,
+        found: Option<Token>,
+    ) -> Self {
+        TokenError(Box::new(Simple::unclosed_delimiter(
+            unclosed_span,
+            unclosed,
+            span,
+            expected,
+            found,
+        )))
+    }
+
+    fn with_label(self, label: &'static str) -> Self {
+        TokenError(Box::new(self.0.with_label(label)))
+    }
+
+    fn merge(self, other: Self) -> Self {
+        TokenError(Box::new(self.0.merge(*other.0)))
+    }
 }
 
-pub fn definition() -> impl Parser<Token, Definition, Error = Simple<Token>> {
+enum Item {
+    Definition(Definition),
+    Test(TestDefinition),
+    Exprs(ExprList),
+    Dedent,
+}
+
+pub fn root() -> impl Parser<Token, Ast, Error = TokenError> {
+    choice((
+        definition().map(Item::Definition),
+        test_def().map(Item::Test),
+        expr().map(Item::Exprs),
+        just(Token::Dedent).map(|_| Item::Dedent),
+    ))
+    // A broken item is skipped up to the next one
+    .recover_with(skip_until([Token::Dedent], |_| Item::Dedent))
+    .repeated()
+    .then_ignore(end())
+    .map(|items| {
+        let mut ast = Ast {
+            defs: vec![],
+            tests: vec![],
+            body: vec![],
+        };
+        for item in items {
+            match item {
+                Item::Definition(d) => ast.defs.push(d),
+                Item::Test(t) => ast.tests.push(t),
+                Item::Exprs(mut e) => ast.body.append(&mut e),
+                Item::Dedent => {}
+            }
+        }
+        ast
+    })
+}
+
+pub fn definition() -> impl Parser<Token, Definition, Error = TokenError> {
     choice((
         function_def().map(Definition::Function),
         type_def().map(Definition::Type),
     ))
 }
 
-pub fn type_def() -> impl Parser<Token, TypeDefinition, Error = Simple<Token>> {
-    let type_name = type_name().then(type_var().repeated());
+pub fn type_def() -> impl Parser<Token, TypeDefinition, Error = TokenError> {
+    let type_name = type_name()
+        .map_with_span(|name, span| (name, span))
+        .then(type_var().repeated());
 
     keyword(Token::Type)
         .ignore_then(type_name.clone())
@@ -105,28 +230,31 @@ pub fn type_def() -> impl Parser<Token, TypeDefinition, Error = Simple<Token>> {
                 .separated_by(just(Token::Pipe))
                 .allow_leading(),
         )
-        .map(|((name, vars), variants)| TypeDefinition {
+        .map(|(((name, span), vars), variants)| TypeDefinition {
             name,
+            span,
             vars,
             variants,
+            doc: None,
         })
 }
 
-pub fn type_variant() -> impl Parser<Token, VariantDefinition, Error = Simple<Token>> {
-    let tuple_var = upper_name()
-        .then(lower_or_upper_name().repeated())
-        .map(|(name, variants)| {
-            let mut items = HashMap::new();
-            let mut n = 0;
-            for v in variants {
-                let key = format!("_{n}");
-                items.insert(key, v);
-                n += 1;
-            }
-            VariantDefinition { name, items }
-        });
+pub fn type_variant() -> impl Parser<Token, VariantDefinition, Error = TokenError> {
+    let name = upper_name().map_with_span(|name, span| (name, span));
 
-    let record_var = upper_name()
+    let tuple_var =
+        name.clone()
+            .then(lower_or_upper_name().repeated())
+            .map(|((name, span), variants)| {
+                let items = variants
+                    .into_iter()
+                    .enumerate()
+                    .map(|(n, v)| (format!("_{n}"), v))
+                    .collect();
+                VariantDefinition { name, span, items }
+            });
+
+    let record_var = name
         .then(
             lower_name()
                 .then_ignore(just(Token::Colon))
@@ -134,24 +262,39 @@ pub fn type_variant() -> impl Parser<Token, VariantDefinition, Error = Simple<To
                 .separated_by(just(Token::Comma))
                 .delimited_by(just(Token::LBrace), just(Token::RBrace)),
         )
-        .map(|(name, variants)| {
+        .map(|((name, span), variants)| {
             let items = variants.into_iter().collect();
-            VariantDefinition { name, items }
+            VariantDefinition { name, span, items }
         });
 
     choice((record_var, tuple_var))
 }
 
-pub fn function_def() -> impl Parser<Token, FunctionDefinition, Error = Simple<Token>> {
+pub fn function_def() -> impl Parser<Token, FunctionDefinition, Error = TokenError> {
     keyword(Token::Def)
-        .ignore_then(term_name())
+        .ignore_then(term_name().map_with_span(|name, span| (name, span)))
         .then(function_type().or_not())
         .then_ignore(keyword(Token::Eq))
         .then(expr().repeated().flatten())
-        .map(|((name, ftype), body)| FunctionDefinition { name, ftype, body })
+        .map(|(((name, span), ftype), body)| FunctionDefinition {
+            name,
+            span,
+            ftype,
+            body,
+            doc: None,
+        })
+}
+
+pub fn test_def() -> impl Parser<Token, TestDefinition, Error = TokenError> {
+    let name = select! { Token::Literal(Literal::String(name)) => name }.labelled("test name");
+
+    keyword(Token::Test)
+        .ignore_then(name.map_with_span(|name, span| (name, span)))
+        .then(block(expr()))
+        .map(|((name, span), body)| TestDefinition { name, span, body })
 }
 
-pub fn function_type() -> impl Parser<Token, FunctionType, Error = Simple<Token>> {
+pub fn function_type() -> impl Parser<Token, FunctionType, Error = TokenError> {
     recursive(|ftype| {
         let plist = choice((
             value_type().map(ParamType::Value),
@@ -181,7 +324,7 @@ pub fn function_type() -> impl Parser<Token, FunctionType, Error = Simple<Token>
     })
 }
 
-pub fn value_type() -> impl Parser<Token, ValueType, Error = Simple<Token>> + Clone {
+pub fn value_type() -> impl Parser<Token, ValueType, Error = TokenError> + Clone {
     module_name()
         .separated_by(just(Token::Dot))
         .at_least(1)
@@ -194,7 +337,7 @@ pub fn value_type() -> impl Parser<Token, ValueType, Error = Simple<Token>> + Cl
         })
 }
 
-pub fn keyword(token: Token) -> impl Parser<Token, Token, Error = Simple<Token>> {
+pub fn keyword(token: Token) -> impl Parser<Token, Token, Error = TokenError> {
     // select! { t if t == token => t.clone() }
     filter_map(move |span, x: Token| match x {
         t if t == token => Ok(t.clone()),
@@ -206,89 +349,148 @@ pub fn keyword(token: Token) -> impl Parser<Token, Token, Error = Simple<Token>>
     })
 }
 
-pub fn expr() -> impl Parser<Token, ExprList, Error = Simple<Token>> {
+pub fn expr() -> impl Parser<Token, ExprList, Error = TokenError> + Clone {
     recursive(|expr| {
         let literal = select! { Token::Literal(l) => l.clone() }.map(Expr::Literal);
 
         let local = just(Token::Arrow)
             .ignore_then(term_name().separated_by(just(Token::Comma)))
-            .map(Expr::Local);
+            .map_with_span(|names, span| Expr::Local { names, span });
 
-        let quote = expr
-            .clone()
-            .repeated()
-            .flatten()
-            .delimited_by(just(Token::LBrace), just(Token::RBrace))
-            .map(Expr::Quote);
+        let quote = block(expr.clone()).map(Expr::Quote);
 
         let lambda = just(Token::Lambda)
-            .ignore_then(choice((literal.clone(), term())))
+            .ignore_then(choice((literal, term())))
             .map(|l| Expr::Quote(vec![l]));
 
+        let constructor = upper_name().map_with_span(|name, span| Expr::Term {
+            name,
+            module: vec![],
+            span,
+        });
+
+        let field = just(Token::Dot).ignore_then(term_name()).map(Expr::Field);
+
         choice((
             vec(literal),
             vec(term()),
+            vec(constructor),
+            vec(field),
+            vec(match_expr(expr.clone())),
             vec(local),
             vec(quote),
             vec(lambda),
-            tuple(expr.clone()),
-            list(expr.clone()),
-            table(expr.clone()),
+            tuple(expr.clone()).recover_with(nested_delimiters(
+                Token::LParen,
+                Token::RParen,
+                [
+                    (Token::LBrace, Token::RBrace),
+                    (Token::LBracket, Token::RBracket),
+                ],
+                |_| vec![],
+            )),
+            list(expr.clone())
+                .or(table(expr.clone()))
+                .recover_with(nested_delimiters(
+                    Token::LBracket,
+                    Token::RBracket,
+                    [
+                        (Token::LBrace, Token::RBrace),
+                        (Token::LParen, Token::RParen),
+                    ],
+                    |_| vec![],
+                )),
             then_else(expr.clone()),
+            try_catch(expr.clone()),
         ))
     })
 }
 
-pub fn lower_name() -> impl Parser<Token, String, Error = Simple<Token>> + Clone {
+pub fn match_expr(
+    expr: impl Parser<Token, ExprList, Error = TokenError> + Clone,
+) -> impl Parser<Token, Expr, Error = TokenError> {
+    let pattern = choice((
+        upper_name().map(Pattern::Variant),
+        select! { Token::Literal(l) => l.clone() }.map(Pattern::Literal),
+        term_name().map(|name| match name.as_str() {
+            "_" => Pattern::Wildcard,
+            _ => Pattern::Bind(name),
+        }),
+    ));
+
+    let body = choice((
+        block(expr.clone()),
+        just(Token::Colon).ignore_then(expr.repeated().flatten()),
+    ));
+
+    let arms = just(Token::Pipe)
+        .ignore_then(pattern)
+        .then(body)
+        .map(|(pattern, body)| MatchArm { pattern, body })
+        .repeated()
+        .at_least(1);
+
+    just(Token::Match)
+        .ignore_then(choice((
+            arms.clone()
+                .delimited_by(just(Token::LBrace), just(Token::RBrace)),
+            arms,
+        )))
+        .map(Expr::Match)
+}
+
+pub fn lower_name() -> impl Parser<Token, String, Error = TokenError> + Clone {
     select! { Token::LowerName(t) => t.clone() }
 }
 
-pub fn upper_name() -> impl Parser<Token, String, Error = Simple<Token>> + Clone {
+pub fn upper_name() -> impl Parser<Token, String, Error = TokenError> + Clone {
     select! { Token::UpperName(t) => t.clone() }
 }
 
-pub fn lower_or_upper_name() -> impl Parser<Token, String, Error = Simple<Token>> + Clone {
+pub fn lower_or_upper_name() -> impl Parser<Token, String, Error = TokenError> + Clone {
     choice((upper_name(), lower_name()))
 }
 
-pub fn term_name() -> impl Parser<Token, String, Error = Simple<Token>> + Clone {
+pub fn term_name() -> impl Parser<Token, String, Error = TokenError> + Clone {
     lower_name().labelled("term name")
 }
 
-pub fn module_name() -> impl Parser<Token, String, Error = Simple<Token>> + Clone {
+pub fn module_name() -> impl Parser<Token, String, Error = TokenError> + Clone {
     upper_name().labelled("module name")
 }
 
-pub fn type_name() -> impl Parser<Token, String, Error = Simple<Token>> + Clone {
+pub fn type_name() -> impl Parser<Token, String, Error = TokenError> + Clone {
     upper_name().labelled("type name")
 }
 
-pub fn type_var() -> impl Parser<Token, String, Error = Simple<Token>> + Clone {
+pub fn type_var() -> impl Parser<Token, String, Error = TokenError> + Clone {
     term_name().labelled("var name")
 }
 
-pub fn term() -> impl Parser<Token, Expr, Error = Simple<Token>> {
+pub fn term() -> impl Parser<Token, Expr, Error = TokenError> {
     let module = module_name()
         .separated_by(just(Token::Dot))
+        .at_least(1)
         .then_ignore(just(Token::Dot));
     module
         .or_not()
         .then(term_name())
-        .map(|(module, name)| Expr::Term {
-            name: name.clone(),
+        .map_with_span(|(module, name), span| Expr::Term {
+            name,
             module: module.unwrap_or_default(),
+            span,
         })
 }
 
 pub fn vec(
-    expr: impl Parser<Token, Expr, Error = Simple<Token>>,
-) -> impl Parser<Token, ExprList, Error = Simple<Token>> {
+    expr: impl Parser<Token, Expr, Error = TokenError>,
+) -> impl Parser<Token, ExprList, Error = TokenError> {
     expr.map(|it| vec![it])
 }
 
 pub fn list(
-    expr: impl Parser<Token, ExprList, Error = Simple<Token>>,
-) -> impl Parser<Token, ExprList, Error = Simple<Token>> {
+    expr: impl Parser<Token, ExprList, Error = TokenError>,
+) -> impl Parser<Token, ExprList, Error = TokenError> {
     expr.repeated()
         .flatten()
         .separated_by(just(Token::Comma))
@@ -304,8 +506,8 @@ pub fn list(
 }
 
 pub fn tuple(
-    expr: impl Parser<Token, ExprList, Error = Simple<Token>>,
-) -> impl Parser<Token, ExprList, Error = Simple<Token>> {
+    expr: impl Parser<Token, ExprList, Error = TokenError>,
+) -> impl Parser<Token, ExprList, Error = TokenError> {
     expr.repeated()
         .flatten()
         .separated_by(just(Token::Comma))
@@ -323,8 +525,8 @@ pub fn tuple(
 }
 
 pub fn table(
-    expr: impl Parser<Token, ExprList, Error = Simple<Token>> + Clone,
-) -> impl Parser<Token, ExprList, Error = Simple<Token>> {
+    expr: impl Parser<Token, ExprList, Error = TokenError> + Clone,
+) -> impl Parser<Token, ExprList, Error = TokenError> {
     let table_pair = expr
         .clone()
         .repeated()
@@ -346,21 +548,68 @@ pub fn table(
 }
 
 pub fn then_else(
-    expr: impl Parser<Token, ExprList, Error = Simple<Token>> + Clone,
-) -> impl Parser<Token, ExprList, Error = Simple<Token>> {
+    expr: impl Parser<Token, ExprList, Error = TokenError> + Clone,
+) -> impl Parser<Token, ExprList, Error = TokenError> {
     just(Token::Then)
-        .ignore_then(
-            expr.clone()
-                .repeated()
-                .flatten()
-                .delimited_by(just(Token::LBrace), just(Token::RBrace)),
-        )
+        .ignore_then(block(expr.clone()))
         .then_ignore(just(Token::Else))
-        .then(
-            expr.clone()
-                .repeated()
-                .flatten()
-                .delimited_by(just(Token::LBrace), just(Token::RBrace)),
-        )
+        .then(block(expr))
         .map(|it| vec![Expr::Quote(it.0), Expr::Quote(it.1), Expr::term("Core.??")])
 }
+
+/// `try { a } catch { b }` is desugared to `{ a } { b } Core.try`
+pub fn try_catch(
+    expr: impl Parser<Token, ExprList, Error = TokenError> + Clone,
+) -> impl Parser<Token, ExprList, Error = TokenError> {
+    just(Token::Try)
+        .ignore_then(block(expr.clone()))
+        .then_ignore(just(Token::Catch))
+        .then(block(expr))
+        .map(|(body, handler)| {
+            vec![
+                Expr::Quote(body),
+                Expr::Quote(handler),
+                Expr::term("Core.try"),
+            ]
+        })
+}
+
+/// Code between `{}`
+///
+/// A block still open at the next top level item is reported as unclosed and kept, a block
+/// with broken code is replaced by an empty one.
+pub fn block(
+    expr: impl Parser<Token, ExprList, Error = TokenError> + Clone,
+) -> impl Parser<Token, ExprList, Error = TokenError> + Clone {
+    let close = choice((
+        just(Token::RBrace).to(true),
+        just(Token::Dedent).rewind().to(false),
+        end().to(false),
+    ));
+
+    just(Token::LBrace)
+        .map_with_span(|_, span| span)
+        .then(expr.repeated().flatten())
+        .then(close)
+        .validate(|((open, body), closed), _, emit| {
+            if !closed {
+                emit(chumsky::Error::unclosed_delimiter(
+                    open.clone(),
+                    Token::LBrace,
+                    open,
+                    Token::RBrace,
+                    None,
+                ));
+            }
+            body
+        })
+        .recover_with(nested_delimiters(
+            Token::LBrace,
+            Token::RBrace,
+            [
+                (Token::LParen, Token::RParen),
+                (Token::LBracket, Token::RBracket),
+            ],
+            |_| vec![],
+        ))
+}
@@ -0,0 +1,106 @@
+use crate::engine::adt::Variant;
+use crate::engine::context::CallContext;
+use crate::engine::error::Error;
+use crate::engine::function::Function;
+use crate::engine::typing::{StackEffect, Type};
+use crate::engine::value::{Value, ValueData};
+
+/// Assertions, failing with `Error::User` at the position of the failing word
+pub fn natives() -> Vec<Function> {
+    let a = Type::var("a");
+    let effect = StackEffect::new;
+
+    vec![
+        Function::native("assert", assert).with_signature(effect(
+            vec![Type::named("Bool"), Type::named("String")],
+            vec![],
+        )),
+        Function::native("assertEq", assert_eq)
+            .with_signature(effect(vec![a.clone(), a.clone()], vec![])),
+        Function::native("assertNe", assert_ne).with_signature(effect(vec![a.clone(), a], vec![])),
+        Function::native("assertErr", assert_err),
+    ]
+}
+
+/// `cond "message" assert`
+fn assert(ctx: &mut CallContext) -> Result<(), Error> {
+    let message = ctx.pop_string()?;
+    if ctx.stack().pop()?.as_bool()? {
+        Ok(())
+    } else {
+        Err(failure(ctx, message))
+    }
+}
+
+/// `found expected assertEq`
+fn assert_eq(ctx: &mut CallContext) -> Result<(), Error> {
+    let expected = ctx.stack().pop()?;
+    let found = ctx.stack().pop()?;
+    if found.data_ref() == expected.data_ref() {
+        return Ok(());
+    }
+
+    let (found, expected) = (found.data_ref().repr(), expected.data_ref().repr());
+    let message = format!("values differ\n{}", diff(&found, &expected));
+    Err(failure(ctx, message))
+}
+
+fn assert_ne(ctx: &mut CallContext) -> Result<(), Error> {
+    let b = ctx.stack().pop()?;
+    let a = ctx.stack().pop()?;
+    if a.data_ref() != b.data_ref() {
+        return Ok(());
+    }
+    let message = format!("both values are `{}`", a.data_ref().repr());
+    Err(failure(ctx, message))
+}
+
+/// `result assertErr` checks a `Result` is an `Err`, `{ code } assertErr` checks running the
+/// code fails, leaving the stack as it was
+fn assert_err(ctx: &mut CallContext) -> Result<(), Error> {
+    let v = ctx.stack().pop()?;
+    if let ValueData::Quote(..) = v.data_ref() {
+        let snapshot = ctx.stack().clone();
+        return match ctx.call(&v) {
+            Err(e @ (Error::BudgetExceeded(_) | Error::Interrupted)) => Err(e),
+            Err(_) => {
+                *ctx.stack() = snapshot;
+                Ok(())
+            }
+            Ok(()) => Err(failure(ctx, "the code didn't fail".to_string())),
+        };
+    }
+
+    match is_err(&v)? {
+        true => Ok(()),
+        false => {
+            let message = format!("expected an `Err`, found `{}`", v.data_ref().repr());
+            Err(failure(ctx, message))
+        }
+    }
+}
+
+fn is_err(v: &Value) -> Result<bool, Error> {
+    let variant = v.as_native_typed::<Variant>()?;
+    if variant.type_name != "Result" {
+        return Err(Error::CastError("Result".to_string()));
+    }
+    Ok(variant.name == "Err")
+}
+
+fn failure(ctx: &CallContext, message: String) -> Error {
+    Error::User(format!("Assertion failed: {message}"), ctx.position())
+}
+
+/// Both values on their own line, with a `^` under the first difference
+fn diff(found: &str, expected: &str) -> String {
+    let common = found
+        .chars()
+        .zip(expected.chars())
+        .take_while(|(a, b)| a == b)
+        .count();
+    format!(
+        "   found: {found}\nexpected: {expected}\n{}^",
+        " ".repeat(10 + common)
+    )
+}
@@ -0,0 +1,187 @@
+use crate::engine::context::CallContext;
+use crate::engine::error::Error;
+use crate::engine::function::{Function, Intrinsic};
+use crate::engine::typing::{StackEffect, Type};
+
+/// Pure words, always available regardless of the granted capabilities
+pub fn natives() -> Vec<Function> {
+    let [a, b, c] = ["a", "b", "c"].map(Type::var);
+    let bool = || Type::named("Bool");
+    let effect = StackEffect::new;
+
+    vec![
+        Function::native("dup", dup).with_signature(effect(vec![a.clone()], vec![a.clone(); 2])),
+        Function::native("drop", drop).with_signature(effect(vec![a.clone()], vec![])),
+        Function::native("swap", swap).with_signature(effect(
+            vec![a.clone(), b.clone()],
+            vec![b.clone(), a.clone()],
+        )),
+        Function::native("over", over).with_signature(effect(
+            vec![a.clone(), b.clone()],
+            vec![a.clone(), b.clone(), a.clone()],
+        )),
+        Function::native("rot", rot).with_signature(effect(
+            vec![a.clone(), b.clone(), c.clone()],
+            vec![b.clone(), c, a.clone()],
+        )),
+        Function::native("true", |ctx| {
+            let stack = ctx.stack();
+            stack.push_bool(true);
+            Ok(())
+        })
+        .with_signature(effect(vec![], vec![bool()])),
+        Function::native("false", |ctx| {
+            let stack = ctx.stack();
+            stack.push_bool(false);
+            Ok(())
+        })
+        .with_signature(effect(vec![], vec![bool()])),
+        Function::native("not", not).with_signature(effect(vec![bool()], vec![bool()])),
+        Function::native("?", choose)
+            .with_signature(effect(vec![bool(), a.clone(), a.clone()], vec![a.clone()])),
+        Function::native("??", choose_lazy).with_intrinsic(Intrinsic::Choose),
+        Function::native("apply", apply).with_intrinsic(Intrinsic::Apply),
+        arithmetic("+", |a, b| a + b),
+        arithmetic("-", |a, b| a - b),
+        arithmetic("*", |a, b| a * b),
+        arithmetic("/", |a, b| a / b),
+        arithmetic("%", |a, b| a % b),
+        comparison("<", |a, b| a < b),
+        comparison(">", |a, b| a > b),
+        comparison("<=", |a, b| a <= b),
+        comparison(">=", |a, b| a >= b),
+        logic("and", |a, b| a && b),
+        logic("or", |a, b| a || b),
+        Function::native("==", eq).with_signature(effect(vec![a.clone(), a.clone()], vec![bool()])),
+        Function::native("!=", ne).with_signature(effect(vec![a.clone(), a], vec![bool()])),
+    ]
+}
+
+fn dup(ctx: &mut CallContext) -> Result<(), Error> {
+    let stack = ctx.stack();
+    let v = stack.peek()?;
+    stack.push(v);
+    Ok(())
+}
+
+fn drop(ctx: &mut CallContext) -> Result<(), Error> {
+    let stack = ctx.stack();
+    stack.pop()?;
+    Ok(())
+}
+
+fn swap(ctx: &mut CallContext) -> Result<(), Error> {
+    let stack = ctx.stack();
+    let b = stack.pop()?;
+    let a = stack.pop()?;
+    stack.push(b);
+    stack.push(a);
+    Ok(())
+}
+
+fn over(ctx: &mut CallContext) -> Result<(), Error> {
+    let stack = ctx.stack();
+    let b = stack.pop()?;
+    let a = stack.peek()?;
+    stack.push(b);
+    stack.push(a);
+    Ok(())
+}
+
+fn rot(ctx: &mut CallContext) -> Result<(), Error> {
+    let stack = ctx.stack();
+    let c = stack.pop()?;
+    let b = stack.pop()?;
+    let a = stack.pop()?;
+    stack.push(b);
+    stack.push(c);
+    stack.push(a);
+    Ok(())
+}
+
+fn not(ctx: &mut CallContext) -> Result<(), Error> {
+    let stack = ctx.stack();
+    let b = stack.pop()?.as_bool()?;
+    stack.push_bool(!b);
+    Ok(())
+}
+
+fn choose(ctx: &mut CallContext) -> Result<(), Error> {
+    let stack = ctx.stack();
+    let otherwise = stack.pop()?;
+    let then = stack.pop()?;
+    let cond = stack.pop()?.as_bool()?;
+    stack.push(if cond { then } else { otherwise });
+    Ok(())
+}
+
+/// Run one of two quotations depending on a condition
+fn choose_lazy(ctx: &mut CallContext) -> Result<(), Error> {
+    let stack = ctx.stack();
+    let otherwise = stack.pop()?;
+    let then = stack.pop()?;
+    let cond = stack.pop()?.as_bool()?;
+    ctx.call(if cond { &then } else { &otherwise })
+}
+
+fn apply(ctx: &mut CallContext) -> Result<(), Error> {
+    let f = ctx.stack().pop()?;
+    ctx.call(&f)
+}
+
+fn eq(ctx: &mut CallContext) -> Result<(), Error> {
+    let stack = ctx.stack();
+    let b = stack.pop()?;
+    let a = stack.pop()?;
+    stack.push_bool(a.data_ref() == b.data_ref());
+    Ok(())
+}
+
+fn ne(ctx: &mut CallContext) -> Result<(), Error> {
+    let stack = ctx.stack();
+    let b = stack.pop()?;
+    let a = stack.pop()?;
+    stack.push_bool(a.data_ref() != b.data_ref());
+    Ok(())
+}
+
+/// Works on both `Int` and `Float`, the operands having the same type
+fn arithmetic(name: &str, op: fn(f64, f64) -> f64) -> Function {
+    let t = Type::var("t");
+    Function::native(name, move |ctx| {
+        let stack = ctx.stack();
+        let b = stack.pop()?.as_num()?;
+        let a = stack.pop()?.as_num()?;
+        stack.push_num(op(a, b));
+        Ok(())
+    })
+    .with_signature(StackEffect::new(vec![t.clone(), t.clone()], vec![t]))
+}
+
+fn comparison(name: &str, op: fn(f64, f64) -> bool) -> Function {
+    Function::native(name, move |ctx| {
+        let stack = ctx.stack();
+        let b = stack.pop()?.as_num()?;
+        let a = stack.pop()?.as_num()?;
+        stack.push_bool(op(a, b));
+        Ok(())
+    })
+    .with_signature(StackEffect::new(
+        vec![Type::var("t"), Type::var("t")],
+        vec![Type::named("Bool")],
+    ))
+}
+
+fn logic(name: &str, op: fn(bool, bool) -> bool) -> Function {
+    Function::native(name, move |ctx| {
+        let stack = ctx.stack();
+        let b = stack.pop()?.as_bool()?;
+        let a = stack.pop()?.as_bool()?;
+        stack.push_bool(op(a, b));
+        Ok(())
+    })
+    .with_signature(StackEffect::new(
+        vec![Type::named("Bool"); 2],
+        vec![Type::named("Bool")],
+    ))
+}
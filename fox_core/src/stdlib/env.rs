@@ -0,0 +1,24 @@
+use std::env;
+
+use crate::engine::context::CallContext;
+use crate::engine::error::Error;
+use crate::engine::function::Function;
+
+pub fn natives() -> Vec<Function> {
+    vec![
+        Function::native("Env.get", env_get),
+        Function::native("Env.has?", env_has),
+    ]
+}
+
+fn env_get(ctx: &mut CallContext) -> Result<(), Error> {
+    let key = ctx.pop_string()?;
+    ctx.stack().push_string(&env::var(key).unwrap_or_default());
+    Ok(())
+}
+
+fn env_has(ctx: &mut CallContext) -> Result<(), Error> {
+    let key = ctx.pop_string()?;
+    ctx.stack().push_bool(env::var_os(key).is_some());
+    Ok(())
+}
@@ -0,0 +1,234 @@
+use std::cell::RefCell;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Lines, Write};
+use std::path::{Component, Path, PathBuf};
+use std::rc::Rc;
+
+use crate::engine::context::CallContext;
+use crate::engine::convert::ToFox;
+use crate::engine::error::Error;
+use crate::engine::function::Function;
+use crate::engine::stack::Stack;
+use crate::engine::value::{List, Value};
+use crate::stdlib::seq::{Seq, SeqSource};
+use crate::stdlib::tuple::Tuple;
+
+/// Words without side effects, always available
+pub fn natives() -> Vec<Function> {
+    vec![Function::native("path-join", path_join)]
+}
+
+pub fn console_natives() -> Vec<Function> {
+    vec![
+        Function::native("print", print),
+        Function::native("println", println),
+        Function::native("eprint", eprint),
+        Function::native("prompt", prompt),
+        Function::native("read-line", read_line),
+    ]
+}
+
+/// Filesystem words, only able to reach files located under `root`.
+///
+/// Failures to read or write push an `Err` with the message of the error, leaving outside
+/// of `root` fails with `Error::PermissionDenied`.
+pub fn fs_natives(root: impl Into<PathBuf>) -> Vec<Function> {
+    let root = Rc::new(root.into());
+
+    vec![
+        fs_word(&root, "read-file", |stack, path| {
+            stack.push(fs::read_to_string(path).map_err(message).to_fox());
+            Ok(())
+        }),
+        write_word(&root, "write-file", fs::write),
+        write_word(&root, "append-file", |path, content| {
+            OpenOptions::new()
+                .append(true)
+                .create(true)
+                .open(path)?
+                .write_all(content.as_bytes())
+        }),
+        fs_word(&root, "read-lines", |stack, path| {
+            let lines = File::open(path)
+                .map(|f| Seq::new(ReadLines(Rc::new(RefCell::new(BufReader::new(f).lines())))));
+            stack.push(lines.map_err(message).to_fox());
+            Ok(())
+        }),
+        fs_word(&root, "file-exists?", |stack, path| {
+            stack.push_bool(path.exists());
+            Ok(())
+        }),
+        fs_word(&root, "list-dir", |stack, path| {
+            let entries = fs::read_dir(path).and_then(|dir| {
+                let mut names = dir
+                    .map(|entry| Ok(entry?.file_name().to_string_lossy().into_owned()))
+                    .collect::<io::Result<Vec<String>>>()?;
+                names.sort();
+                Ok(names)
+            });
+            stack.push(entries.map_err(message).to_fox());
+            Ok(())
+        }),
+    ]
+}
+
+/// Word popping a path
+fn fs_word(
+    root: &Rc<PathBuf>,
+    name: &'static str,
+    run: impl Fn(&mut Stack, PathBuf) -> Result<(), Error> + 'static,
+) -> Function {
+    let root = root.clone();
+    Function::native(name, move |ctx| {
+        let path = sandboxed(&root, name, &ctx.pop_string()?)?;
+        run(ctx.stack(), path)
+    })
+}
+
+/// `path content word`, pushing `Ok ()` or `Err message`
+fn write_word(
+    root: &Rc<PathBuf>,
+    name: &'static str,
+    write: fn(PathBuf, String) -> io::Result<()>,
+) -> Function {
+    let root = root.clone();
+    Function::native(name, move |ctx| {
+        let content = ctx.pop_string()?;
+        let path = sandboxed(&root, name, &ctx.pop_string()?)?;
+        ctx.stack().push(unit(write(path, content)));
+        Ok(())
+    })
+}
+
+fn message(e: io::Error) -> String {
+    e.to_string()
+}
+
+/// `Ok ()` or `Err message`
+fn unit(result: io::Result<()>) -> Value {
+    result.map(|_| Tuple(vec![])).map_err(message).to_fox()
+}
+
+/// Lines of a file, read as they are pulled. Copies of the sequence share the file cursor.
+#[derive(Debug, Clone)]
+struct ReadLines(Rc<RefCell<Lines<BufReader<File>>>>);
+
+impl SeqSource for ReadLines {
+    fn next(&mut self, _: &mut CallContext) -> Result<Option<Value>, Error> {
+        match self.0.borrow_mut().next() {
+            Some(line) => Ok(Some(line.map_err(io_error)?.to_fox())),
+            None => Ok(None),
+        }
+    }
+
+    fn cloned(&self) -> Box<dyn SeqSource> {
+        Box::new(self.clone())
+    }
+}
+
+/// Text of a value as shown by `print`, strings are printed without quotes
+pub fn display(value: &Value) -> String {
+    match value.as_native_typed::<List>() {
+        Ok(list) if !list.0.is_empty() => {
+            list.to_string().unwrap_or_else(|_| value.data_ref().repr())
+        }
+        _ => value.data_ref().repr(),
+    }
+}
+
+fn io_error(e: io::Error) -> Error {
+    Error::IoError(e.to_string())
+}
+
+/// The value on top of the stack, a `Seq` is collected to print its values
+fn pop_printed(ctx: &mut CallContext) -> Result<Value, Error> {
+    let v = ctx.stack().pop()?;
+    match v.as_native_typed::<Seq>() {
+        Ok(seq) => Ok(List(seq.clone().collect(ctx)?).to_fox()),
+        Err(_) => Ok(v),
+    }
+}
+
+fn print(ctx: &mut CallContext) -> Result<(), Error> {
+    let v = pop_printed(ctx)?;
+    let output = ctx.output();
+    write!(output, "{}", display(&v)).map_err(io_error)?;
+    output.flush().map_err(io_error)
+}
+
+fn println(ctx: &mut CallContext) -> Result<(), Error> {
+    let v = pop_printed(ctx)?;
+    writeln!(ctx.output(), "{}", display(&v)).map_err(io_error)
+}
+
+fn eprint(ctx: &mut CallContext) -> Result<(), Error> {
+    let v = pop_printed(ctx)?;
+    let output = ctx.error_output();
+    write!(output, "{}", display(&v)).map_err(io_error)?;
+    output.flush().map_err(io_error)
+}
+
+/// Print a message then read a line
+fn prompt(ctx: &mut CallContext) -> Result<(), Error> {
+    print(ctx)?;
+    read_line(ctx)
+}
+
+/// Pushes `Ok line` without its line ending, or `Err` at the end of the input
+fn read_line(ctx: &mut CallContext) -> Result<(), Error> {
+    let mut line = String::new();
+    let line = match ctx.input().read_line(&mut line) {
+        Ok(0) => Err("end of input".to_string()),
+        Ok(_) => Ok(line.trim_end_matches(['\r', '\n']).to_string()),
+        Err(e) => Err(e.to_string()),
+    };
+    ctx.stack().push(line.to_fox());
+    Ok(())
+}
+
+fn path_join(ctx: &mut CallContext) -> Result<(), Error> {
+    let child = ctx.pop_string()?;
+    let parent = ctx.pop_string()?;
+    ctx.stack()
+        .push_string(&Path::new(&parent).join(child).to_string_lossy());
+    Ok(())
+}
+
+/// Resolve `path` relative to `root`, refusing anything that would escape it
+fn sandboxed(root: &Path, word: &str, path: &str) -> Result<PathBuf, Error> {
+    let denied = || {
+        Error::PermissionDenied(
+            word.to_string(),
+            format!("cannot access `{path}` outside of `{}`", root.display()),
+        )
+    };
+
+    let mut resolved = root.to_path_buf();
+    let mut depth = 0;
+    for component in Path::new(path).components() {
+        match component {
+            Component::Normal(c) => {
+                resolved.push(c);
+                depth += 1;
+            }
+            Component::CurDir => {}
+            Component::ParentDir if depth > 0 => {
+                resolved.pop();
+                depth -= 1;
+            }
+            Component::ParentDir | Component::RootDir | Component::Prefix(_) => {
+                return Err(denied())
+            }
+        }
+    }
+
+    // Symlinks inside the root could still point outside of it
+    let existing = resolved.ancestors().find_map(|p| p.canonicalize().ok());
+    if let (Ok(real_root), Some(real_path)) = (root.canonicalize(), existing) {
+        if !real_path.starts_with(real_root) {
+            return Err(denied());
+        }
+    }
+
+    Ok(resolved)
+}
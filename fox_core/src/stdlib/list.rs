@@ -1,18 +1,57 @@
-use std::fmt::Debug;
+use crate::engine::context::CallContext;
+use crate::engine::convert::ToFox;
+use crate::engine::error::Error;
+use crate::engine::function::Function;
+use crate::engine::value::List;
 
-use crate::engine::value::{NativeObject, Value};
+pub fn natives() -> Vec<Function> {
+    vec![
+        Function::native("List.empty", list_empty),
+        Function::native("List.push", list_push),
+        Function::native("List.len", list_len),
+        Function::native("List.get", list_get),
+        Function::native("concat", concat),
+    ]
+}
 
-#[derive(Debug, Clone)]
-pub struct List(pub Vec<Value>);
+fn list_empty(ctx: &mut CallContext) -> Result<(), Error> {
+    let stack = ctx.stack();
+    stack.push_native(List(vec![]));
+    Ok(())
+}
 
-impl NativeObject for List {
-    fn repr(&self) -> String {
-        let items: Vec<String> = self.0.iter().map(|it| it.data_ref().repr()).collect();
-        format!("[{}]", items.join(", "))
-    }
+fn list_push(ctx: &mut CallContext) -> Result<(), Error> {
+    let v = ctx.stack().pop()?;
+    let mut list = ctx.pop_list()?;
+    list.0.push(v);
+    ctx.stack().push_native(list);
+    Ok(())
+}
+
+fn list_len(ctx: &mut CallContext) -> Result<(), Error> {
+    let len = ctx.pop_list()?.0.len();
+    ctx.stack().push_num(len as f64);
+    Ok(())
+}
+
+/// `list index List.get` pushes `Some item`, or `None` when out of bounds
+fn list_get(ctx: &mut CallContext) -> Result<(), Error> {
+    let index = ctx.stack().pop_as::<i64>()?;
+    let items = ctx.pop_list()?.0;
+    let item = usize::try_from(index)
+        .ok()
+        .and_then(|i| items.get(i))
+        .cloned();
+    ctx.stack().push(item.to_fox());
+    Ok(())
+}
 
-    fn cloned(&self) -> Box<dyn NativeObject> {
-        let cloned: List = Clone::clone(self);
-        Box::new(cloned)
+/// `[a, b] concat` joins lists, such as the strings `["a", "b"]` into `"ab"`
+fn concat(ctx: &mut CallContext) -> Result<(), Error> {
+    let mut items = vec![];
+    for list in ctx.pop_list()?.0 {
+        items.extend(list.as_native_typed::<List>()?.0.iter().cloned());
     }
+    ctx.stack().push_native(List(items));
+    Ok(())
 }
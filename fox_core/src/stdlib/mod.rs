@@ -1 +1,31 @@
+use crate::engine::capability::Capability;
+use crate::engine::env::Env;
+use crate::engine::function::Function;
+
+pub mod assert;
+pub mod base;
+pub mod env;
+pub mod io;
 pub mod list;
+pub mod option;
+pub mod random;
+pub mod result;
+pub mod seq;
+pub mod string;
+pub mod table;
+pub mod time;
+pub mod tuple;
+
+pub fn register(definitions: &mut Env<Function>, natives: Vec<Function>) {
+    for f in natives {
+        definitions.set(f.name().to_string(), f);
+    }
+}
+
+/// Register every word of a module as failing with `Error::PermissionDenied`
+pub fn deny(definitions: &mut Env<Function>, natives: Vec<Function>, capability: Capability) {
+    for f in natives {
+        let name = f.name().to_string();
+        definitions.set(name.clone(), Function::denied(name, capability));
+    }
+}
@@ -0,0 +1,105 @@
+use crate::engine::adt;
+use crate::engine::context::CallContext;
+use crate::engine::convert::{FromFox, ToFox};
+use crate::engine::error::Error;
+use crate::engine::function::Function;
+use crate::engine::typing::{StackEffect, Type};
+use crate::engine::value::Value;
+
+/// `Option` constructors and combinators, part of the prelude
+pub fn natives() -> Vec<Function> {
+    let t = || Type::var("t");
+    let u = || Type::var("u");
+    let option = |t: Type| Type::applied(vec![t], "Option");
+    let effect = StackEffect::new;
+
+    vec![
+        adt::constructor("Option", "None", vec![])
+            .with_signature(effect(vec![], vec![option(t())])),
+        adt::constructor("Option", "Some", vec!["_0".to_string()])
+            .with_signature(effect(vec![t()], vec![option(t())])),
+        Function::native("Option.map", option_map).with_signature(effect(
+            vec![option(t()), Type::quote(vec![t()], vec![u()])],
+            vec![option(u())],
+        )),
+        Function::native("Option.and-then", option_and_then).with_signature(effect(
+            vec![option(t()), Type::quote(vec![t()], vec![option(u())])],
+            vec![option(u())],
+        )),
+        Function::native("Option.unwrap-or", option_unwrap_or)
+            .with_signature(effect(vec![option(t()), t()], vec![t()])),
+        Function::native("Option.is-some", option_is_some)
+            .with_signature(effect(vec![option(t())], vec![Type::named("Bool")])),
+        Function::native("Option.filter", option_filter).with_signature(effect(
+            vec![
+                option(t()),
+                Type::quote(vec![t()], vec![Type::named("Bool")]),
+            ],
+            vec![option(t())],
+        )),
+    ]
+}
+
+/// Pop a quotation, then the option it applies to
+fn pop_with_quote(ctx: &mut CallContext) -> Result<(Option<Value>, Value), Error> {
+    let quote = ctx.stack().pop()?;
+    let option = ctx.stack().pop_as::<Option<Value>>()?;
+    Ok((option, quote))
+}
+
+fn option_map(ctx: &mut CallContext) -> Result<(), Error> {
+    let (option, quote) = pop_with_quote(ctx)?;
+    let mapped = match option {
+        Some(v) => {
+            ctx.stack().push(v);
+            ctx.call(&quote)?;
+            Some(ctx.stack().pop()?)
+        }
+        None => None,
+    };
+    ctx.stack().push(mapped.to_fox());
+    Ok(())
+}
+
+/// The quotation returns an `Option` itself
+fn option_and_then(ctx: &mut CallContext) -> Result<(), Error> {
+    match pop_with_quote(ctx)? {
+        (Some(v), quote) => {
+            ctx.stack().push(v);
+            ctx.call(&quote)
+        }
+        (None, _) => {
+            ctx.stack().push(None::<Value>.to_fox());
+            Ok(())
+        }
+    }
+}
+
+fn option_unwrap_or(ctx: &mut CallContext) -> Result<(), Error> {
+    let stack = ctx.stack();
+    let default = stack.pop()?;
+    let option = stack.pop_as::<Option<Value>>()?;
+    stack.push(option.unwrap_or(default));
+    Ok(())
+}
+
+fn option_is_some(ctx: &mut CallContext) -> Result<(), Error> {
+    let stack = ctx.stack();
+    let option = stack.pop_as::<Option<Value>>()?;
+    stack.push_bool(option.is_some());
+    Ok(())
+}
+
+fn option_filter(ctx: &mut CallContext) -> Result<(), Error> {
+    let (option, quote) = pop_with_quote(ctx)?;
+    let kept = match option {
+        Some(v) => {
+            ctx.stack().push(v.clone());
+            ctx.call(&quote)?;
+            bool::from_fox(ctx.stack().pop()?)?.then_some(v)
+        }
+        None => None,
+    };
+    ctx.stack().push(kept.to_fox());
+    Ok(())
+}
@@ -0,0 +1,41 @@
+use std::cell::Cell;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::rc::Rc;
+
+use crate::engine::function::Function;
+
+pub fn natives() -> Vec<Function> {
+    let state = Rc::new(Cell::new(seed()));
+
+    let s = state.clone();
+    let float = Function::native("Random.float", move |ctx| {
+        let stack = ctx.stack();
+        stack.push_num(next_float(&s));
+        Ok(())
+    });
+
+    let s = state;
+    let int = Function::native("Random.int", move |ctx| {
+        let stack = ctx.stack();
+        let n = stack.pop()?.as_num()?;
+        stack.push_num((next_float(&s) * n).floor());
+        Ok(())
+    });
+
+    vec![float, int]
+}
+
+fn seed() -> u64 {
+    RandomState::new().build_hasher().finish() | 1
+}
+
+/// xorshift64*, uniform in `[0, 1)`
+fn next_float(state: &Cell<u64>) -> f64 {
+    let mut x = state.get();
+    x ^= x >> 12;
+    x ^= x << 25;
+    x ^= x >> 27;
+    state.set(x);
+    (x.wrapping_mul(0x2545_f491_4f6c_dd1d) >> 11) as f64 / (1u64 << 53) as f64
+}
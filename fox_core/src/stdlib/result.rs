@@ -0,0 +1,75 @@
+use crate::engine::adt::{self, Variant};
+use crate::engine::context::CallContext;
+use crate::engine::convert::ToFox;
+use crate::engine::error::Error;
+use crate::engine::function::{Function, Intrinsic};
+use crate::engine::value::Value;
+
+/// `Result` constructors, error propagation and recovery
+pub fn natives() -> Vec<Function> {
+    vec![
+        adt::constructor("Result", "Ok", vec!["_0".to_string()]),
+        adt::constructor("Result", "Err", vec!["_0".to_string()]),
+        Function::native("try?", propagate),
+        Function::native("try", try_catch).with_intrinsic(Intrinsic::Try),
+        Function::native("panic", panic),
+    ]
+}
+
+/// Unwrap an `Ok`, or make the enclosing word return the `Err`
+fn propagate(ctx: &mut CallContext) -> Result<(), Error> {
+    let result = ctx.stack().pop()?;
+    let variant = result.as_native_typed::<Variant>()?;
+    if variant.type_name != "Result" {
+        return Err(Error::CastError("Result".to_string()));
+    }
+    match (variant.name.as_str(), variant.first()) {
+        ("Ok", Some(v)) => {
+            let v = v.clone();
+            ctx.stack().push(v);
+            Ok(())
+        }
+        ("Err", Some(_)) => Err(ctx.propagate(result.clone())),
+        _ => Err(Error::CastError("Result".to_string())),
+    }
+}
+
+/// `{ body } { handler } try`: run `body`, and if it fails restore the stack, push the error
+/// and run `handler`. Exhausted budgets and interruptions are never caught.
+fn try_catch(ctx: &mut CallContext) -> Result<(), Error> {
+    let handler = ctx.stack().pop()?;
+    let body = ctx.stack().pop()?;
+    let snapshot = ctx.stack().clone();
+
+    match ctx.call(&body) {
+        Err(e @ (Error::BudgetExceeded(_) | Error::Interrupted)) => Err(e),
+        Err(e) => {
+            *ctx.stack() = snapshot;
+            let error = error_value(e, ctx.take_propagated());
+            ctx.stack().push(error);
+            ctx.call(&handler)
+        }
+        Ok(()) => Ok(()),
+    }
+}
+
+/// The payload of a propagated `Err`, or the message of any other error
+pub(crate) fn error_value(error: Error, propagated: Option<Value>) -> Value {
+    let result = match error {
+        Error::Propagated(_) => propagated,
+        _ => None,
+    };
+    let payload = result
+        .as_ref()
+        .and_then(|r| r.as_native_typed::<Variant>().ok())
+        .and_then(Variant::first);
+    match payload {
+        Some(v) => v.clone(),
+        None => error.to_string().to_fox(),
+    }
+}
+
+fn panic(ctx: &mut CallContext) -> Result<(), Error> {
+    let message = ctx.pop_string()?;
+    Err(Error::User(message, ctx.position()))
+}
@@ -0,0 +1,587 @@
+use std::collections::VecDeque;
+use std::fmt::Debug;
+use std::rc::Rc;
+
+use crate::engine::context::CallContext;
+use crate::engine::convert::{FromFox, ToFox};
+use crate::engine::error::Error;
+use crate::engine::function::{Function, Intrinsic};
+use crate::engine::generator::Generator;
+use crate::engine::value::{List, NativeObject, Value};
+use crate::stdlib::tuple::Tuple;
+
+/// Values produced one at a time, possibly by running Fox code.
+///
+/// Implement it, then return a [`Seq`] from `NativeObject::seq`, to make a native type
+/// iterable.
+pub trait SeqSource: Debug {
+    fn next(&mut self, ctx: &mut CallContext) -> Result<Option<Value>, Error>;
+    fn cloned(&self) -> Box<dyn SeqSource>;
+}
+
+/// Lazy sequence, nothing is computed until a value is pulled
+#[derive(Debug)]
+pub struct Seq(Box<dyn SeqSource>);
+
+impl Seq {
+    pub fn new(source: impl SeqSource + 'static) -> Self {
+        Seq(Box::new(source))
+    }
+
+    /// Pull the next value, counted as an instruction of the running evaluation
+    pub fn next(&mut self, ctx: &mut CallContext) -> Result<Option<Value>, Error> {
+        ctx.tick()?;
+        self.0.next(ctx)
+    }
+
+    pub fn collect(mut self, ctx: &mut CallContext) -> Result<Vec<Value>, Error> {
+        let mut items = vec![];
+        while let Some(v) = self.next(ctx)? {
+            items.push(v);
+        }
+        Ok(items)
+    }
+}
+
+impl Clone for Seq {
+    fn clone(&self) -> Self {
+        Seq(self.0.cloned())
+    }
+}
+
+impl NativeObject for Seq {
+    fn repr(&self) -> String {
+        "Seq".to_string()
+    }
+
+    fn cloned(&self) -> Box<dyn NativeObject> {
+        Box::new(self.clone())
+    }
+
+    fn seq(&self) -> Option<Seq> {
+        Some(self.clone())
+    }
+}
+
+/// Sequence over any iterable value: lists, strings, tables, tuples, seqs and user types
+pub fn to_seq(value: &Value) -> Result<Seq, Error> {
+    value
+        .as_native()
+        .ok()
+        .and_then(|o| o.seq())
+        .ok_or(Error::CastError("Seq".to_string()))
+}
+
+macro_rules! impl_cloned {
+    () => {
+        fn cloned(&self) -> Box<dyn SeqSource> {
+            Box::new(self.clone())
+        }
+    };
+}
+
+#[derive(Debug, Clone)]
+pub struct Items {
+    items: Rc<Vec<Value>>,
+    index: usize,
+}
+
+impl Items {
+    pub fn new(items: Vec<Value>) -> Self {
+        Items {
+            items: Rc::new(items),
+            index: 0,
+        }
+    }
+}
+
+impl SeqSource for Items {
+    fn next(&mut self, _: &mut CallContext) -> Result<Option<Value>, Error> {
+        let item = self.items.get(self.index).cloned();
+        self.index += 1;
+        Ok(item)
+    }
+
+    impl_cloned!();
+}
+
+/// Numbers from `next`, up to `end` excluded or endless
+#[derive(Debug, Clone)]
+struct Range {
+    next: f64,
+    end: Option<f64>,
+}
+
+impl SeqSource for Range {
+    fn next(&mut self, _: &mut CallContext) -> Result<Option<Value>, Error> {
+        match self.end {
+            Some(end) if self.next >= end => Ok(None),
+            _ => {
+                self.next += 1.0;
+                Ok(Some((self.next - 1.0).to_fox()))
+            }
+        }
+    }
+
+    impl_cloned!();
+}
+
+/// Run `quote` on `args`, returning the value it leaves on top of the stack
+fn call(ctx: &mut CallContext, quote: &Value, args: Vec<Value>) -> Result<Value, Error> {
+    ctx.stack().push_many(args);
+    ctx.call(quote)?;
+    ctx.stack().pop()
+}
+
+#[derive(Debug, Clone)]
+struct Unfold {
+    state: Option<Value>,
+    quote: Value,
+}
+
+impl SeqSource for Unfold {
+    fn next(&mut self, ctx: &mut CallContext) -> Result<Option<Value>, Error> {
+        let Some(state) = self.state.take() else {
+            return Ok(None);
+        };
+        let step = Option::<(Value, Value)>::from_fox(call(ctx, &self.quote, vec![state])?)?;
+        Ok(step.map(|(value, state)| {
+            self.state = Some(state);
+            value
+        }))
+    }
+
+    impl_cloned!();
+}
+
+#[derive(Debug, Clone)]
+struct Map {
+    inner: Seq,
+    quote: Value,
+}
+
+impl SeqSource for Map {
+    fn next(&mut self, ctx: &mut CallContext) -> Result<Option<Value>, Error> {
+        match self.inner.next(ctx)? {
+            Some(v) => Ok(Some(call(ctx, &self.quote, vec![v])?)),
+            None => Ok(None),
+        }
+    }
+
+    impl_cloned!();
+}
+
+#[derive(Debug, Clone)]
+struct Filter {
+    inner: Seq,
+    quote: Value,
+}
+
+impl SeqSource for Filter {
+    fn next(&mut self, ctx: &mut CallContext) -> Result<Option<Value>, Error> {
+        while let Some(v) = self.inner.next(ctx)? {
+            if call(ctx, &self.quote, vec![v.clone()])?.as_bool()? {
+                return Ok(Some(v));
+            }
+        }
+        Ok(None)
+    }
+
+    impl_cloned!();
+}
+
+#[derive(Debug, Clone)]
+struct Take {
+    inner: Seq,
+    n: usize,
+}
+
+impl SeqSource for Take {
+    fn next(&mut self, ctx: &mut CallContext) -> Result<Option<Value>, Error> {
+        if self.n == 0 {
+            return Ok(None);
+        }
+        self.n -= 1;
+        self.inner.next(ctx)
+    }
+
+    impl_cloned!();
+}
+
+#[derive(Debug, Clone)]
+struct Skip {
+    inner: Seq,
+    n: usize,
+}
+
+impl SeqSource for Skip {
+    fn next(&mut self, ctx: &mut CallContext) -> Result<Option<Value>, Error> {
+        while self.n > 0 {
+            self.n -= 1;
+            if self.inner.next(ctx)?.is_none() {
+                return Ok(None);
+            }
+        }
+        self.inner.next(ctx)
+    }
+
+    impl_cloned!();
+}
+
+#[derive(Debug, Clone)]
+struct TakeWhile {
+    inner: Seq,
+    quote: Value,
+    done: bool,
+}
+
+impl SeqSource for TakeWhile {
+    fn next(&mut self, ctx: &mut CallContext) -> Result<Option<Value>, Error> {
+        if self.done {
+            return Ok(None);
+        }
+        match self.inner.next(ctx)? {
+            Some(v) if call(ctx, &self.quote, vec![v.clone()])?.as_bool()? => Ok(Some(v)),
+            _ => {
+                self.done = true;
+                Ok(None)
+            }
+        }
+    }
+
+    impl_cloned!();
+}
+
+/// `(index, value)` tuples
+#[derive(Debug, Clone)]
+struct Enumerate {
+    inner: Seq,
+    index: usize,
+}
+
+impl SeqSource for Enumerate {
+    fn next(&mut self, ctx: &mut CallContext) -> Result<Option<Value>, Error> {
+        let Some(v) = self.inner.next(ctx)? else {
+            return Ok(None);
+        };
+        let index = self.index.to_fox();
+        self.index += 1;
+        Ok(Some(tuple(vec![index, v])))
+    }
+
+    impl_cloned!();
+}
+
+/// `(a, b)` tuples, until one of the sequences ends
+#[derive(Debug, Clone)]
+struct Zip {
+    a: Seq,
+    b: Seq,
+}
+
+impl SeqSource for Zip {
+    fn next(&mut self, ctx: &mut CallContext) -> Result<Option<Value>, Error> {
+        match (self.a.next(ctx)?, self.b.next(ctx)?) {
+            (Some(a), Some(b)) => Ok(Some(tuple(vec![a, b]))),
+            _ => Ok(None),
+        }
+    }
+
+    impl_cloned!();
+}
+
+#[derive(Debug, Clone)]
+struct Chain {
+    first: Option<Seq>,
+    second: Seq,
+}
+
+impl SeqSource for Chain {
+    fn next(&mut self, ctx: &mut CallContext) -> Result<Option<Value>, Error> {
+        if let Some(first) = &mut self.first {
+            match first.next(ctx)? {
+                Some(v) => return Ok(Some(v)),
+                None => self.first = None,
+            }
+        }
+        self.second.next(ctx)
+    }
+
+    impl_cloned!();
+}
+
+#[derive(Debug, Clone)]
+struct FlatMap {
+    inner: Seq,
+    quote: Value,
+    current: Option<Seq>,
+}
+
+impl SeqSource for FlatMap {
+    fn next(&mut self, ctx: &mut CallContext) -> Result<Option<Value>, Error> {
+        loop {
+            if let Some(current) = &mut self.current {
+                if let Some(v) = current.next(ctx)? {
+                    return Ok(Some(v));
+                }
+            }
+            match self.inner.next(ctx)? {
+                Some(v) => self.current = Some(to_seq(&call(ctx, &self.quote, vec![v])?)?),
+                None => return Ok(None),
+            }
+        }
+    }
+
+    impl_cloned!();
+}
+
+/// Lists of `size` values, the last one may be shorter
+#[derive(Debug, Clone)]
+struct Chunks {
+    inner: Seq,
+    size: usize,
+}
+
+impl SeqSource for Chunks {
+    fn next(&mut self, ctx: &mut CallContext) -> Result<Option<Value>, Error> {
+        let mut chunk = vec![];
+        while chunk.len() < self.size {
+            match self.inner.next(ctx)? {
+                Some(v) => chunk.push(v),
+                None => break,
+            }
+        }
+        Ok((!chunk.is_empty()).then(|| list(chunk)))
+    }
+
+    impl_cloned!();
+}
+
+/// Overlapping lists of `size` consecutive values
+#[derive(Debug, Clone)]
+struct Windows {
+    inner: Seq,
+    size: usize,
+    window: VecDeque<Value>,
+}
+
+impl SeqSource for Windows {
+    fn next(&mut self, ctx: &mut CallContext) -> Result<Option<Value>, Error> {
+        if self.window.len() == self.size {
+            self.window.pop_front();
+        }
+        while self.window.len() < self.size {
+            match self.inner.next(ctx)? {
+                Some(v) => self.window.push_back(v),
+                None => return Ok(None),
+            }
+        }
+        Ok(Some(list(self.window.iter().cloned().collect())))
+    }
+
+    impl_cloned!();
+}
+
+fn tuple(items: Vec<Value>) -> Value {
+    Tuple(items).to_fox()
+}
+
+fn list(items: Vec<Value>) -> Value {
+    List(items).to_fox()
+}
+
+pub fn natives() -> Vec<Function> {
+    vec![
+        Function::native("seq", |ctx| {
+            let seq = to_seq(&ctx.stack().pop()?)?;
+            ctx.stack().push_native(seq);
+            Ok(())
+        }),
+        Function::native("range", |ctx| {
+            let stack = ctx.stack();
+            let end = stack.pop_as::<f64>()?;
+            let start = stack.pop_as::<f64>()?;
+            stack.push_native(Seq::new(Range {
+                next: start,
+                end: Some(end),
+            }));
+            Ok(())
+        }),
+        Function::native("Seq.count-from", |ctx| {
+            let stack = ctx.stack();
+            let start = stack.pop_as::<f64>()?;
+            stack.push_native(Seq::new(Range {
+                next: start,
+                end: None,
+            }));
+            Ok(())
+        }),
+        Function::native("Seq.unfold", |ctx| {
+            let quote = ctx.stack().pop()?;
+            let state = ctx.stack().pop()?;
+            ctx.stack().push_native(Seq::new(Unfold {
+                state: Some(state),
+                quote,
+            }));
+            Ok(())
+        }),
+        // `{ 1 yield 2 yield } generator` is a `Seq` of 1 and 2
+        Function::native("generator", |ctx| {
+            let body = ctx.stack().pop()?;
+            let (body, env) = body.as_quote()?;
+            let generator = Generator::new(body, env.child());
+            ctx.stack().push_native(Seq::new(generator));
+            Ok(())
+        }),
+        // Only reached when not run by a generator, which suspends instead
+        Function::native("yield", |_| Err(Error::YieldOutsideGenerator))
+            .with_intrinsic(Intrinsic::Yield),
+        combinator("map", |inner, quote| Ok(Seq::new(Map { inner, quote }))),
+        combinator("filter", |inner, quote| {
+            Ok(Seq::new(Filter { inner, quote }))
+        }),
+        combinator("take", |inner, n| {
+            let n = usize::from_fox(n)?;
+            Ok(Seq::new(Take { inner, n }))
+        }),
+        combinator("skip", |inner, n| {
+            let n = usize::from_fox(n)?;
+            Ok(Seq::new(Skip { inner, n }))
+        }),
+        // `skip`, qualified since `drop` removes the top of the stack
+        combinator("Seq.drop", |inner, n| {
+            let n = usize::from_fox(n)?;
+            Ok(Seq::new(Skip { inner, n }))
+        }),
+        combinator("take-while", |inner, quote| {
+            Ok(Seq::new(TakeWhile {
+                inner,
+                quote,
+                done: false,
+            }))
+        }),
+        Function::native("enumerate", |ctx| {
+            let input = ctx.stack().pop()?;
+            ctx.stack().push_native(Seq::new(Enumerate {
+                inner: to_seq(&input)?,
+                index: 0,
+            }));
+            Ok(())
+        }),
+        combinator("zip", |a, b| {
+            let b = to_seq(&b)?;
+            Ok(Seq::new(Zip { a, b }))
+        }),
+        combinator("chain", |first, second| {
+            let second = to_seq(&second)?;
+            Ok(Seq::new(Chain {
+                first: Some(first),
+                second,
+            }))
+        }),
+        combinator("flat-map", |inner, quote| {
+            Ok(Seq::new(FlatMap {
+                inner,
+                quote,
+                current: None,
+            }))
+        }),
+        combinator("chunks", |inner, size| {
+            let size = positive(size)?;
+            Ok(Seq::new(Chunks { inner, size }))
+        }),
+        combinator("windows", |inner, size| {
+            let size = positive(size)?;
+            Ok(Seq::new(Windows {
+                inner,
+                size,
+                window: VecDeque::new(),
+            }))
+        }),
+        Function::native("collect", |ctx| {
+            let seq = to_seq(&ctx.stack().pop()?)?;
+            let items = seq.collect(ctx)?;
+            ctx.stack().push_native(List(items));
+            Ok(())
+        }),
+        Function::native("reduce", reduce),
+        Function::native("fold", fold),
+        Function::native("sum", |ctx| arithmetic(ctx, 0.0, |a, b| a + b)),
+        Function::native("product", |ctx| arithmetic(ctx, 1.0, |a, b| a * b)),
+        Function::native("count", |ctx| {
+            let mut seq = to_seq(&ctx.stack().pop()?)?;
+            let mut count = 0;
+            while seq.next(ctx)?.is_some() {
+                count += 1;
+            }
+            ctx.stack().push_num(count as f64);
+            Ok(())
+        }),
+        Function::native("for-each", |ctx| {
+            let quote = ctx.stack().pop()?;
+            let mut seq = to_seq(&ctx.stack().pop()?)?;
+            while let Some(v) = seq.next(ctx)? {
+                ctx.stack().push(v);
+                ctx.call(&quote)?;
+            }
+            Ok(())
+        })
+        .with_intrinsic(Intrinsic::ForEach),
+    ]
+}
+
+/// Lazy word taking an iterable and one argument, giving a `Seq` even for a list.
+///
+/// Nothing runs until the `Seq` is consumed, by `collect` or a word taking a list.
+fn combinator(name: &str, build: impl Fn(Seq, Value) -> Result<Seq, Error> + 'static) -> Function {
+    Function::native(name, move |ctx| {
+        let arg = ctx.stack().pop()?;
+        let input = ctx.stack().pop()?;
+        let seq = build(to_seq(&input)?, arg)?;
+        ctx.stack().push_native(seq);
+        Ok(())
+    })
+}
+
+fn positive(n: Value) -> Result<usize, Error> {
+    match usize::from_fox(n)? {
+        0 => Err(Error::CastError("a positive size".to_string())),
+        n => Ok(n),
+    }
+}
+
+/// `seq { acc item -> acc } reduce`, starting from the first value
+fn reduce(ctx: &mut CallContext) -> Result<(), Error> {
+    let quote = ctx.stack().pop()?;
+    let mut seq = to_seq(&ctx.stack().pop()?)?;
+    let mut acc = seq.next(ctx)?.ok_or(Error::StackError(
+        "Can't reduce an empty sequence".to_string(),
+    ))?;
+    while let Some(v) = seq.next(ctx)? {
+        acc = call(ctx, &quote, vec![acc, v])?;
+    }
+    ctx.stack().push(acc);
+    Ok(())
+}
+
+/// `seq init { acc item -> acc } fold`
+fn fold(ctx: &mut CallContext) -> Result<(), Error> {
+    let quote = ctx.stack().pop()?;
+    let mut acc = ctx.stack().pop()?;
+    let mut seq = to_seq(&ctx.stack().pop()?)?;
+    while let Some(v) = seq.next(ctx)? {
+        acc = call(ctx, &quote, vec![acc, v])?;
+    }
+    ctx.stack().push(acc);
+    Ok(())
+}
+
+fn arithmetic(ctx: &mut CallContext, init: f64, op: fn(f64, f64) -> f64) -> Result<(), Error> {
+    let mut seq = to_seq(&ctx.stack().pop()?)?;
+    let mut acc = init;
+    while let Some(v) = seq.next(ctx)? {
+        acc = op(acc, v.as_num()?);
+    }
+    ctx.stack().push_num(acc);
+    Ok(())
+}
@@ -0,0 +1,21 @@
+use crate::engine::context::CallContext;
+use crate::engine::convert::ToFox;
+use crate::engine::error::Error;
+use crate::engine::function::Function;
+use crate::engine::typing::{StackEffect, Type};
+
+pub fn natives() -> Vec<Function> {
+    vec![
+        Function::native("String.parse-int", parse_int).with_signature(StackEffect::new(
+            vec![Type::named("String")],
+            vec![Type::applied(vec![Type::named("Int")], "Option")],
+        )),
+    ]
+}
+
+/// `Some` integer, or `None` if the string isn't one
+fn parse_int(ctx: &mut CallContext) -> Result<(), Error> {
+    let s = ctx.pop_string()?;
+    ctx.stack().push(s.trim().parse::<i64>().ok().to_fox());
+    Ok(())
+}
@@ -0,0 +1,107 @@
+use crate::engine::context::CallContext;
+use crate::engine::convert::ToFox;
+use crate::engine::error::Error;
+use crate::engine::function::Function;
+use crate::engine::value::{NativeObject, Value};
+use crate::stdlib::seq::{Items, Seq};
+use crate::stdlib::tuple::Tuple;
+
+/// Associative table, keys are compared by value and kept in insertion order
+#[derive(Debug, Clone, Default)]
+pub struct Table(pub Vec<(Value, Value)>);
+
+impl Table {
+    pub fn get(&self, key: &Value) -> Option<&Value> {
+        self.0.iter().find(|(k, _)| k == key).map(|(_, v)| v)
+    }
+
+    pub fn set(&mut self, key: Value, value: Value) {
+        match self.0.iter_mut().find(|(k, _)| *k == key) {
+            Some((_, v)) => *v = value,
+            None => self.0.push((key, value)),
+        }
+    }
+}
+
+impl NativeObject for Table {
+    fn repr(&self) -> String {
+        let items: Vec<String> = self
+            .0
+            .iter()
+            .map(|(k, v)| format!("{}: {}", k.data_ref().repr(), v.data_ref().repr()))
+            .collect();
+        format!("[{}]", items.join(", "))
+    }
+
+    fn cloned(&self) -> Box<dyn NativeObject> {
+        Box::new(self.clone())
+    }
+
+    fn size(&self) -> usize {
+        1 + self
+            .0
+            .iter()
+            .map(|(k, v)| k.size() + v.size())
+            .sum::<usize>()
+    }
+
+    /// Tables holding the same pairs, in any order
+    fn eq(&self, other: &dyn NativeObject) -> bool {
+        other.as_any().downcast_ref::<Table>().is_some_and(|other| {
+            self.0.len() == other.0.len() && self.0.iter().all(|(k, v)| other.get(k) == Some(v))
+        })
+    }
+
+    /// `(key, value)` tuples
+    fn seq(&self) -> Option<Seq> {
+        let pairs = self
+            .0
+            .iter()
+            .map(|(k, v)| Tuple(vec![k.clone(), v.clone()]).to_fox())
+            .collect();
+        Some(Seq::new(Items::new(pairs)))
+    }
+}
+
+pub fn natives() -> Vec<Function> {
+    vec![
+        Function::native("Table.empty", table_empty),
+        Function::native("Table.set", table_set),
+        Function::native("Table.get", table_get),
+        Function::native("Table.len", table_len),
+    ]
+}
+
+fn table_empty(ctx: &mut CallContext) -> Result<(), Error> {
+    let stack = ctx.stack();
+    stack.push_native(Table::default());
+    Ok(())
+}
+
+fn table_set(ctx: &mut CallContext) -> Result<(), Error> {
+    let stack = ctx.stack();
+    let value = stack.pop()?;
+    let key = stack.pop()?;
+    let mut obj = stack.pop()?;
+    obj.as_native_typed_mut::<Table>()?.set(key, value);
+    stack.push(obj);
+    Ok(())
+}
+
+/// Pushes `Some value` or `None`
+fn table_get(ctx: &mut CallContext) -> Result<(), Error> {
+    let stack = ctx.stack();
+    let key = stack.pop()?;
+    let obj = stack.pop()?;
+    let value = obj.as_native_typed::<Table>()?.get(&key).cloned();
+    stack.push(value.to_fox());
+    Ok(())
+}
+
+fn table_len(ctx: &mut CallContext) -> Result<(), Error> {
+    let stack = ctx.stack();
+    let obj = stack.pop()?;
+    let len = obj.as_native_typed::<Table>()?.0.len();
+    stack.push_num(len as f64);
+    Ok(())
+}
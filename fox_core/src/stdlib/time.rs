@@ -0,0 +1,19 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::engine::context::CallContext;
+use crate::engine::error::Error;
+use crate::engine::function::Function;
+
+pub fn natives() -> Vec<Function> {
+    vec![Function::native("Clock.now", clock_now)]
+}
+
+/// Seconds elapsed since the unix epoch
+fn clock_now(ctx: &mut CallContext) -> Result<(), Error> {
+    let stack = ctx.stack();
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_err(|e| Error::IoError(e.to_string()))?;
+    stack.push_num(now.as_secs_f64());
+    Ok(())
+}
@@ -0,0 +1,56 @@
+use crate::engine::function::Function;
+use crate::engine::value::{NativeObject, Value};
+use crate::stdlib::seq::{Items, Seq};
+
+#[derive(Debug, Clone)]
+pub struct Tuple(pub Vec<Value>);
+
+impl NativeObject for Tuple {
+    fn repr(&self) -> String {
+        let items: Vec<String> = self.0.iter().map(|it| it.data_ref().repr()).collect();
+        format!("({})", items.join(", "))
+    }
+
+    fn cloned(&self) -> Box<dyn NativeObject> {
+        Box::new(self.clone())
+    }
+
+    fn size(&self) -> usize {
+        1 + self.0.iter().map(Value::size).sum::<usize>()
+    }
+
+    fn eq(&self, other: &dyn NativeObject) -> bool {
+        other
+            .as_any()
+            .downcast_ref::<Tuple>()
+            .is_some_and(|other| self.0 == other.0)
+    }
+
+    fn variant(&self) -> Option<(&str, Vec<Value>)> {
+        Some(("Tuple", self.0.clone()))
+    }
+
+    /// Items are named `_0`, `_1`, ...
+    fn field(&self, name: &str) -> Option<Value> {
+        let index: usize = name.strip_prefix('_')?.parse().ok()?;
+        self.0.get(index).cloned()
+    }
+
+    fn seq(&self) -> Option<Seq> {
+        Some(Seq::new(Items::new(self.0.clone())))
+    }
+}
+
+/// `Tuple0` to `Tuple9`, built by the `(a, b)` syntax
+pub fn natives() -> Vec<Function> {
+    (0..=9)
+        .map(|n| {
+            Function::native(format!("Tuple{n}"), move |ctx| {
+                let stack = ctx.stack();
+                let items = stack.pop_n(n)?;
+                stack.push_native(Tuple(items));
+                Ok(())
+            })
+        })
+        .collect()
+}
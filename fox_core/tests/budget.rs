@@ -0,0 +1,133 @@
+use std::time::Duration;
+
+use fox_core::engine::{
+    budget::{Budget, Limit, DEFAULT_CALL_DEPTH},
+    engine::Engine,
+    error::Error,
+    expr::Expr,
+    function::Function,
+};
+
+fn looping_engine(budget: Budget) -> Engine {
+    let mut engine = Engine::builder().budget(budget).build();
+    engine.definitions.set(
+        "forever".to_string(),
+        Function::fox(
+            "forever".to_string(),
+            vec![Expr::num(1.0), Expr::symbol("forever")],
+        ),
+    );
+    engine
+}
+
+#[test]
+fn test_instruction_budget() {
+    let mut engine = looping_engine(Budget::unlimited().instructions(100).call_depth(1000));
+
+    let res = engine.eval(&[Expr::symbol("forever")]);
+    assert!(matches!(
+        res,
+        Err(Error::BudgetExceeded(Limit::Instructions(100)))
+    ));
+    assert!(engine.stack.is_empty());
+}
+
+#[test]
+fn test_call_depth_budget() {
+    let mut engine = looping_engine(Budget::unlimited().call_depth(10));
+
+    let res = engine.eval(&[Expr::symbol("forever")]);
+    assert!(matches!(
+        res,
+        Err(Error::BudgetExceeded(Limit::CallDepth(10)))
+    ));
+}
+
+#[test]
+fn test_default_call_depth() {
+    // Run on a stack the size of a main thread's, test threads get smaller ones
+    let res = std::thread::Builder::new()
+        .stack_size(8 << 20)
+        .spawn(|| looping_engine(Budget::unlimited()).eval(&[Expr::symbol("forever")]))
+        .unwrap()
+        .join()
+        .unwrap();
+    assert!(matches!(
+        res,
+        Err(Error::BudgetExceeded(Limit::CallDepth(DEFAULT_CALL_DEPTH)))
+    ));
+}
+
+#[test]
+fn test_stack_depth_budget() {
+    let mut engine = looping_engine(Budget::unlimited().stack_depth(5).call_depth(1000));
+
+    let res = engine.eval(&[Expr::symbol("forever")]);
+    assert!(matches!(
+        res,
+        Err(Error::BudgetExceeded(Limit::StackDepth(5)))
+    ));
+}
+
+#[test]
+fn test_value_budget() {
+    let mut engine = looping_engine(Budget::unlimited().values(200));
+
+    let res = engine.eval(&vec![Expr::string("ab"); 300]);
+    assert!(matches!(
+        res,
+        Err(Error::BudgetExceeded(Limit::Values(200)))
+    ));
+    assert!(engine.stack.is_empty());
+}
+
+#[test]
+fn test_interrupt_leaves_engine_reusable() {
+    let mut engine = Engine::builder().build();
+    engine.eval(&[Expr::num(7.0)]).unwrap();
+
+    let handle = engine.interrupt_handle();
+    let interrupter = std::thread::spawn(move || {
+        std::thread::sleep(Duration::from_millis(50));
+        handle.interrupt()
+    });
+
+    // Counts forever, dropping every number
+    let res = engine.eval(&[
+        Expr::num(0.0),
+        Expr::symbol("Seq.count-from"),
+        Expr::quote(vec![Expr::symbol("drop")]),
+        Expr::symbol("for-each"),
+    ]);
+    interrupter.join().unwrap();
+    assert!(matches!(res, Err(Error::Interrupted)));
+    assert_eq!(engine.stack.len(), 1);
+
+    engine.eval(&[Expr::num(8.0)]).unwrap();
+    assert_eq!(engine.stack.pop().unwrap().as_num().unwrap(), 8.0);
+    assert_eq!(engine.stack.pop().unwrap().as_num().unwrap(), 7.0);
+}
+
+#[test]
+fn test_interrupt_after_eval_is_ignored() {
+    let mut engine = Engine::new();
+    engine.eval(&[Expr::num(7.0)]).unwrap();
+    engine.interrupt_handle().interrupt();
+
+    engine.eval(&[Expr::num(8.0)]).unwrap();
+    assert_eq!(engine.stack.len(), 2);
+}
+
+#[test]
+fn test_abort_restores_popped_values() {
+    let mut engine = looping_engine(Budget::unlimited().instructions(100).call_depth(1000));
+    engine.eval(&[Expr::num(1.0), Expr::num(2.0)]).unwrap();
+
+    let res = engine.eval(&[
+        Expr::symbol("drop"),
+        Expr::symbol("drop"),
+        Expr::symbol("forever"),
+    ]);
+    assert!(res.is_err());
+    assert_eq!(engine.stack.repr(), "[1 2]");
+}
@@ -0,0 +1,43 @@
+//! Helpers shared by the integration tests, each of them using only some
+#![allow(dead_code)]
+
+use std::path::PathBuf;
+
+use fox_core::engine::{
+    budget::Budget, compile::compile, engine::Engine, error::Error, stack::Stack,
+};
+use fox_core::{parsing, stdlib};
+
+/// Run `source` on `engine`, registering its definitions first
+pub fn eval(engine: &mut Engine, source: &str) -> Result<(), Error> {
+    let (functions, body) = compile(&parsing::parse(source).unwrap());
+    stdlib::register(&mut engine.definitions, functions);
+    engine.eval(&body)
+}
+
+/// Run `source` on a new engine without capabilities, returning its stack
+pub fn run(source: &str) -> Result<Stack, Error> {
+    run_with_budget(source, Budget::unlimited())
+}
+
+pub fn run_with_budget(source: &str, budget: Budget) -> Result<Stack, Error> {
+    let mut engine = Engine::builder().budget(budget).build();
+    eval(&mut engine, source).map(|_| engine.stack)
+}
+
+/// Directory removed with its files when dropped
+pub struct TempDir(pub PathBuf);
+
+impl TempDir {
+    pub fn new(name: &str) -> Self {
+        let path = std::env::temp_dir().join(format!("{name}_{}", std::process::id()));
+        std::fs::create_dir_all(&path).unwrap();
+        TempDir(path)
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
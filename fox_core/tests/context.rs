@@ -0,0 +1,75 @@
+use std::cell::Cell;
+use std::rc::Rc;
+
+mod common;
+
+use common::{run, run_with_budget};
+use fox_core::engine::{
+    budget::Budget, engine::Engine, error::Error, expr::Expr, function::Function,
+};
+
+struct Counter(u32);
+
+#[test]
+fn test_stateful_natives() {
+    let mut engine = Engine::new();
+    engine.set_user_data(Counter(0));
+
+    let calls = Rc::new(Cell::new(0));
+    let c = calls.clone();
+    engine.definitions.set(
+        "tick".to_string(),
+        Function::native("tick", move |ctx| {
+            c.set(c.get() + 1);
+            let counter = ctx.user_data_mut::<Counter>().unwrap();
+            counter.0 += 10;
+            let total = counter.0;
+            ctx.stack().push_num(total as f64);
+            Ok(())
+        }),
+    );
+    engine.definitions.set(
+        "local?".to_string(),
+        Function::native("local?", |ctx| {
+            let found = ctx.locals().has("x");
+            ctx.stack().push_bool(found);
+            Ok(())
+        }),
+    );
+
+    engine
+        .eval(&[
+            Expr::symbol("tick"),
+            Expr::symbol("tick"),
+            Expr::local(&["x"]),
+            Expr::symbol("local?"),
+        ])
+        .unwrap();
+
+    assert_eq!(calls.get(), 2);
+    assert_eq!(engine.user_data::<Counter>().unwrap().0, 20);
+    assert!(engine.stack.pop().unwrap().as_bool().unwrap());
+    assert_eq!(engine.stack.pop().unwrap().as_num().unwrap(), 10.0);
+}
+
+#[test]
+fn test_callbacks() {
+    let source = "def double = 2 *\n\
+                  [1, 2, 3] \\double map { 2 > } filter \\+ reduce";
+    let mut stack = run(source).unwrap();
+    assert_eq!(stack.pop_as::<f64>().unwrap(), 10.0);
+
+    let source = "0 1 > then { 1 } else { 2 } 5 { 1 + } apply";
+    let mut stack = run(source).unwrap();
+    assert_eq!(stack.pop_as::<f64>().unwrap(), 6.0);
+    assert_eq!(stack.pop_as::<f64>().unwrap(), 2.0);
+
+    let res = run("[1, 2] { missing } map collect");
+    assert!(matches!(res, Err(Error::UnknownSymbol(s)) if s == "missing"));
+
+    let res = run_with_budget(
+        "[1, 2, 3, 4] { 1 + 1 + 1 + } map collect",
+        Budget::unlimited().instructions(10),
+    );
+    assert!(matches!(res, Err(Error::BudgetExceeded(_))));
+}
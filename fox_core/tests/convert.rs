@@ -0,0 +1,113 @@
+use std::collections::HashMap;
+
+mod common;
+
+use common::run;
+use fox_core::engine::{
+    engine::Engine,
+    error::Error,
+    value::{NativeObject, Value, ValueData},
+};
+
+#[test]
+fn test_round_trip() {
+    let mut engine = Engine::new();
+
+    engine.stack.push_many([1u8, 2, 3]);
+    assert_eq!(engine.stack.pop_n(3).unwrap().len(), 3);
+
+    engine
+        .stack
+        .push_many(vec![Some(vec!["a".to_string(), "b".to_string()]), None]);
+    assert_eq!(engine.stack.pop_as::<Option<Vec<String>>>().unwrap(), None);
+    assert_eq!(
+        engine.stack.pop_as::<Option<Vec<String>>>().unwrap(),
+        Some(vec!["a".to_string(), "b".to_string()])
+    );
+
+    let table = HashMap::from([("one".to_string(), 1i32), ("two".to_string(), 2)]);
+    engine.stack.push_many([table.clone()]);
+    assert_eq!(
+        engine.stack.pop_as::<HashMap<String, i32>>().unwrap(),
+        table
+    );
+
+    engine.stack.push_many([(1.5, 'x', true)]);
+    assert_eq!(
+        engine.stack.pop_as::<(f64, char, bool)>().unwrap(),
+        (1.5, 'x', true)
+    );
+
+    engine
+        .stack
+        .push_many([Ok::<i64, String>(3), Err("oops".to_string())]);
+    assert_eq!(
+        engine.stack.pop_as::<Result<i64, String>>().unwrap(),
+        Err("oops".to_string())
+    );
+    assert_eq!(engine.stack.pop_as::<Result<i64, String>>().unwrap(), Ok(3));
+}
+
+#[test]
+fn test_invalid_conversions() {
+    let mut engine = Engine::new();
+
+    engine.stack.push_many([300.0]);
+    assert!(matches!(
+        engine.stack.pop_as::<u8>(),
+        Err(Error::CastError(_))
+    ));
+
+    engine.stack.push_many([1.5]);
+    assert!(engine.stack.pop_as::<i64>().is_err());
+
+    engine.stack.push_many([2f64.powi(64), 2f64.powi(63)]);
+    assert!(engine.stack.pop_as::<i64>().is_err());
+    assert!(engine.stack.pop_as::<u64>().is_err());
+
+    engine
+        .stack
+        .push_many([255.0, -(2f64.powi(63)), 2f64.powi(63)]);
+    assert_eq!(engine.stack.pop_as::<u64>().unwrap(), 1 << 63);
+    assert_eq!(engine.stack.pop_as::<i64>().unwrap(), i64::MIN);
+    assert_eq!(engine.stack.pop_as::<u8>().unwrap(), 255);
+
+    engine.stack.push_many([(1, 2)]);
+    assert!(engine.stack.pop_as::<(i64, i64, i64)>().is_err());
+}
+
+#[test]
+fn test_literals() {
+    let mut stack = run("(1, 'a') [\"k\": 2] \"k\" Table.get").unwrap();
+
+    assert_eq!(stack.pop_as::<Option<i64>>().unwrap(), Some(2));
+    assert_eq!(stack.pop_as::<(i64, char)>().unwrap(), (1, 'a'));
+}
+
+#[test]
+fn test_natives_equality() {
+    #[derive(Debug, Clone)]
+    struct Handle;
+
+    impl NativeObject for Handle {
+        fn repr(&self) -> String {
+            "Handle".to_string()
+        }
+
+        fn cloned(&self) -> Box<dyn NativeObject> {
+            Box::new(self.clone())
+        }
+    }
+
+    let native = |obj: Box<dyn NativeObject>| Value::from(ValueData::Native(obj));
+    assert_ne!(native(Box::new(Handle)), native(Box::new(Handle)));
+
+    let stack =
+        run("[1, 2] [1, 2] ('a', \"b\") ('a', \"b\") [\"k\": 1, \"l\": 2] [\"l\": 2, \"k\": 1]")
+            .unwrap();
+    let values = stack.values();
+    assert_eq!(values[0], values[1]);
+    assert_eq!(values[2], values[3]);
+    assert_eq!(values[4], values[5]);
+    assert_ne!(values[0], values[2]);
+}
@@ -0,0 +1,111 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use fox_core::engine::debug::{Breakpoint, Command, Debugger, Frontend, Pause, PauseReason};
+mod common;
+
+use common::eval;
+use fox_core::engine::{engine::Engine, error::Error};
+
+type Pauses = Vec<(String, Option<usize>, PauseReason)>;
+
+/// Answers with the scripted commands, recording where it stopped
+struct Scripted {
+    commands: Vec<Command>,
+    pauses: Rc<RefCell<Pauses>>,
+}
+
+impl Frontend for Scripted {
+    fn paused(&mut self, pause: &Pause, _: &mut Vec<Breakpoint>) -> Command {
+        let expr = pause.trace.expr.repr();
+        self.pauses
+            .borrow_mut()
+            .push((expr, pause.line, pause.reason.clone()));
+        self.commands.pop().unwrap_or(Command::Continue)
+    }
+}
+
+fn debug(source: &str, commands: Vec<Command>, breakpoints: Vec<Breakpoint>, stop: bool) -> Pauses {
+    let pauses = Rc::new(RefCell::new(vec![]));
+    let frontend = Scripted {
+        commands: commands.into_iter().rev().collect(),
+        pauses: pauses.clone(),
+    };
+    let mut debugger = Debugger::new(source, frontend);
+    for b in breakpoints {
+        debugger = debugger.break_at(b);
+    }
+    if !stop {
+        debugger = debugger.run_to_breakpoint();
+    }
+
+    let mut engine = Engine::builder().build();
+    engine.set_trace(debugger.into_hook());
+    eval(&mut engine, source).unwrap();
+
+    let pauses = pauses.borrow().clone();
+    pauses
+}
+
+const SOURCE: &str = "def square = dup *\n\
+                      2 square\n\
+                      3 square";
+
+#[test]
+fn test_stepping() {
+    let pauses = debug(
+        SOURCE,
+        vec![
+            Command::StepOver,
+            Command::StepIn,
+            Command::StepIn,
+            Command::StepOut,
+        ],
+        vec![],
+        true,
+    );
+    let exprs: Vec<&str> = pauses.iter().map(|(e, _, _)| e.as_str()).collect();
+    assert_eq!(exprs, vec!["2", "square", "dup", "*", "3"]);
+}
+
+#[test]
+fn test_breakpoints() {
+    let pauses = debug(
+        SOURCE,
+        vec![],
+        vec![Breakpoint::Line(3), Breakpoint::Word("*".to_string())],
+        false,
+    );
+    let stops: Vec<(&str, Option<usize>)> =
+        pauses.iter().map(|(e, l, _)| (e.as_str(), *l)).collect();
+    assert_eq!(
+        stops,
+        vec![("*", Some(1)), ("square", Some(3)), ("*", Some(1))]
+    );
+}
+
+#[test]
+fn test_line_breakpoint_in_loop() {
+    let source = "def count = dup 0 > then { 1 - count } else { }\n\
+                  3 count";
+    let pauses = debug(source, vec![], vec![Breakpoint::Line(1)], false);
+    let stops: Vec<(&str, Option<usize>)> =
+        pauses.iter().map(|(e, l, _)| (e.as_str(), *l)).collect();
+    assert_eq!(stops, vec![("dup", Some(1)); 4]);
+}
+
+#[test]
+fn test_quit() {
+    let pauses = Rc::new(RefCell::new(vec![]));
+    let frontend = Scripted {
+        commands: vec![Command::Quit, Command::StepIn],
+        pauses: pauses.clone(),
+    };
+    let mut engine = Engine::builder().build();
+    let debugger = Debugger::new(SOURCE, frontend).interrupt(engine.interrupt_handle());
+    engine.set_trace(debugger.into_hook());
+
+    let res = eval(&mut engine, SOURCE);
+    assert!(matches!(res, Err(Error::Interrupted)));
+    assert_eq!(pauses.borrow().len(), 2);
+}
@@ -0,0 +1,35 @@
+use fox_core::engine::doctest::{self, Failure};
+use fox_core::engine::engine::Engine;
+
+fn run(code: &str) -> Result<(), Failure> {
+    doctest::run(&mut Engine::builder().build(), code)
+}
+
+#[test]
+fn test_doctest() {
+    assert_eq!(
+        run("def square = dup *\n\n4 square\n# 16\n[1, 2] { 3 + } map\n# [4, 5]\n\"a\" 'b'\n# \"a\" 'b'"),
+        Ok(())
+    );
+    assert_eq!(
+        run("1 2\n# stack: [16 1 2]\n# Not checked\nswap"),
+        Err(Failure {
+            line: 1,
+            message: "expected the stack [16 1 2], found [1 2]".to_string(),
+        })
+    );
+    assert_eq!(
+        run("1 2\n# stack: [1 2]\nswap\n# 2"),
+        Err(Failure {
+            line: 3,
+            message: "expected 2, found 1".to_string(),
+        })
+    );
+    assert_eq!(
+        run("1\n\nnope"),
+        Err(Failure {
+            line: 2,
+            message: "The symbol `nope` is not defined".to_string(),
+        })
+    );
+}
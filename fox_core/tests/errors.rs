@@ -0,0 +1,76 @@
+mod common;
+
+use common::{eval, run};
+use fox_core::engine::{engine::Engine, error::Error};
+
+#[test]
+fn test_result() {
+    let source = "def half = -> n n 2 % 0 == then { n 2 / Ok } else { \"odd\" Err }\n\
+                  def quarter = half try? half try? 1 +\n\
+                  8 quarter 6 quarter";
+    let mut stack = run(source).unwrap();
+
+    assert_eq!(
+        stack.pop_as::<Result<f64, String>>().unwrap(),
+        Err("odd".to_string())
+    );
+    assert_eq!(stack.pop_as::<f64>().unwrap(), 3.0);
+}
+
+#[test]
+fn test_try_catch() {
+    let mut stack = run("1 try { 2 missing } catch { -> e 3 }").unwrap();
+    assert_eq!(stack.pop_as::<f64>().unwrap(), 3.0);
+    assert_eq!(stack.pop_as::<f64>().unwrap(), 1.0);
+    assert!(stack.is_empty());
+
+    let mut stack = run("try { \"boom\" panic } catch { }").unwrap();
+    assert_eq!(stack.pop_string().unwrap(), "boom");
+
+    let mut stack = run("try { \"bad\" Err try? } catch { }").unwrap();
+    assert_eq!(stack.pop_string().unwrap(), "bad");
+}
+
+#[test]
+fn test_panic() {
+    let user_error = |source: &str| match run(source) {
+        Err(Error::User(message, span)) => (message, span),
+        res => panic!("expected a user error, got {res:?}"),
+    };
+
+    assert_eq!(
+        user_error("def check = 0 1 > \"positive\" assert\n\ncheck"),
+        ("Assertion failed: positive".to_string(), Some(29..35))
+    );
+    assert_eq!(
+        user_error("\"oops\" panic"),
+        ("oops".to_string(), Some(7..12))
+    );
+}
+
+#[test]
+fn test_position_after_call() {
+    let position = |source: &str| {
+        let mut engine = Engine::builder().build();
+        assert!(eval(&mut engine, source).is_err());
+        engine.position()
+    };
+
+    assert_eq!(position("def f = 1 dup\nf .name"), Some(14..15));
+    assert_eq!(
+        position("def f = 1 dup\n{ f .name } generator collect"),
+        Some(16..17)
+    );
+}
+
+#[test]
+fn test_errors_are_send() {
+    fn send_sync<T: Send + Sync>() {}
+    send_sync::<Error>();
+
+    let mut stack = run("def f = \"bad\" Err try? 1\n{ f } apply").unwrap();
+    let result = stack.pop_as::<Result<f64, String>>().unwrap();
+    assert_eq!(result, Err("bad".to_string()));
+    let result = run("1 Err try?");
+    assert!(matches!(result, Err(Error::Propagated(e)) if e == "Err(1)"));
+}
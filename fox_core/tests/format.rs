@@ -0,0 +1,68 @@
+use fox_core::parsing::format::format;
+
+#[test]
+fn test_format() {
+    let source = "
+def   square (Int -> Int)=   dup    *
+# comment at column 0
+def fact =
+  match {
+   | 0: 1
+   | n:
+     n dup 1 -
+        fact *   # recursion
+  }
+def sum =
+    0 swap      # total
+    { + } fold  # add each one
+
+
+
+type Option o = None | Some o
+def pick = positive? then {1}
+  else {  2 }
+[ 1 ,2,
+3 ] List.push
+[\"a\" : 1,
+  \"b\": 2] x .name";
+    let expected = "def square (Int -> Int) = dup *
+# comment at column 0
+def fact =
+    match {
+        | 0: 1
+        | n:
+            n dup 1 -
+            fact * # recursion
+    }
+def sum =
+    0 swap      # total
+    { + } fold  # add each one
+
+type Option o =
+    | None
+    | Some o
+def pick = positive? then { 1 } else { 2 }
+[
+    1,
+    2,
+    3
+] List.push
+[
+    \"a\": 1,
+    \"b\": 2
+] x .name
+";
+    let formatted = format(source).unwrap();
+    assert_eq!(formatted, expected);
+    assert_eq!(format(&formatted).unwrap(), formatted);
+}
+
+#[test]
+fn test_format_examples() {
+    for entry in std::fs::read_dir("../examples").unwrap() {
+        let path = entry.unwrap().path();
+        let source = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(format(&source).unwrap(), source, "{}", path.display());
+    }
+    assert!(format("def f = (1").is_err());
+}
@@ -0,0 +1,67 @@
+mod common;
+
+use common::{eval, run, run_with_budget};
+use fox_core::engine::budget::Budget;
+use fox_core::engine::error::Error;
+use fox_core::engine::{engine::Engine, function::Function};
+
+#[test]
+fn test_generator() {
+    let source = "def naturals = -> n n yield n 1 + naturals\n\
+                  def evens = -> n n 2 % 0 == then { n yield } else { } n 1 + evens\n\
+                  { 1 yield 2 yield 3 yield } generator collect \
+                  { 0 evens } generator 3 take collect \
+                  { 0 naturals } generator 1 skip 4 take sum";
+    let mut stack = run(source).unwrap();
+
+    assert_eq!(stack.pop_as::<i64>().unwrap(), 10);
+    assert_eq!(stack.pop_as::<Vec<i64>>().unwrap(), vec![0, 2, 4]);
+    assert_eq!(stack.pop_as::<Vec<i64>>().unwrap(), vec![1, 2, 3]);
+}
+
+#[test]
+fn test_generator_yields_in_for_each_and_try() {
+    let source = "{ [1, 2, 3] { 10 * yield } for-each 4 yield } generator collect \
+                  { try { 1 yield \"boom\" panic } catch { drop 2 yield } 3 yield } generator collect \
+                  { 7 try { 8 1 Err try? } catch { yield yield } } generator collect";
+    let mut stack = run(source).unwrap();
+
+    assert_eq!(stack.pop_as::<Vec<i64>>().unwrap(), vec![1, 7]);
+    assert_eq!(stack.pop_as::<Vec<i64>>().unwrap(), vec![1, 2, 3]);
+    assert_eq!(stack.pop_as::<Vec<i64>>().unwrap(), vec![10, 20, 30, 4]);
+}
+
+#[test]
+fn test_generator_limits() {
+    let res = run("1 yield");
+    assert!(matches!(res, Err(Error::YieldOutsideGenerator)));
+
+    // Recursion in tail position doesn't grow the frames of the generator
+    let source = "def naturals = -> n n yield n 1 + naturals\n\
+                  { 0 naturals } generator 100 take count";
+    let mut stack = run_with_budget(source, Budget::unlimited().call_depth(5)).unwrap();
+    assert_eq!(stack.pop_as::<i64>().unwrap(), 100);
+
+    let res = run_with_budget(
+        "def naturals = -> n n yield n 1 + naturals\n\
+         { 0 naturals } generator count",
+        Budget::unlimited().instructions(1000),
+    );
+    assert!(matches!(res, Err(Error::BudgetExceeded(_))));
+}
+
+#[test]
+fn test_generator_runs_only_builtin_intrinsics() {
+    let mut engine = Engine::builder().build();
+    // A word only named like a builtin runs as any other native
+    engine.definitions.set(
+        "apply".to_string(),
+        Function::native("apply", |ctx| {
+            ctx.stack().pop()?;
+            ctx.stack().push_num(42.0);
+            Ok(())
+        }),
+    );
+    eval(&mut engine, "{ { 7 } apply yield } generator collect").unwrap();
+    assert_eq!(engine.stack.pop_as::<Vec<i64>>().unwrap(), vec![42]);
+}
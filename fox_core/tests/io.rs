@@ -0,0 +1,60 @@
+use std::cell::RefCell;
+use std::io::Write;
+use std::rc::Rc;
+
+mod common;
+
+use common::{eval, TempDir};
+use fox_core::engine::engine::Engine;
+
+/// Output shared with the engine, to read what the script printed
+#[derive(Clone, Default)]
+struct Captured(Rc<RefCell<Vec<u8>>>);
+
+impl Write for Captured {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.borrow_mut().write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn test_captured_output() {
+    let output = Captured::default();
+    let mut engine = Engine::builder().console().build();
+    engine.set_output(output.clone());
+
+    eval(&mut engine, "\"Hello\" print 42 println").unwrap();
+    eval(
+        &mut engine,
+        "[1, 2, 3] { 1 + } map println \"abc\" 1 skip println",
+    )
+    .unwrap();
+
+    let printed = String::from_utf8(output.0.take()).unwrap();
+    assert_eq!(printed, "Hello42\n[2, 3, 4]\nbc\n");
+}
+
+#[test]
+fn test_files() {
+    let root = TempDir::new("fox_io_test");
+    let mut engine = Engine::builder().filesystem(&root.0).build();
+
+    let source = "\"lines.txt\" \"a\n\" write-file drop \
+                  \"lines.txt\" \"b\n\" append-file drop \
+                  \"lines.txt\" read-lines match | Ok { collect } | Err { } \
+                  \"lines.txt\" file-exists? \
+                  \"missing.txt\" read-file \
+                  \"dir\" \"lines.txt\" path-join";
+    eval(&mut engine, source).unwrap();
+
+    let stack = &mut engine.stack;
+    assert_eq!(stack.pop_string().unwrap(), "dir/lines.txt");
+    assert!(stack.pop_as::<Result<String, String>>().unwrap().is_err());
+    assert!(stack.pop_as::<bool>().unwrap());
+    let lines = stack.pop_as::<Vec<String>>().unwrap();
+    assert_eq!(lines, vec!["a", "b"]);
+}
@@ -0,0 +1,114 @@
+//! The code blocks of `language.md` and `README.md`, checked against the values written in
+//! their comments
+
+use fox_core::engine::doctest;
+use fox_core::engine::engine::Engine;
+
+/// Blocks of `language.md` the implementation does not follow, by their heading and the line
+/// introducing them. Each is asserted to fail so that this list shrinks as they are fixed.
+const KNOWN_FAILURES: [(&str, &str); 4] = [
+    // There is no `ref` word
+    ("Ref", "to this one with `Ref`"),
+    // Type variables follow the type name: `type Option t`
+    ("Examples", "Enum/Sum types"),
+    // Records are built by a constructor, with no `{x:0, y:1}` literal nor `:x` update
+    ("Examples", "Struct/Product types"),
+    // Wrappers and tuples are variants: `type Age = Age Int`
+    ("Examples", "Wrapper/Tuple types"),
+];
+
+struct Block {
+    /// Line of the opening fence, starting at 1
+    line: usize,
+    /// Last heading above the block, without its `#`
+    heading: String,
+    /// Last line of text above the block
+    caption: String,
+    /// Code without its indentation, `None` if it is not Fox
+    code: Option<String>,
+}
+
+/// Fenced code blocks, `` ```fox `` or without a language
+fn blocks(markdown: &str) -> Vec<Block> {
+    let mut blocks = vec![];
+    let mut heading = String::new();
+    let mut caption = String::new();
+    // The current block, with its indentation
+    let mut current: Option<(Block, usize)> = None;
+    for (i, line) in markdown.lines().enumerate() {
+        let trimmed = line.trim_start();
+        // A code span such as ```fox run```, on a single line
+        let fence = trimmed
+            .strip_prefix("```")
+            .filter(|info| !info.contains("```"));
+        match (current.take(), fence) {
+            (None, Some(info)) => {
+                let block = Block {
+                    line: i + 1,
+                    heading: heading.clone(),
+                    caption: caption.clone(),
+                    code: (info.trim().is_empty() || info.trim() == "fox").then(String::new),
+                };
+                current = Some((block, line.len() - trimmed.len()));
+            }
+            (None, None) => {
+                if let Some(title) = trimmed.strip_prefix('#') {
+                    heading = title.trim_start_matches('#').trim().to_string();
+                } else if !trimmed.is_empty() {
+                    caption = trimmed.to_string();
+                }
+            }
+            (Some((block, _)), Some(_)) => blocks.push(block),
+            (Some((mut block, indent)), None) => {
+                if let Some(code) = &mut block.code {
+                    let dedented = line
+                        .get(indent..)
+                        .filter(|_| line[..indent].trim().is_empty());
+                    code.push_str(dedented.unwrap_or(trimmed));
+                    code.push('\n');
+                }
+                current = Some((block, indent));
+            }
+        }
+    }
+    blocks
+}
+
+/// Locations and messages of the blocks failing, or of the known failures passing
+fn check(file: &str, markdown: &str, known: &[(&str, &str)]) -> Vec<String> {
+    let mut failures = vec![];
+    for block in blocks(markdown) {
+        let Some(code) = &block.code else {
+            continue;
+        };
+        let mut engine = Engine::builder().console().build();
+        engine.set_output(std::io::sink());
+        engine.set_input(&b"Fox\n"[..]);
+        let result = doctest::run(&mut engine, code);
+
+        let known = known.contains(&(block.heading.as_str(), block.caption.as_str()));
+        match result {
+            Err(failure) if !known => {
+                let line = block.line + 1 + failure.line;
+                failures.push(format!("{file}:{line}: {}", failure.message));
+            }
+            Ok(()) if known => failures.push(format!("{file}:{}: passes now", block.line)),
+            _ => {}
+        }
+    }
+    failures
+}
+
+#[test]
+fn test_language_examples() {
+    let markdown = include_str!("../../language.md");
+    let failures = check("language.md", markdown, &KNOWN_FAILURES);
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
+
+#[test]
+fn test_readme_examples() {
+    let markdown = include_str!("../../README.md");
+    let failures = check("README.md", markdown, &[]);
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
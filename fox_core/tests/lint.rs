@@ -0,0 +1,54 @@
+use fox_core::engine::engine::Engine;
+use fox_core::engine::lint::{self, Lint, Rule};
+use fox_core::parsing;
+
+fn lints(source: &str) -> Vec<Lint> {
+    let ast = parsing::parse(source).unwrap();
+    let natives = lint::natives(&Engine::builder().build().definitions);
+    lint::lint(source, &ast, &natives)
+}
+
+#[test]
+fn test_lint() {
+    let source = "
+def f (Int -> Int) = dup swap swap drop 1 +
+def g = -> x, y y 2 *
+def g = 3
+def dup = 1
+[1, 2] { -> a 1 } map
+-> b { b } apply
+def k (Int, Int -> Int) = + +
+";
+    let found: Vec<(Rule, &str)> = lints(source)
+        .iter()
+        .map(|lint| (lint.rule, &source[lint.span.clone()]))
+        .collect();
+    assert_eq!(
+        found,
+        vec![
+            (Rule::SwapSwap, "swap swap"),
+            (Rule::UnusedLocal, "-> x, y"),
+            (Rule::ShadowedDefinition, "g"),
+            (Rule::ShadowedDefinition, "dup"),
+            (Rule::UnusedLocal, "-> a"),
+            (Rule::EffectMismatch, "k"),
+        ]
+    );
+}
+
+#[test]
+fn test_allow_and_fix() {
+    let source = "
+# allow(unused-local, dup-drop)
+def h = -> z dup drop
+def f = dup swap swap drop 1
+";
+    let found = lints(source);
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].rule, Rule::SwapSwap);
+
+    let fixed = lint::fix(source, &found);
+    assert!(fixed.ends_with("def f = dup drop 1\n"));
+    assert!(lints(&fixed).iter().any(|lint| lint.rule == Rule::DupDrop));
+    assert_eq!("unused-local".parse(), Ok(Rule::UnusedLocal));
+}
@@ -0,0 +1,23 @@
+mod common;
+
+use common::run;
+
+#[test]
+fn test_option() {
+    let mut stack = run("[1, 2, 3] 1 List.get { 10 * } Option.map \
+                         [1] 5 List.get 0 Option.unwrap-or \
+                         \"42\" String.parse-int { 40 > } Option.filter Option.is-some \
+                         \"x\" String.parse-int { Some } Option.and-then")
+    .unwrap();
+
+    assert_eq!(stack.pop_as::<Option<i64>>().unwrap(), None);
+    assert!(stack.pop_as::<bool>().unwrap());
+    assert_eq!(stack.pop_as::<i64>().unwrap(), 0);
+    assert_eq!(stack.pop_as::<Option<i64>>().unwrap(), Some(20));
+}
+
+#[test]
+fn test_option_match() {
+    let mut stack = run("2 Some match | None { 0 } | Some { 1 + }").unwrap();
+    assert_eq!(stack.pop_as::<i64>().unwrap(), 3);
+}
@@ -1,13 +1,14 @@
 use chumsky::{prelude::*, Parser};
-use eq_float::F64;
-use fox::{
-    self, parsing,
+use fox_core::{
+    self,
+    engine::{compile::compile, engine::Engine, expr::Expr},
+    parsing,
     parsing::{
         lexer,
         lexer::{Literal, Token},
         parser,
-        parser::FunctionDefinition,
     },
+    stdlib,
 };
 use pretty_assertions::assert_eq;
 
@@ -53,6 +54,7 @@ fn test_parse_char() {
 }
 
 #[test]
+#[allow(clippy::approx_constant)]
 fn test_parse_float() {
     let parse = test_parser(lexer::float());
 
@@ -79,6 +81,68 @@ fn test_parse_literal() {
     );
 }
 
+#[test]
+fn test_lex_names() {
+    let lex = |s: &str| {
+        lexer::lex(s)
+            .unwrap()
+            .into_iter()
+            .map(|(t, _)| t)
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(lex("=="), vec![Token::LowerName("==".to_string())]);
+    assert_eq!(lex("define"), vec![Token::LowerName("define".to_string())]);
+    assert_eq!(
+        lex("def x = 1 # comment\n  # indented comment\n"),
+        vec![
+            Token::Def,
+            Token::LowerName("x".to_string()),
+            Token::Eq,
+            Token::Literal(Literal::Int(1)),
+        ]
+    );
+}
+
+#[test]
+fn test_parse_top_level() {
+    let ast = parsing::parse("def square = dup *\n\n4 square\ndef inc = 1 +").unwrap();
+
+    assert_eq!(ast.defs.len(), 2);
+    assert_eq!(
+        ast.body,
+        vec![
+            parser::Expr::Literal(Literal::Int(4)),
+            parser::Expr::Term {
+                name: "square".to_string(),
+                module: vec![],
+                span: 22..28,
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_type_and_match() {
+    let source = "type Opt = | None | Some a\n\
+                  def or = -> d match | Some: | None: d\n\
+                  def first = .x\n\
+                  5 Some 0 or None 7 or";
+    let ast = parsing::parse(source).unwrap();
+    let (functions, body) = compile(&ast);
+
+    let mut engine = Engine::new();
+    stdlib::register(&mut engine.definitions, functions);
+    engine.eval(&body).unwrap();
+
+    assert_eq!(engine.stack.pop().unwrap().as_num().unwrap(), 7.0);
+    assert_eq!(engine.stack.pop().unwrap().as_num().unwrap(), 5.0);
+
+    engine.stack.push_num(1.0);
+    let res = engine.eval(&[Expr::symbol("first")]);
+    assert!(res.is_err());
+}
+
 // #[test]
 // fn test_parse_term() {
 //     let parse = test_parser(lexer::term());
@@ -116,3 +180,95 @@ fn test_parse_literal() {
 //         })
 //     );
 // }
+
+fn def_names(ast: &parser::Ast) -> Vec<&str> {
+    ast.defs
+        .iter()
+        .map(|def| match def {
+            parser::Definition::Function(f) => f.name.as_str(),
+            parser::Definition::Type(t) => t.name.as_str(),
+        })
+        .collect()
+}
+
+#[test]
+fn test_recover_definitions() {
+    let source = "def ok = 1 +\n\
+                  def broken (Int -> = 2\n\
+                  type Shape = Circle Float\n\
+                  def list = [1, 2 : ] drop\n\
+                  def unclosed = { 1 2\n\
+                  def last = 5\n\
+                  5 last";
+    let (ast, errors) = parsing::parse_partial(source);
+
+    assert_eq!(
+        def_names(&ast),
+        vec!["ok", "Shape", "list", "unclosed", "last"]
+    );
+    assert_eq!(ast.body.len(), 2);
+
+    let spans: Vec<_> = errors.iter().map(|e| e.span.clone()).collect();
+    assert_eq!(spans, vec![32..33, 79..80, 103..104]);
+    assert_eq!(errors[2].message, "Unclosed delimiter `{`");
+    assert_eq!(parsing::parse(source).unwrap_err(), errors);
+}
+
+#[test]
+fn test_recover_delimiters() {
+    let (ast, errors) = parsing::parse_partial("1 ) 2\n3\n( 4 ]\n{ 5 : } 6");
+
+    assert_eq!(errors.len(), 3);
+    assert!(errors[0].message.starts_with("Unexpected `)`"));
+    assert!(errors[1].message.starts_with("Unexpected `]`"));
+    // The broken quote is kept empty
+    assert_eq!(
+        ast.body,
+        vec![
+            parser::Expr::Literal(Literal::Int(1)),
+            parser::Expr::Literal(Literal::Int(3)),
+            parser::Expr::Quote(vec![]),
+            parser::Expr::Literal(Literal::Int(6)),
+        ]
+    );
+}
+
+#[test]
+fn test_recover_lexer_errors() {
+    let (ast, errors) = parsing::parse_partial("1 @ 2\n\"abc");
+
+    assert_eq!(errors.len(), 2);
+    assert!(errors[0].message.starts_with("Unexpected `@`"));
+    assert_eq!(ast.body.len(), 3);
+}
+
+#[test]
+fn test_parse_tests() {
+    let ast = parsing::parse("def f = 1\ntest \"f is one\" {\n    f 1 assertEq\n}\nf").unwrap();
+
+    assert_eq!(ast.defs.len(), 1);
+    assert_eq!(ast.tests.len(), 1);
+    assert_eq!(ast.tests[0].name, "f is one");
+    assert_eq!(ast.tests[0].span, 15..25);
+    assert_eq!(ast.tests[0].body.len(), 3);
+    assert_eq!(ast.body.len(), 1);
+}
+
+#[test]
+fn test_doc_comments() {
+    let source = "## Twice a number\n##\n##     2 double\ndef double = 2 *\n\n## Unused\n\n# Not a doc\ndef triple = 3 *\n## A pair\ntype Pair a = Pair a a";
+    let ast = parsing::parse(source).unwrap();
+
+    let docs: Vec<Option<&str>> = ast
+        .defs
+        .iter()
+        .map(|def| match def {
+            parser::Definition::Function(f) => f.doc.as_deref(),
+            parser::Definition::Type(t) => t.doc.as_deref(),
+        })
+        .collect();
+    assert_eq!(
+        docs,
+        vec![Some("Twice a number\n\n    2 double"), None, Some("A pair")]
+    );
+}
@@ -0,0 +1,45 @@
+mod common;
+
+use common::TempDir;
+use fox_core::engine::{engine::Engine, error::Error, expr::Expr};
+
+#[test]
+fn test_pure_core() {
+    let mut engine = Engine::builder().build();
+
+    engine
+        .eval(&[Expr::num(1.0), Expr::num(2.0), Expr::symbol("+")])
+        .unwrap();
+    assert_eq!(engine.stack.pop().unwrap().as_num().unwrap(), 3.0);
+
+    let res = engine.eval(&[Expr::symbol("Clock.now")]);
+    assert!(matches!(res, Err(Error::PermissionDenied(word, _)) if word == "Clock.now"));
+}
+
+#[test]
+fn test_granted_capability() {
+    let mut engine = Engine::builder().clock().build();
+
+    engine.eval(&[Expr::symbol("Clock.now")]).unwrap();
+    assert!(engine.stack.pop().unwrap().as_num().unwrap() > 0.0);
+}
+
+#[test]
+fn test_filesystem_root() {
+    let root = TempDir::new("fox_sandbox_test");
+    let mut engine = Engine::builder().filesystem(&root.0).build();
+
+    engine.stack.push_string("hello.txt");
+    engine.stack.push_string("hello");
+    engine.eval(&[Expr::symbol("write-file")]).unwrap();
+    engine.stack.pop().unwrap();
+
+    engine.stack.push_string("./hello.txt");
+    engine.eval(&[Expr::symbol("read-file")]).unwrap();
+    let content = engine.stack.pop_as::<Result<String, String>>().unwrap();
+    assert_eq!(content.unwrap(), "hello");
+
+    engine.stack.push_string("../escape.txt");
+    let res = engine.eval(&[Expr::symbol("read-file")]);
+    assert!(matches!(res, Err(Error::PermissionDenied(word, _)) if word == "read-file"));
+}
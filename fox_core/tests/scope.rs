@@ -0,0 +1,27 @@
+mod common;
+
+use common::run;
+use fox_core::engine::error::Error;
+
+#[test]
+fn test_quotes_see_their_locals() {
+    let mut stack = run("def run = -> q q apply\n\
+                         5 -> x { x 1 + } run")
+    .unwrap();
+    assert_eq!(stack.pop_as::<f64>().unwrap(), 6.0);
+
+    let mut stack = run("def gen = -> q { q apply } generator\n\
+                         3 -> x { x yield } gen collect")
+    .unwrap();
+    assert_eq!(stack.pop_as::<Vec<f64>>().unwrap(), vec![3.0]);
+}
+
+#[test]
+fn test_quote_locals_stay_inside() {
+    let mut stack = run("1 -> x { 2 -> x } apply x").unwrap();
+    assert_eq!(stack.pop_as::<f64>().unwrap(), 1.0);
+
+    let result = run("def run = -> q q apply y\n\
+                      { 2 -> y } run");
+    assert!(matches!(result, Err(Error::UnknownSymbol(s)) if s == "y"));
+}
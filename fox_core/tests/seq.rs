@@ -0,0 +1,70 @@
+use std::cell::Cell;
+use std::rc::Rc;
+
+mod common;
+
+use common::{eval, run};
+use fox_core::engine::{engine::Engine, function::Function};
+
+#[test]
+fn test_seq_combinators() {
+    let mut stack = run(
+        "1 Seq.count-from { 2 * } map { 10 > } filter 2 take collect \
+                         1 5 range 3 Seq.drop [10, 20] zip collect \
+                         [1, 2, 3, 4, 5] 2 windows { sum } map collect \
+                         \"abc\" enumerate { ._0 } map collect \
+                         1 4 range { 0 swap range } flat-map 1 chunks count",
+    )
+    .unwrap();
+
+    assert_eq!(stack.pop_as::<i64>().unwrap(), 6);
+    assert_eq!(stack.pop_as::<Vec<i64>>().unwrap(), vec![0, 1, 2]);
+    assert_eq!(stack.pop_as::<Vec<i64>>().unwrap(), vec![3, 5, 7, 9]);
+    assert_eq!(stack.pop_as::<Vec<(i64, i64)>>().unwrap(), vec![(4, 10)]);
+    assert_eq!(stack.pop_as::<Vec<i64>>().unwrap(), vec![12, 14]);
+}
+
+#[test]
+fn test_seq_terminals() {
+    let mut stack = run("1 5 range product \
+                         [1, 2] [3] chain sum \
+                         1 { dup 4 < then { dup 1 + Tuple2 Some } else { drop None } } Seq.unfold \
+                         0 \\+ fold \
+                         Table.empty 1 \"a\" Table.set seq count")
+    .unwrap();
+
+    assert_eq!(stack.pop_as::<i64>().unwrap(), 1);
+    assert_eq!(stack.pop_as::<i64>().unwrap(), 6);
+    assert_eq!(stack.pop_as::<i64>().unwrap(), 6);
+    assert_eq!(stack.pop_as::<i64>().unwrap(), 24);
+
+    assert!(run("[] \\+ reduce").is_err());
+}
+
+#[test]
+fn test_lazy_list_combinators() {
+    let calls = Rc::new(Cell::new(0));
+    let mut engine = Engine::builder().build();
+    let c = calls.clone();
+    engine.definitions.set(
+        "tick".to_string(),
+        Function::native("tick", move |_| {
+            c.set(c.get() + 1);
+            Ok(())
+        }),
+    );
+    eval(
+        &mut engine,
+        "[1, 2, 3, 4] { tick 2 * } map { tick 2 > } filter",
+    )
+    .unwrap();
+    assert_eq!(calls.get(), 0);
+
+    eval(&mut engine, "2 take collect").unwrap();
+    assert_eq!(calls.get(), 6);
+    assert_eq!(engine.stack.pop_as::<Vec<i64>>().unwrap(), vec![4, 6]);
+
+    eval(&mut engine, "[1, 2, 3] { tick } map List.len").unwrap();
+    assert_eq!(calls.get(), 9);
+    assert_eq!(engine.stack.pop_as::<i64>().unwrap(), 3);
+}
@@ -0,0 +1,60 @@
+use fox_core::engine::engine::Engine;
+use fox_core::engine::typing::Signatures;
+use fox_core::parsing;
+
+fn signatures(source: &str) -> Vec<(String, Option<String>)> {
+    let ast = parsing::parse(source).unwrap();
+    let natives = Engine::builder().build().definitions;
+    let mut signatures = Signatures::new(&ast, |name| natives.get(name)?.signature().cloned());
+
+    ["square", "pair", "area", "quoted", "loop", "unknown"]
+        .into_iter()
+        .map(|name| {
+            (
+                name.to_string(),
+                signatures.get(name).map(|e| e.to_string()),
+            )
+        })
+        .filter(|(name, _)| source.contains(&format!("def {name}")))
+        .collect()
+}
+
+#[test]
+fn test_infer() {
+    let source = "
+def square = dup *
+def pair = -> a, b b a swap
+def quoted = { 1 + } swap
+def loop = loop
+def unknown = 1 List.empty
+";
+    assert_eq!(
+        signatures(source),
+        vec![
+            ("square".to_string(), Some("(a -> a)".to_string())),
+            ("pair".to_string(), Some("(a b -> b a)".to_string())),
+            (
+                "quoted".to_string(),
+                Some("(a -> (Int -> Int) a)".to_string())
+            ),
+            ("loop".to_string(), None),
+            ("unknown".to_string(), None),
+        ]
+    );
+}
+
+#[test]
+fn test_declared_and_constructors() {
+    let source = "
+type Shape = Circle Float | Rect Float Float
+def area (Shape -> Float) = drop 1.0
+def square = 2 Circle
+";
+    assert_eq!(
+        signatures(source),
+        vec![
+            ("square".to_string(), None),
+            ("area".to_string(), Some("(Shape -> Float)".to_string())),
+        ]
+    );
+}
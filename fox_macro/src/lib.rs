@@ -1 +1,378 @@
+use proc_macro::TokenStream;
+use proc_macro2::Span;
+use quote::{format_ident, quote};
+use syn::{
+    parse_macro_input, Attribute, Data, DeriveInput, Fields, FnArg, Ident, ItemFn, LitStr, Path,
+    ReturnType,
+};
 
+/// Path of `fox_core` in the generated code, reached through the `fox` crate unless
+/// overridden with `crate = "path"`
+fn core_path(path: Option<Path>) -> proc_macro2::TokenStream {
+    match path {
+        Some(path) => quote! { #path },
+        None => quote! { ::fox::__core },
+    }
+}
+
+/// The `crate = "path"` override of a `#[fox(...)]` attribute
+fn crate_attr(attrs: &[Attribute]) -> syn::Result<Option<Path>> {
+    let mut path = None;
+    for attr in attrs.iter().filter(|a| a.path().is_ident("fox")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("crate") {
+                path = Some(meta.value()?.parse::<LitStr>()?.parse()?);
+                Ok(())
+            } else {
+                Err(meta.error("unsupported fox attribute, expected `crate`"))
+            }
+        })?;
+    }
+    Ok(path)
+}
+
+/// Expose a Rust function as a Fox word.
+///
+/// Next to the function, a module of the same name is generated with:
+/// - `NAME`, the name of the word, the function name with `_` replaced by `-` unless
+///   overridden with `#[fox_fn(name = "...")]`
+/// - `signature()`, the stack effect of the word
+/// - `word()`, the native function popping the arguments, the last one being on top
+///   of the stack, and pushing the result
+/// - `register(definitions)`, defining the word
+///
+/// Crates using `fox_core` without `fox` set its path with `#[fox_fn(crate = "fox_core")]`.
+#[proc_macro_attribute]
+pub fn fox_fn(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let mut name: Option<LitStr> = None;
+    let mut path: Option<Path> = None;
+    let attr_parser = syn::meta::parser(|meta| {
+        if meta.path.is_ident("name") {
+            name = Some(meta.value()?.parse()?);
+            Ok(())
+        } else if meta.path.is_ident("crate") {
+            path = Some(meta.value()?.parse::<LitStr>()?.parse()?);
+            Ok(())
+        } else {
+            Err(meta.error("unsupported fox_fn attribute, expected `name` or `crate`"))
+        }
+    });
+    parse_macro_input!(attr with attr_parser);
+
+    let func = parse_macro_input!(item as ItemFn);
+    match expand_fox_fn(name, core_path(path), &func) {
+        Ok(tokens) => tokens.into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+fn expand_fox_fn(
+    name: Option<LitStr>,
+    core: proc_macro2::TokenStream,
+    func: &ItemFn,
+) -> syn::Result<proc_macro2::TokenStream> {
+    let vis = &func.vis;
+    let ident = &func.sig.ident;
+    let word = name
+        .unwrap_or_else(|| LitStr::new(&ident.to_string().replace('_', "-"), Span::call_site()));
+
+    let mut arg_types = vec![];
+    for input in &func.sig.inputs {
+        match input {
+            FnArg::Typed(arg) => arg_types.push(arg.ty.as_ref().clone()),
+            FnArg::Receiver(r) => {
+                return Err(syn::Error::new_spanned(
+                    r,
+                    "fox_fn can't be used on methods",
+                ))
+            }
+        }
+    }
+    let arg_names: Vec<Ident> = (0..arg_types.len())
+        .map(|i| format_ident!("__fox_arg{}", i))
+        .collect();
+    let arity = arg_types.len();
+
+    let ret = match &func.sig.output {
+        ReturnType::Default => quote! { () },
+        ReturnType::Type(_, ty) => quote! { #ty },
+    };
+
+    Ok(quote! {
+        #func
+
+        #vis mod #ident {
+            #[allow(unused_imports)]
+            use super::*;
+
+            pub const NAME: &str = #word;
+
+            pub fn signature() -> #core::engine::typing::StackEffect {
+                #core::engine::typing::StackEffect::new(
+                    vec![#(<#arg_types as #core::engine::typing::Typed>::fox_type()),*],
+                    <#ret as #core::engine::typing::ReturnTypes>::fox_types(),
+                )
+            }
+
+            pub fn word() -> #core::engine::function::Function {
+                #core::engine::function::Function::native(NAME, |ctx| {
+                    let stack = ctx.stack();
+                    #[allow(unused_mut, unused_variables)]
+                    let mut args = stack.pop_n(#arity)?.into_iter();
+                    #(
+                        let #arg_names = <#arg_types as #core::engine::convert::FromFox>::from_fox(
+                            args.next().unwrap(),
+                        )?;
+                    )*
+                    #core::engine::convert::IntoReturn::push_to(super::#ident(#(#arg_names),*), stack)
+                })
+                .with_signature(signature())
+            }
+
+            pub fn register(
+                definitions: &mut #core::engine::env::Env<#core::engine::function::Function>,
+            ) {
+                definitions.set(NAME.to_string(), word());
+            }
+        }
+    })
+}
+
+/// Implement `NativeObject` using `Debug`, `Clone` and `PartialEq`, along with `ToFox`, `FromFox` and
+/// `Typed` so the type can be passed to and returned from words.
+///
+/// Crates using `fox_core` without `fox` set its path with `#[fox(crate = "fox_core")]`.
+#[proc_macro_derive(NativeObject, attributes(fox))]
+pub fn derive_native_object(item: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(item as DeriveInput);
+    let expanded = crate_attr(&input.attrs)
+        .and_then(|path| expand_native_object(&input, &core_path(path), quote! {}));
+    match expanded {
+        Ok(tokens) => tokens.into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+/// Everything `#[derive(NativeObject)]` does, with fields exposed as `.field` accessors,
+/// `match` support and one constructor word per enum variant, or one named after a struct.
+///
+/// Tuple fields are named `_0`, `_1`, ... and all fields must implement `ToFox`, `FromFox`,
+/// `Clone` and `PartialEq`.
+#[proc_macro_derive(FoxType, attributes(fox))]
+pub fn derive_fox_type(item: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(item as DeriveInput);
+    match expand_fox_type(&input) {
+        Ok(tokens) => tokens.into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+fn expand_native_object(
+    input: &DeriveInput,
+    core: &proc_macro2::TokenStream,
+    hooks: proc_macro2::TokenStream,
+) -> syn::Result<proc_macro2::TokenStream> {
+    if !input.generics.params.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &input.generics,
+            "generic types can't be Fox values",
+        ));
+    }
+    let ident = &input.ident;
+    let name = ident.to_string();
+
+    Ok(quote! {
+        impl #core::engine::value::NativeObject for #ident {
+            fn repr(&self) -> String {
+                format!("{:?}", self)
+            }
+
+            fn cloned(&self) -> Box<dyn #core::engine::value::NativeObject> {
+                Box::new(::std::clone::Clone::clone(self))
+            }
+
+            fn eq(&self, other: &dyn #core::engine::value::NativeObject) -> bool {
+                use #core::engine::value::AsAny as _;
+                other
+                    .as_any()
+                    .downcast_ref::<Self>()
+                    .is_some_and(|other| ::std::cmp::PartialEq::eq(self, other))
+            }
+
+            #hooks
+        }
+
+        impl #core::engine::convert::ToFox for #ident {
+            fn to_fox(self) -> #core::engine::value::Value {
+                #core::engine::value::ValueData::Native(Box::new(self)).into()
+            }
+        }
+
+        impl #core::engine::convert::FromFox for #ident {
+            fn from_fox(
+                value: #core::engine::value::Value,
+            ) -> Result<Self, #core::engine::error::Error> {
+                value.as_native_typed::<Self>().cloned()
+            }
+        }
+
+        impl #core::engine::typing::Typed for #ident {
+            fn fox_type() -> #core::engine::typing::Type {
+                #core::engine::typing::Type::named(#name)
+            }
+        }
+    })
+}
+
+/// A struct or an enum variant, with the names of its fields
+struct Shape {
+    name: String,
+    /// Path building the value, `Self` or `Self::Variant`
+    path: proc_macro2::TokenStream,
+    fields: Vec<String>,
+    /// Identifiers bound when destructuring
+    bindings: Vec<Ident>,
+    kind: FieldsKind,
+}
+
+#[derive(Clone, Copy)]
+enum FieldsKind {
+    Named,
+    Tuple,
+    Unit,
+}
+
+impl Shape {
+    fn new(name: &Ident, path: proc_macro2::TokenStream, fields: &Fields) -> Self {
+        let (fields, bindings, kind): (Vec<String>, Vec<Ident>, _) = match fields {
+            Fields::Named(named) => {
+                let idents: Vec<Ident> = named
+                    .named
+                    .iter()
+                    .map(|f| f.ident.clone().unwrap())
+                    .collect();
+                let names = idents.iter().map(Ident::to_string).collect();
+                (names, idents, FieldsKind::Named)
+            }
+            Fields::Unnamed(unnamed) => {
+                let count = unnamed.unnamed.len();
+                let names = (0..count).map(|i| format!("_{i}")).collect();
+                let idents = (0..count)
+                    .map(|i| format_ident!("__fox_field{}", i))
+                    .collect();
+                (names, idents, FieldsKind::Tuple)
+            }
+            Fields::Unit => (vec![], vec![], FieldsKind::Unit),
+        };
+
+        Shape {
+            name: name.to_string(),
+            path,
+            fields,
+            bindings,
+            kind,
+        }
+    }
+
+    /// Pattern destructuring the value, binding every field by reference
+    fn pattern(&self) -> proc_macro2::TokenStream {
+        let path = &self.path;
+        let bindings = &self.bindings;
+        match self.kind {
+            FieldsKind::Named => quote! { #path { #(#bindings),* } },
+            FieldsKind::Tuple => quote! { #path ( #(#bindings),* ) },
+            FieldsKind::Unit => quote! { #path },
+        }
+    }
+
+    /// Expression building the value from the arguments popped by the constructor
+    fn build(&self, core: &proc_macro2::TokenStream) -> proc_macro2::TokenStream {
+        let path = &self.path;
+        let bindings = &self.bindings;
+        let values = self.bindings.iter().map(|_| {
+            quote! { #core::engine::convert::FromFox::from_fox(args.next().unwrap())? }
+        });
+        match self.kind {
+            FieldsKind::Named => quote! { #path { #(#bindings: #values),* } },
+            FieldsKind::Tuple => quote! { #path ( #(#values),* ) },
+            FieldsKind::Unit => quote! { #path },
+        }
+    }
+}
+
+fn expand_fox_type(input: &DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let core = core_path(crate_attr(&input.attrs)?);
+    let ident = &input.ident;
+    let shapes: Vec<Shape> = match &input.data {
+        Data::Struct(data) => vec![Shape::new(ident, quote! { Self }, &data.fields)],
+        Data::Enum(data) => data
+            .variants
+            .iter()
+            .map(|v| {
+                let variant = &v.ident;
+                Shape::new(variant, quote! { Self::#variant }, &v.fields)
+            })
+            .collect(),
+        Data::Union(data) => {
+            return Err(syn::Error::new_spanned(
+                data.union_token,
+                "unions can't be Fox types",
+            ))
+        }
+    };
+
+    let patterns: Vec<_> = shapes.iter().map(Shape::pattern).collect();
+    let names: Vec<&String> = shapes.iter().map(|s| &s.name).collect();
+    let bindings: Vec<&Vec<Ident>> = shapes.iter().map(|s| &s.bindings).collect();
+    let fields: Vec<&Vec<String>> = shapes.iter().map(|s| &s.fields).collect();
+
+    let hooks = quote! {
+        #[allow(unused_variables)]
+        fn variant(&self) -> Option<(&str, Vec<#core::engine::value::Value>)> {
+            match self {
+                #(#patterns => Some((
+                    #names,
+                    vec![#(#core::engine::convert::ToFox::to_fox(
+                        ::std::clone::Clone::clone(#bindings),
+                    )),*],
+                )),)*
+            }
+        }
+
+        #[allow(unused_variables)]
+        fn field(&self, name: &str) -> Option<#core::engine::value::Value> {
+            match self {
+                #(#patterns => match name {
+                    #(#fields => Some(#core::engine::convert::ToFox::to_fox(
+                        ::std::clone::Clone::clone(#bindings),
+                    )),)*
+                    _ => None,
+                },)*
+            }
+        }
+    };
+
+    let native_object = expand_native_object(input, &core, hooks)?;
+
+    let arities = shapes.iter().map(|s| s.fields.len());
+    let builds = shapes.iter().map(|s| s.build(&core));
+
+    Ok(quote! {
+        #native_object
+
+        impl #core::engine::adt::FoxType for #ident {
+            fn constructors() -> Vec<#core::engine::function::Function> {
+                vec![#(
+                    #core::engine::function::Function::native(#names, |ctx| {
+                        let stack = ctx.stack();
+                        #[allow(unused_mut, unused_variables)]
+                        let mut args = stack.pop_n(#arities)?.into_iter();
+                        let value: Self = #builds;
+                        stack.push(#core::engine::convert::ToFox::to_fox(value));
+                        Ok(())
+                    })
+                ),*]
+            }
+        }
+    })
+}